@@ -1,10 +1,23 @@
 
 export async function get_pubkey() {
-    return (await window.nostr.getPublicKey()).toString();
+    try {
+        return (await window.nostr.getPublicKey()).toString();
+    } catch (e) {
+        console.log(e);
+        return null;
+    }
 }
 export async function encrypt_content(pubkey, content) {
     return (await window.nostr.nip04.encrypt(pubkey, content)).toString();
 }
+export async function decrypt_content(pubkey, content) {
+    try {
+        return (await window.nostr.nip04.decrypt(pubkey, content)).toString();
+    } catch (e) {
+        console.log(e);
+        return null;
+    }
+}
 export async function sign_event(created_at, content, pubkey) {
 
     console.log(created_at);
@@ -24,3 +37,272 @@ const event = {
 
     return JSON.stringify(e);
 }
+
+const STORE_DB = "cashcrab";
+const STORE_KEY_ID = "storage_key";
+const WRAPPED_KEY_ID = "wrapped_storage_key";
+const STAGED_KEY_ID = "staged_storage_key";
+const PIN_ITERATIONS = 600000;
+
+// Storage key unwrapped with the PIN for this session
+let session_key = null;
+// Key a PIN change moves the storage to, used for every write until it's committed
+let staged_key = null;
+
+function open_store_db() {
+    return new Promise((resolve, reject) => {
+        const request = indexedDB.open(STORE_DB, 1);
+        request.onupgradeneeded = () => request.result.createObjectStore("keys");
+        request.onsuccess = () => resolve(request.result);
+        request.onerror = () => reject(request.error);
+    });
+}
+
+async function store_get(id) {
+    const db = await open_store_db();
+    return new Promise((resolve, reject) => {
+        const request = db.transaction("keys").objectStore("keys").get(id);
+        request.onsuccess = () => resolve(request.result);
+        request.onerror = () => reject(request.error);
+    });
+}
+
+async function store_put(id, value) {
+    const db = await open_store_db();
+    return new Promise((resolve, reject) => {
+        const request = db.transaction("keys", "readwrite").objectStore("keys").put(value, id);
+        request.onsuccess = () => resolve();
+        request.onerror = () => reject(request.error);
+    });
+}
+
+async function store_delete(id) {
+    const db = await open_store_db();
+    return new Promise((resolve, reject) => {
+        const request = db.transaction("keys", "readwrite").objectStore("keys").delete(id);
+        request.onsuccess = () => resolve();
+        request.onerror = () => reject(request.error);
+    });
+}
+
+// AES-GCM key wrapping the storage key, derived from the PIN
+async function pin_key(pin, salt) {
+    const key = await crypto.subtle.importKey("raw", new TextEncoder().encode(pin), "PBKDF2", false, ["deriveKey"]);
+    return crypto.subtle.deriveKey({ name: "PBKDF2", hash: "SHA-256", salt: salt, iterations: PIN_ITERATIONS }, key, { name: "AES-GCM", length: 256 }, false, ["encrypt", "decrypt"]);
+}
+
+// Non extractable AES-GCM key kept in IndexedDB so it never appears in LocalStorage,
+// or the PIN protected key once it has been unlocked
+async function storage_key() {
+    if (session_key) {
+        return session_key;
+    }
+    if (await store_get(WRAPPED_KEY_ID)) {
+        throw new Error("Storage is locked");
+    }
+
+    const stored = await store_get(STORE_KEY_ID);
+    if (stored) {
+        return stored;
+    }
+
+    const key = await crypto.subtle.generateKey({ name: "AES-GCM", length: 256 }, false, ["encrypt", "decrypt"]);
+    await store_put(STORE_KEY_ID, key);
+    return key;
+}
+
+// Whether the storage key needs the PIN before anything can be decrypted
+export async function storage_locked() {
+    return session_key === null && (await store_get(WRAPPED_KEY_ID)) !== undefined;
+}
+
+export async function unlock_storage(pin) {
+    try {
+        const wrapped = await store_get(WRAPPED_KEY_ID);
+        const raw = await crypto.subtle.decrypt({ name: "AES-GCM", iv: wrapped.iv }, await pin_key(pin, wrapped.salt), wrapped.key);
+        session_key = await crypto.subtle.importKey("raw", raw, "AES-GCM", false, ["encrypt", "decrypt"]);
+        return true;
+    } catch (e) {
+        console.log(e);
+        return false;
+    }
+}
+
+// New storage key, kept sealed with the current one until every entry has been
+// rewritten so an interrupted PIN change can be finished with the old PIN
+async function staged_storage_key() {
+    if (staged_key) {
+        return staged_key;
+    }
+
+    const staged = await store_get(STAGED_KEY_ID);
+    if (!staged) {
+        return null;
+    }
+
+    const raw = await crypto.subtle.decrypt({ name: "AES-GCM", iv: staged.sealed_iv }, await storage_key(), staged.sealed);
+    staged_key = await crypto.subtle.importKey("raw", raw, "AES-GCM", false, ["encrypt", "decrypt"]);
+    return staged_key;
+}
+
+export async function storage_key_staged() {
+    return (await store_get(STAGED_KEY_ID)) !== undefined;
+}
+
+// Stages a new storage key wrapped by the PIN, or a device key when the PIN is
+// null. Every entry must be rewritten before it's committed.
+export async function stage_storage_pin(pin) {
+    try {
+        const raw = crypto.getRandomValues(new Uint8Array(32));
+        const sealed_iv = crypto.getRandomValues(new Uint8Array(12));
+        const sealed = await crypto.subtle.encrypt({ name: "AES-GCM", iv: sealed_iv }, await storage_key(), raw);
+
+        let stored;
+        if (pin == null) {
+            stored = await crypto.subtle.importKey("raw", raw, "AES-GCM", false, ["encrypt", "decrypt"]);
+        } else {
+            const salt = crypto.getRandomValues(new Uint8Array(16));
+            const iv = crypto.getRandomValues(new Uint8Array(12));
+            stored = { salt: salt, iv: iv, key: await crypto.subtle.encrypt({ name: "AES-GCM", iv: iv }, await pin_key(pin, salt), raw) };
+        }
+
+        await store_put(STAGED_KEY_ID, { pin: pin != null, stored: stored, sealed: sealed, sealed_iv: sealed_iv });
+        staged_key = await crypto.subtle.importKey("raw", raw, "AES-GCM", false, ["encrypt", "decrypt"]);
+        return true;
+    } catch (e) {
+        console.log(e);
+        return false;
+    }
+}
+
+// Replaces the storage key with the staged one and deletes the old key
+export async function commit_storage_pin() {
+    try {
+        const staged = await store_get(STAGED_KEY_ID);
+        const key = await staged_storage_key();
+        const db = await open_store_db();
+
+        // One transaction so the old key is only gone once the new one is in place
+        await new Promise((resolve, reject) => {
+            const transaction = db.transaction("keys", "readwrite");
+            const store = transaction.objectStore("keys");
+            if (staged.pin) {
+                store.put(staged.stored, WRAPPED_KEY_ID);
+                store.delete(STORE_KEY_ID);
+            } else {
+                store.put(staged.stored, STORE_KEY_ID);
+                store.delete(WRAPPED_KEY_ID);
+            }
+            store.delete(STAGED_KEY_ID);
+            transaction.oncomplete = () => resolve();
+            transaction.onerror = () => reject(transaction.error);
+        });
+
+        session_key = key;
+        staged_key = null;
+        return true;
+    } catch (e) {
+        console.log(e);
+        return false;
+    }
+}
+
+export async function encrypt_local(content) {
+    try {
+        const iv = crypto.getRandomValues(new Uint8Array(12));
+        const key = (await staged_storage_key()) || (await storage_key());
+        const encrypted = await crypto.subtle.encrypt({ name: "AES-GCM", iv: iv }, key, new TextEncoder().encode(content));
+
+        const bytes = new Uint8Array(iv.length + encrypted.byteLength);
+        bytes.set(iv);
+        bytes.set(new Uint8Array(encrypted), iv.length);
+        let binary = "";
+        for (const byte of bytes) {
+            binary += String.fromCharCode(byte);
+        }
+        return btoa(binary);
+    } catch (e) {
+        console.log(e);
+        return null;
+    }
+}
+
+export async function decrypt_local(content) {
+    try {
+        const bytes = Uint8Array.from(atob(content), c => c.charCodeAt(0));
+        const params = { name: "AES-GCM", iv: bytes.slice(0, 12) };
+        let decrypted;
+        try {
+            decrypted = await crypto.subtle.decrypt(params, await storage_key(), bytes.slice(12));
+        } catch (e) {
+            // Already rewritten by a PIN change in progress
+            const staged = await staged_storage_key();
+            if (!staged) {
+                throw e;
+            }
+            decrypted = await crypto.subtle.decrypt(params, staged, bytes.slice(12));
+        }
+        return new TextDecoder().decode(decrypted);
+    } catch (e) {
+        console.log(e);
+        return null;
+    }
+}
+
+// Sends raw ESC/POS bytes to a printer over WebSerial or WebUSB
+export async function print_escpos(bytes) {
+    try {
+        if ("serial" in navigator) {
+            const ports = await navigator.serial.getPorts();
+            const port = ports.length > 0 ? ports[0] : await navigator.serial.requestPort();
+            await port.open({ baudRate: 9600 });
+            const writer = port.writable.getWriter();
+            await writer.write(bytes);
+            writer.releaseLock();
+            await port.close();
+            return true;
+        }
+
+        if ("usb" in navigator) {
+            const devices = await navigator.usb.getDevices();
+            const device = devices.length > 0 ? devices[0] : await navigator.usb.requestDevice({ filters: [] });
+            await device.open();
+            if (device.configuration === null) {
+                await device.selectConfiguration(1);
+            }
+            const iface = device.configuration.interfaces[0];
+            await device.claimInterface(iface.interfaceNumber);
+            const endpoint = iface.alternate.endpoints.find(e => e.direction === "out");
+            await device.transferOut(endpoint.endpointNumber, bytes);
+            await device.close();
+            return true;
+        }
+    } catch (e) {
+        console.log(e);
+    }
+
+    return false;
+}
+
+export function download(filename, content, mime) {
+    const url = URL.createObjectURL(new Blob([content], { type: mime }));
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = filename;
+    link.click();
+    // Some browsers start the download after click returns
+    setTimeout(() => URL.revokeObjectURL(url), 1000);
+}
+
+// PBKDF2-HMAC-SHA256 of a PIN, returned as hex
+export async function pbkdf2(pin, salt_hex, iterations) {
+    try {
+        const salt = Uint8Array.from(salt_hex.match(/../g), h => parseInt(h, 16));
+        const key = await crypto.subtle.importKey("raw", new TextEncoder().encode(pin), "PBKDF2", false, ["deriveBits"]);
+        const bits = await crypto.subtle.deriveBits({ name: "PBKDF2", hash: "SHA-256", salt: salt, iterations: iterations }, key, 256);
+        return Array.from(new Uint8Array(bits), b => b.toString(16).padStart(2, "0")).join("");
+    } catch (e) {
+        console.log(e);
+        return null;
+    }
+}
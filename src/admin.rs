@@ -0,0 +1,381 @@
+//! Admin PIN and cashier accounts guarding the settings
+//!
+//! Only PBKDF2 hashes of PINs are stored. Failed attempts lock the PIN
+//! entry for increasing periods, persisted so a reload does not reset them.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::bindings;
+
+/// PBKDF2-HMAC-SHA256 iterations for new PINs
+const PIN_ITERATIONS: u32 = 600_000;
+
+/// Failed attempts allowed before the PIN entry is locked
+const MAX_ATTEMPTS: u32 = 5;
+
+/// First lockout, doubled for every further failure
+const LOCKOUT_SECS: u64 = 60;
+
+/// Inactivity after which the settings lock again
+pub const AUTO_LOCK_SECS: u64 = 120;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinHash {
+    /// Hex encoded random salt
+    salt: String,
+    /// Hex encoded derived key
+    hash: String,
+    iterations: u32,
+}
+
+async fn derive(pin: &str, salt: &str, iterations: u32) -> Result<String> {
+    bindings::pbkdf2(pin.to_string(), salt.to_string(), iterations)
+        .await
+        .as_string()
+        .ok_or(anyhow!("Could not derive PIN hash"))
+}
+
+impl PinHash {
+    pub async fn new(pin: &str) -> Result<Self> {
+        let mut salt = [0u8; 16];
+        getrandom::getrandom(&mut salt)?;
+        let salt = hex::encode(salt);
+
+        Ok(Self {
+            hash: derive(pin, &salt, PIN_ITERATIONS).await?,
+            salt,
+            iterations: PIN_ITERATIONS,
+        })
+    }
+
+    pub async fn verify(&self, pin: &str) -> Result<bool> {
+        let hash = derive(pin, &self.salt, self.iterations).await?;
+
+        // Compare every byte so the time taken doesn't leak the match length
+        Ok(hash.len() == self.hash.len()
+            && hash
+                .bytes()
+                .zip(self.hash.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    /// Takes payments
+    Cashier,
+    /// Also issues refunds, changes settings and reads reports
+    Manager,
+}
+
+impl Role {
+    pub const ALL: [Role; 2] = [Role::Cashier, Role::Manager];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Cashier => "Cashier",
+            Role::Manager => "Manager",
+        }
+    }
+}
+
+/// Settings unlocked with the admin PIN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdminSession {
+    /// Unix time the settings lock again
+    until: u64,
+}
+
+impl AdminSession {
+    pub fn start(now: u64) -> Self {
+        Self {
+            until: now + AUTO_LOCK_SECS,
+        }
+    }
+
+    /// Keeps the settings unlocked after input from the user
+    pub fn touch(&mut self, now: u64) {
+        self.until = now + AUTO_LOCK_SECS;
+    }
+
+    pub fn is_active(&self, now: u64) -> bool {
+        now < self.until
+    }
+}
+
+/// Staff member signing in to the terminal with their own PIN
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cashier {
+    pub name: String,
+    pub role: Role,
+    pub pin: PinHash,
+}
+
+/// Whether someone can still open the settings with these accounts
+///
+/// Once any account exists the settings need a manager's PIN or the admin
+/// PIN, so cashiers are only allowed alongside one of them.
+fn can_unlock(cashiers: &[Cashier], admin_pin: bool) -> bool {
+    admin_pin || cashiers.is_empty() || cashiers.iter().any(|c| c.role == Role::Manager)
+}
+
+/// Checks an account with `role` can be added
+pub fn check_add(cashiers: &[Cashier], role: Role, admin_pin: bool) -> Result<()> {
+    let manager = role == Role::Manager || cashiers.iter().any(|c| c.role == Role::Manager);
+
+    if !admin_pin && !manager {
+        bail!("Add a manager or set an admin PIN before adding cashiers");
+    }
+
+    Ok(())
+}
+
+/// Checks the account `name` can be removed
+pub fn check_remove(cashiers: &[Cashier], name: &str, admin_pin: bool) -> Result<()> {
+    let remaining: Vec<Cashier> = cashiers
+        .iter()
+        .filter(|c| c.name != name)
+        .cloned()
+        .collect();
+
+    if !can_unlock(&remaining, admin_pin) {
+        bail!("Can't remove the last manager while cashiers remain and no admin PIN is set");
+    }
+
+    Ok(())
+}
+
+/// Checks the admin PIN can be removed
+pub fn check_remove_pin(cashiers: &[Cashier]) -> Result<()> {
+    if !can_unlock(cashiers, false) {
+        bail!("Add a manager before removing the admin PIN");
+    }
+
+    Ok(())
+}
+
+/// Failed PIN attempts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinLockout {
+    failures: u32,
+    locked_until: u64,
+}
+
+impl PinLockout {
+    /// Seconds until the PIN can be tried again
+    pub fn locked_for(&self, now: u64) -> Option<u64> {
+        (self.locked_until > now).then(|| self.locked_until - now)
+    }
+
+    pub fn failed(&mut self, now: u64) {
+        self.failures += 1;
+
+        if self.failures >= MAX_ATTEMPTS {
+            let doublings = (self.failures - MAX_ATTEMPTS).min(10);
+            self.locked_until = now + (LOCKOUT_SECS << doublings);
+        }
+    }
+}
+
+/// Account of the admin PIN in [`PinLockouts`], no cashier has an empty name
+pub const ADMIN_ACCOUNT: &str = "";
+
+/// Failed PIN attempts of every account so one locked account doesn't lock
+/// out the others
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PinLockouts(HashMap<String, PinLockout>);
+
+impl PinLockouts {
+    /// Lockouts stored by older versions were a single one for every PIN
+    pub fn migrate(lockout: PinLockout) -> Self {
+        Self(HashMap::from([(ADMIN_ACCOUNT.to_string(), lockout)]))
+    }
+
+    /// Seconds until the PIN of `account` can be tried again
+    pub fn locked_for(&self, account: &str, now: u64) -> Option<u64> {
+        self.0.get(account).and_then(|l| l.locked_for(now))
+    }
+
+    /// Seconds every locked account has to wait
+    pub fn locked(&self, now: u64) -> HashMap<String, u64> {
+        self.0
+            .iter()
+            .filter_map(|(account, l)| Some((account.clone(), l.locked_for(now)?)))
+            .collect()
+    }
+
+    /// Seconds until one of `accounts` can be tried again, `None` if one
+    /// can be tried now or there are none
+    pub fn all_locked_for(&self, accounts: &[String], now: u64) -> Option<u64> {
+        accounts
+            .iter()
+            .map(|account| self.locked_for(account, now))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min()
+    }
+
+    pub fn failed(&mut self, account: &str, now: u64) {
+        self.0.entry(account.to_string()).or_default().failed(now);
+    }
+
+    pub fn reset(&mut self, account: &str) {
+        self.0.remove(account);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(name: &str, role: Role) -> Cashier {
+        Cashier {
+            name: name.to_string(),
+            role,
+            pin: PinHash {
+                salt: String::new(),
+                hash: String::new(),
+                iterations: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn lockout_after_max_attempts() {
+        let mut lockout = PinLockout::default();
+
+        for _ in 1..MAX_ATTEMPTS {
+            lockout.failed(1000);
+        }
+        assert_eq!(lockout.locked_for(1000), None);
+
+        lockout.failed(1000);
+        assert_eq!(lockout.locked_for(1000), Some(LOCKOUT_SECS));
+        assert_eq!(lockout.locked_for(1000 + LOCKOUT_SECS), None);
+
+        // Every further failure doubles the wait
+        lockout.failed(2000);
+        assert_eq!(lockout.locked_for(2000), Some(LOCKOUT_SECS * 2));
+    }
+
+    #[test]
+    fn lockout_doubling_is_capped() {
+        let mut lockout = PinLockout::default();
+
+        for _ in 0..100 {
+            lockout.failed(0);
+        }
+
+        assert_eq!(lockout.locked_for(0), Some(LOCKOUT_SECS << 10));
+    }
+
+    #[test]
+    fn lockouts_per_account() {
+        let mut lockouts = PinLockouts::default();
+
+        for _ in 0..MAX_ATTEMPTS {
+            lockouts.failed("Alice", 1000);
+        }
+
+        assert_eq!(lockouts.locked_for("Alice", 1000), Some(LOCKOUT_SECS));
+        assert_eq!(lockouts.locked_for("Bob", 1000), None);
+        assert_eq!(lockouts.locked_for(ADMIN_ACCOUNT, 1000), None);
+        assert_eq!(
+            lockouts.locked(1000),
+            HashMap::from([("Alice".to_string(), LOCKOUT_SECS)])
+        );
+
+        lockouts.reset("Alice");
+        assert_eq!(lockouts.locked_for("Alice", 1000), None);
+    }
+
+    #[test]
+    fn all_accounts_locked() {
+        let mut lockouts = PinLockouts::default();
+        let accounts = vec![ADMIN_ACCOUNT.to_string(), "Alice".to_string()];
+
+        for _ in 0..MAX_ATTEMPTS {
+            lockouts.failed("Alice", 1000);
+        }
+        assert_eq!(lockouts.all_locked_for(&accounts, 1000), None);
+
+        for _ in 0..MAX_ATTEMPTS {
+            lockouts.failed(ADMIN_ACCOUNT, 1030);
+        }
+        assert_eq!(
+            lockouts.all_locked_for(&accounts, 1030),
+            Some(LOCKOUT_SECS - 30)
+        );
+        assert_eq!(lockouts.all_locked_for(&[], 1030), None);
+    }
+
+    #[test]
+    fn lockouts_migrate_single_lockout() {
+        let old = r#"{"failures":5,"locked_until":1060}"#;
+
+        assert!(serde_json::from_str::<PinLockouts>(old).is_err());
+
+        let lockouts = PinLockouts::migrate(serde_json::from_str(old).unwrap());
+        assert_eq!(lockouts.locked_for(ADMIN_ACCOUNT, 1000), Some(60));
+    }
+
+    #[test]
+    fn cashiers_need_a_manager_or_admin_pin() {
+        let manager = account("Alice", Role::Manager);
+
+        assert!(check_add(&[], Role::Cashier, false).is_err());
+        assert!(check_add(&[], Role::Cashier, true).is_ok());
+        assert!(check_add(&[], Role::Manager, false).is_ok());
+        assert!(check_add(&[manager], Role::Cashier, false).is_ok());
+    }
+
+    #[test]
+    fn last_manager_is_kept() {
+        let accounts = [
+            account("Alice", Role::Manager),
+            account("Bob", Role::Cashier),
+        ];
+
+        assert!(check_remove(&accounts, "Alice", false).is_err());
+        assert!(check_remove(&accounts, "Alice", true).is_ok());
+        assert!(check_remove(&accounts, "Bob", false).is_ok());
+        assert!(check_remove(&accounts[..1], "Alice", false).is_ok());
+
+        let accounts = [accounts[0].clone(), account("Carol", Role::Manager)];
+        assert!(check_remove(&accounts, "Alice", false).is_ok());
+    }
+
+    #[test]
+    fn admin_pin_kept_for_cashiers() {
+        assert!(check_remove_pin(&[account("Bob", Role::Cashier)]).is_err());
+        assert!(check_remove_pin(&[
+            account("Alice", Role::Manager),
+            account("Bob", Role::Cashier)
+        ])
+        .is_ok());
+        assert!(check_remove_pin(&[]).is_ok());
+    }
+
+    #[test]
+    fn session_expires_without_input() {
+        let session = AdminSession::start(1000);
+
+        assert!(session.is_active(1000));
+        assert!(session.is_active(1000 + AUTO_LOCK_SECS - 1));
+        assert!(!session.is_active(1000 + AUTO_LOCK_SECS));
+    }
+
+    #[test]
+    fn input_extends_session() {
+        let mut session = AdminSession::start(1000);
+        session.touch(1100);
+
+        assert!(session.is_active(1000 + AUTO_LOCK_SECS));
+        assert!(!session.is_active(1100 + AUTO_LOCK_SECS));
+    }
+}
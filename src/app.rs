@@ -1,44 +1,117 @@
 use std::time::Duration;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use cashu_crab::{
-    nuts::{nut00::wallet::Token, nut03::RequestMintResponse},
-    wallet::Wallet,
-    Amount, Client as CashuClient, Invoice,
+    nuts::nut00::{wallet::Token, Proofs},
+    Amount,
 };
+use futures::channel::oneshot;
 use gloo::storage::LocalStorage;
 use gloo::storage::Storage;
 use gloo::timers::future::sleep;
 use log::warn;
-use nostr_sdk::{prelude::FromPkStr, Client, Keys};
+use nostr_sdk::{
+    prelude::{FromPkStr, FromSkStr, ToBech32, XOnlyPublicKey},
+    Client, Keys,
+};
 use tokio::sync::Mutex as TokioMutex;
 use url::Url;
 use yew::platform::spawn_local;
 use yew::prelude::*;
 
+use crate::admin::{
+    self, AdminSession, Cashier, PinHash, PinLockout, PinLockouts, Role, ADMIN_ACCOUNT,
+};
+use crate::bindings;
 use crate::components::{
-    invoice::InvoiceView, invoice_paid::InvoicePaid, pos::Pos, set_mint::SetMint,
-    set_rec_key::SetRecKey, set_relays::SetRelays, settings::Settings,
+    backup::Backup,
+    cashiers::Cashiers,
+    config_transfer::ConfigTransfer,
+    dashboard::Dashboard,
+    export::ExportView,
+    invoice::{InvoiceView, QrPayload},
+    invoice_paid::InvoicePaid,
+    pos::{self, Pos},
+    sales::SalesView,
+    sales_sync::SalesSync,
+    set_melt::SetMelt,
+    set_message::SetMessage,
+    set_mint::SetMint,
+    set_pin::SetPin,
+    set_printer::SetPrinter,
+    set_rec_key::SetRecKey,
+    set_relays::SetRelays,
+    set_remote::SetRemote,
+    set_shop::SetShop,
+    set_splits::SetSplits,
+    settings::Settings,
+    shift::ShiftView,
+    sign_in::SignIn,
+    terminal_wallet::TerminalWallet,
+    unlock::Unlock,
+};
+use crate::config_bundle::ConfigBundle;
+use crate::dashboard::{self, FeedItem, Login, MintStatuses, Redemption, Session, Signer};
+use crate::escpos::{self, PrinterSettings};
+use crate::melt::{self, MeltSettings};
+use crate::message::MessageSettings;
+use crate::mint_api::{self, MintQuote};
+use crate::payment_request::{self, PaymentRequest, PaymentRequestPayload, Transport};
+use crate::pending_mint::{self, PendingMint};
+use crate::quote_watch;
+use crate::receipt::Receipt;
+use crate::remote_config::{self, AppliedConfig, RemoteConfig, RemoteUpdate};
+use crate::sale::{self, Refund, Sale, SaleLeg};
+use crate::sale_sync;
+use crate::seed::{self, Seed};
+use crate::shift::{Shift, ZReport};
+use crate::split::{self, SplitRecipient};
+use crate::storage::{
+    self, LocalSettings, StorageCallbacks, AUTO_RETURN_KEY, CLAIMED_TOKENS_KEY, LAST_SWEEP_KEY,
+    MESSAGE_KEY, PIN_LOCKOUT_KEY, PREVIOUS_SEEDS_KEY, PRINTER_KEY, QR_PAYLOAD_KEY,
+    REMOTE_CONFIG_AT_KEY, REMOTE_CONFIG_KEY, SALES_KEY, SEED_BACKED_UP_KEY, SHIFT_KEY,
+    SHOP_NAME_KEY, STORAGE_LOCKOUT_KEY, SWEEP_KEY, SYNCED_SALES_KEY, Z_REPORTS_KEY,
 };
 use crate::utls;
-
-pub const NOSTR_KEY: &str = "nostr_rec";
-pub const MINT_URL_KEY: &str = "mint_url";
-pub const RELAYS_KEY: &str = "relays";
+use crate::wallet::Wallet;
+use crate::wallet_store::{self, StoredConfig, SweepSchedule};
 
 #[derive(Debug, Default, Clone)]
 pub enum View {
+    /// Reading the encrypted store
     #[default]
+    Loading,
+    /// Waiting for the admin PIN to decrypt the store
+    StorageLocked,
     SetMint,
     SetRecKey,
     Pos,
-    Invoice((Invoice, String)),
-    InvoicePaid,
+    Invoice((MintQuote, String, String)),
+    /// Payment hash of the paid sale
+    InvoicePaid(String),
     SetRelays,
+    SetSplits,
+    SetMelt,
+    SetShop,
+    SetMessage,
+    SetPrinter,
+    Shift,
+    Export,
+    Sales,
+    SalesSync,
+    SetPin,
+    Unlock,
+    SignIn,
+    Cashiers,
+    SetRemote,
+    ConfigTransfer,
+    Dashboard,
+    TerminalWallet,
+    Backup,
     Settings,
 }
 
@@ -47,16 +120,186 @@ pub enum Msg {
     MintUrlSet(Url),
     ClientCreated(Client),
     WalletCreated(Wallet),
-    AmountSet((Amount, String)),
-    InvoiceSet((Amount, String, RequestMintResponse)),
-    InvoicePaid((Amount, Token)),
+    AmountSet((Amount, String, Option<String>)),
+    InvoiceSet((Amount, String, Option<String>, MintQuote)),
+    RegenerateInvoice,
+    QrPayloadSet(QrPayload),
+    InvoicePaid((String, Amount, Token)),
+    /// Paid quote minted after the terminal was reloaded
+    MintResumed((String, Amount, Token)),
+    EcashReceived(PaymentRequestPayload),
+    /// Hash, sale amount, amount paid and the swapped token
+    EcashPaid((String, Amount, Amount, Token)),
+    EcashFailed(String),
+    SaleSettled((String, Vec<SaleLeg>, Amount)),
     AddRelay(Url),
     RelaysSet,
+    AddSplit(SplitRecipient),
+    ClearSplits,
+    MeltSettingsSet(Option<MeltSettings>),
+    ShopNameSet(Option<String>),
+    OnchainAddressSet(Option<String>),
+    AutoReturnSet(Option<u32>),
+    Receipt(String),
+    ReceiptCreated((String, String)),
+    Print(String),
+    PrinterSet(Option<PrinterSettings>),
+    ShiftOpened(String),
+    ShiftClosed(bool),
+    RefundRequested((String, Amount)),
+    /// Invoice the merchant pays when the terminal balance can't cover a refund
+    RefundInvoice((String, Amount, MintQuote)),
+    RefundInvoiceCancelled,
+    /// Hash of the sale, the refund and whether its token is stored encrypted
+    Refunded((String, Refund, bool)),
+    RefundFailed(String),
+    RefundTokensLoaded(HashMap<String, String>),
+    PinLockoutsLoaded(PinLockouts),
+    RefundTokensSaved(Vec<String>),
+    SyncKeySet(Option<Keys>),
+    SyncSales,
+    SalesSynced(Vec<Sale>),
+    SyncFailed(String),
+    Unlock(String),
+    /// Accounts whose PIN was tried and the one it matched
+    Unlocked((Vec<String>, Option<String>)),
+    PinSet(Option<String>),
+    PinSaved(Option<PinHash>),
+    AutoLockTick,
+    ConfigLoaded(StoredConfig),
+    StorageLocked,
+    StorageUnlock(String),
+    StorageUnlockFailed,
+    SignIn((String, String)),
+    /// Cashier and whether the PIN was right
+    SignedIn((Cashier, bool)),
+    SignOut,
+    CashierAdded((String, Role, String)),
+    CashierSaved(Cashier),
+    CashierRemoved(String),
+    MerchantSet(Option<Keys>),
+    RemoteConfigReceived(Vec<RemoteUpdate>),
+    /// Updates saved and ready to be applied
+    RemoteConfigSaved(AppliedConfig),
+    ExportConfig,
+    ConfigExported(String),
+    ConfigImported(RemoteConfig),
+    SignerTrusted(XOnlyPublicKey),
+    DashboardLogin((Vec<Url>, Login)),
+    DashboardLoggedIn(XOnlyPublicKey),
+    DashboardFailed(String),
+    FeedItemReceived(FeedItem),
+    Redeem,
+    Redeemed(Redemption),
+    RedeemFailed(String),
+    ClaimedTokensLoaded(Vec<String>),
+    TokenStatusesLoaded(HashMap<String, MintStatuses>),
+    ClaimedTokensCleared,
+    MessageSettingsSet(MessageSettings),
+    SweepScheduleSet(Option<SweepSchedule>),
+    SweepTick,
+    Sweep,
+    BalanceLoaded(Amount),
+    /// The stored proofs could not be read so none will be written
+    WalletLoadFailed(String),
+    SeedLoaded(Seed),
+    SeedBackedUp,
+    Restore(String),
+    Restored(String),
+    PreviousSeedsLoaded(Vec<String>),
     Home,
     Settings,
     AddRelayView,
     SetMintView,
     SetPubkeyView,
+    SetSplitsView,
+    SetMeltView,
+    SetShopView,
+    SetMessageView,
+    SetPrinterView,
+    ShiftView,
+    ExportView,
+    SalesView,
+    SalesSyncView,
+    SetPinView,
+    CashiersView,
+    SetRemoteView,
+    ConfigTransferView,
+    DashboardView,
+    TerminalWalletView,
+    BackupView,
+}
+
+impl Msg {
+    /// Whether the message comes from someone using the terminal, rather than
+    /// from timers, relays or finished requests
+    fn is_user_input(&self) -> bool {
+        matches!(
+            self,
+            Msg::NostrRecKeySet(_)
+                | Msg::MintUrlSet(_)
+                | Msg::AmountSet(_)
+                | Msg::RegenerateInvoice
+                | Msg::QrPayloadSet(_)
+                | Msg::AddRelay(_)
+                | Msg::RelaysSet
+                | Msg::AddSplit(_)
+                | Msg::ClearSplits
+                | Msg::MeltSettingsSet(_)
+                | Msg::ShopNameSet(_)
+                | Msg::OnchainAddressSet(_)
+                | Msg::AutoReturnSet(_)
+                | Msg::Receipt(_)
+                | Msg::Print(_)
+                | Msg::PrinterSet(_)
+                | Msg::ShiftOpened(_)
+                | Msg::ShiftClosed(_)
+                | Msg::RefundRequested(_)
+                | Msg::RefundInvoiceCancelled
+                | Msg::SyncKeySet(_)
+                | Msg::SyncSales
+                | Msg::Unlock(_)
+                | Msg::PinSet(_)
+                | Msg::StorageUnlock(_)
+                | Msg::SignIn(_)
+                | Msg::SignOut
+                | Msg::CashierAdded(_)
+                | Msg::CashierRemoved(_)
+                | Msg::MerchantSet(_)
+                | Msg::ExportConfig
+                | Msg::ConfigImported(_)
+                | Msg::SignerTrusted(_)
+                | Msg::DashboardLogin(_)
+                | Msg::Redeem
+                | Msg::ClaimedTokensCleared
+                | Msg::MessageSettingsSet(_)
+                | Msg::SweepScheduleSet(_)
+                | Msg::Sweep
+                | Msg::SeedBackedUp
+                | Msg::Restore(_)
+                | Msg::Home
+                | Msg::Settings
+                | Msg::AddRelayView
+                | Msg::SetMintView
+                | Msg::SetPubkeyView
+                | Msg::SetSplitsView
+                | Msg::SetMeltView
+                | Msg::SetShopView
+                | Msg::SetMessageView
+                | Msg::SetPrinterView
+                | Msg::ShiftView
+                | Msg::ExportView
+                | Msg::SalesView
+                | Msg::SalesSyncView
+                | Msg::SetPinView
+                | Msg::CashiersView
+                | Msg::SetRemoteView
+                | Msg::ConfigTransferView
+                | Msg::DashboardView
+                | Msg::TerminalWalletView
+                | Msg::BackupView
+        )
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -67,6 +310,85 @@ pub struct App {
     wallet: Arc<Mutex<Option<Wallet>>>,
     nostr_client: Arc<TokioMutex<Option<Client>>>,
     unpaid_invoices: HashSet<String>,
+    /// Stops waiting on the Lightning quote of a sale paid with ecash, by hash
+    quote_watchers: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
+    /// Ecash payments being swapped, the same one arrives from every relay
+    ecash_receiving: HashSet<String>,
+    splits: Vec<SplitRecipient>,
+    sales: Vec<Sale>,
+    /// Key shared by the terminals syncing sales
+    sync_keys: Option<Keys>,
+    /// Sales pulled from the other terminals
+    synced_sales: Vec<Sale>,
+    sync_status: Option<String>,
+    melt_settings: Option<MeltSettings>,
+    sweep_schedule: Option<SweepSchedule>,
+    /// Proofs kept on the terminal until they are melted or swept
+    balance: Arc<TokioMutex<Proofs>>,
+    /// Last known terminal balance for display
+    balance_amount: Option<Amount>,
+    wallet_error: Option<String>,
+    seed: Option<Seed>,
+    seed_backed_up: bool,
+    restore_status: Option<String>,
+    /// Seeds replaced by a restore, kept in case they are still needed
+    previous_seeds: Vec<String>,
+    qr_payload: QrPayload,
+    /// Key payment requests are sent to
+    pos_pubkey: Option<XOnlyPublicKey>,
+    shop_name: Option<String>,
+    /// On-chain address offered in the BIP21 QR
+    onchain_address: Option<String>,
+    message_settings: MessageSettings,
+    /// Seconds the paid screen is shown before returning to the keypad
+    auto_return: Option<u32>,
+    /// Payment hash and signed receipt shown on the paid screen
+    receipt: Option<(String, String)>,
+    printer_settings: Option<PrinterSettings>,
+    /// Currently open shift
+    shift: Option<Shift>,
+    z_reports: Vec<ZReport>,
+    refund_status: Option<String>,
+    /// A refund is being made, only one runs at a time
+    refunding: bool,
+    /// Sale hash and quote of the invoice funding a refund
+    refund_invoice: Option<(String, MintQuote)>,
+    /// Keys of the refund tokens kept in the encrypted store
+    refund_tokens: HashSet<String>,
+    admin_pin: Option<PinHash>,
+    /// Failed attempts of the admin PIN and of every cashier
+    pin_lockouts: PinLockouts,
+    /// Failed attempts at unlocking the storage
+    storage_lockout: PinLockout,
+    /// A PIN is being verified, further attempts wait for it
+    pin_checking: bool,
+    pin_error: Option<String>,
+    /// Unix time the settings lock again, extended on every action
+    admin_session: Option<AdminSession>,
+    cashiers: Vec<Cashier>,
+    /// Cashier signed in to take payments
+    cashier: Option<Cashier>,
+    /// Merchant admin key allowed to push configuration
+    merchant: Option<XOnlyPublicKey>,
+    /// Time the last remote config taken was sent, ahead of the stored one
+    /// while it's being saved
+    remote_config_at: u64,
+    /// Signed config bundle shown for export
+    config_bundle: Option<String>,
+    /// Keys besides the merchant trusted to sign config bundles
+    trusted_signers: Vec<XOnlyPublicKey>,
+    /// Merchant logged in to the dashboard
+    dashboard_merchant: Option<XOnlyPublicKey>,
+    /// Feed of the last dashboard login, closed by the next one
+    dashboard_session: Arc<TokioMutex<Option<Session>>>,
+    /// Tokens received by the merchant, oldest first
+    feed: Vec<FeedItem>,
+    dashboard_error: Option<String>,
+    /// Tokens claimed from the feed, kept until the merchant clears them
+    claimed_tokens: Vec<String>,
+    /// Status of feed tokens redeemed before, by encoded token
+    token_statuses: HashMap<String, MintStatuses>,
+    redeem_status: Option<String>,
 }
 
 // Creates the websocket client that is used for communicating with relays
@@ -79,16 +401,159 @@ async fn create_client(keys: &Keys, relays: Vec<Url>, client_cb: Callback<Client
     Ok(())
 }
 
+/// DMs proofs as a token to `pubkey` in the configured message
+async fn send_proofs_dm(
+    nostr_client: &Client,
+    mint_url: &Url,
+    pubkey: XOnlyPublicKey,
+    proofs: Proofs,
+    memo: Option<String>,
+    sale: Option<&Sale>,
+    message_settings: &MessageSettings,
+) -> SaleLeg {
+    let amount = melt::proofs_amount(&proofs);
+
+    let token = Token::new(mint_url.clone(), proofs.clone(), memo);
+    let delivered = match token.convert_to_string() {
+        Ok(token) => {
+            let message = message_settings.render(sale, amount, token);
+            nostr_client.send_direct_msg(pubkey, message).await.is_ok()
+        }
+        Err(_) => false,
+    };
+
+    if !delivered {
+        warn!("Could not send token to {}", pubkey);
+    } else if let Err(err) = seed::hand_out(&proofs) {
+        warn!("Could not record handed out proofs {:?}", err);
+    }
+
+    SaleLeg {
+        pubkey,
+        amount,
+        delivered,
+    }
+}
+
+/// Swaps the proofs of an ecash payment so they can't be spent by the payer
+async fn receive_ecash(
+    wallet: &Wallet,
+    seed: Option<Seed>,
+    sale: Sale,
+    payload: PaymentRequestPayload,
+    paid_cb: Callback<(String, Amount, Amount, Token)>,
+) -> Result<()> {
+    let mint_url = wallet.mint_url.clone();
+    let proofs = wallet.receive(seed.as_ref(), &payload.proofs).await?;
+    let paid = melt::proofs_amount(&proofs);
+
+    paid_cb.emit((
+        sale.hash,
+        sale.amount,
+        paid,
+        Token::new(mint_url, proofs, None),
+    ));
+
+    Ok(())
+}
+
+/// Turns `proofs` into a refund token for the customer
+///
+/// The token is kept with the sale if it can't be stored encrypted, so it is
+/// never lost.
+async fn finish_refund(
+    mint_url: Url,
+    hash: String,
+    proofs: Proofs,
+    memo: String,
+    refunded_cb: Callback<(String, Refund, bool)>,
+) -> Result<()> {
+    // The customer's now, whether or not they ever spend it
+    if let Err(err) = seed::hand_out(&proofs) {
+        warn!("Could not record handed out proofs {:?}", err);
+    }
+
+    let refund = Refund {
+        amount: melt::proofs_amount(&proofs),
+        created_at: sale::unix_time(),
+        token: Token::new(mint_url, proofs, Some(memo)).convert_to_string()?,
+    };
+
+    let tokens = HashMap::from([(refund.token_key(&hash), refund.token.clone())]);
+    let stored = match storage::save_refund_tokens(tokens).await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!("Could not store refund token encrypted {:?}", err);
+            false
+        }
+    };
+
+    refunded_cb.emit((hash, refund, stored));
+
+    Ok(())
+}
+
 async fn create_wallet(mint_url: &Url, wallet_cb: Callback<Wallet>) -> Result<()> {
-    let client = CashuClient::new(mint_url.as_str())?;
-    let mint_keys = client.get_keys().await?;
-    let wallet = Wallet::new(client, mint_keys);
+    let wallet = Wallet::new(mint_url).await?;
 
     wallet_cb.emit(wallet);
     Ok(())
 }
 
 impl App {
+    fn storage_callbacks(ctx: &Context<Self>) -> StorageCallbacks {
+        StorageCallbacks {
+            seed_cb: ctx.link().callback(Msg::SeedLoaded),
+            config_cb: ctx.link().callback(Msg::ConfigLoaded),
+            pin_lockouts_cb: ctx.link().callback(Msg::PinLockoutsLoaded),
+            wallet_failed_cb: ctx.link().callback(Msg::WalletLoadFailed),
+            refund_tokens_cb: ctx.link().callback(Msg::RefundTokensLoaded),
+        }
+    }
+
+    /// Keeps the failed PIN attempts in the encrypted store
+    fn save_pin_lockouts(&self) {
+        let lockouts = self.pin_lockouts.clone();
+        spawn_local(async move {
+            if let Err(err) = wallet_store::set_encrypted(PIN_LOCKOUT_KEY, &lockouts).await {
+                warn!("Could not save PIN lockouts {:?}", err);
+            }
+        });
+    }
+
+    fn save_splits(&self) {
+        let splits = self.splits.clone();
+        spawn_local(async move {
+            if let Err(err) = wallet_store::update_config(|c| c.splits = splits).await {
+                warn!("Could not save splits {:?}", err);
+            }
+        });
+    }
+
+    fn save_cashiers(&self) {
+        let cashiers = self.cashiers.clone();
+        spawn_local(async move {
+            if let Err(err) = wallet_store::update_config(|c| c.cashiers = cashiers).await {
+                warn!("Could not save cashiers {:?}", err);
+            }
+        });
+    }
+
+    /// PINs unlocking the settings, the admin PIN and that of a signed in
+    /// manager, by the account failures are counted against
+    fn unlock_pins(&self) -> Vec<(String, PinHash)> {
+        self.admin_pin
+            .iter()
+            .map(|pin| (ADMIN_ACCOUNT.to_string(), pin.clone()))
+            .chain(
+                self.cashier
+                    .iter()
+                    .filter(|c| c.role == Role::Manager)
+                    .map(|c| (c.name.clone(), c.pin.clone())),
+            )
+            .collect()
+    }
+
     fn app_view(&self) -> View {
         let wallet = self.wallet.lock().unwrap().clone();
         let key = self.nostr_receice_pubkey.clone();
@@ -96,6 +561,10 @@ impl App {
         log::debug!("{:?}", self.relays);
 
         match (key, wallet, self.relays.is_empty()) {
+            (Some(_), Some(_), false) if !self.seed_backed_up => View::Backup,
+            (Some(_), Some(_), false) if !self.cashiers.is_empty() && self.cashier.is_none() => {
+                View::SignIn
+            }
             (Some(_), Some(_), false) => View::Pos,
             (None, Some(_), _) => View::SetRecKey,
             (Some(_), None, _) => View::SetMint,
@@ -104,301 +573,2384 @@ impl App {
         }
     }
 
+    fn is_unlocked(&self) -> bool {
+        (self.admin_pin.is_none() && self.cashiers.is_empty())
+            || self
+                .admin_session
+                .is_some_and(|session| session.is_active(sale::unix_time()))
+    }
+
+    /// Messages only a manager may send once the terminal is set up
+    fn requires_manager(&self, msg: &Msg) -> bool {
+        let sensitive = matches!(
+            msg,
+            Msg::MintUrlSet(_)
+                | Msg::NostrRecKeySet(_)
+                | Msg::AddRelay(_)
+                | Msg::RelaysSet
+                | Msg::AddSplit(_)
+                | Msg::ClearSplits
+                | Msg::MeltSettingsSet(_)
+                | Msg::ShopNameSet(_)
+                | Msg::OnchainAddressSet(_)
+                | Msg::AutoReturnSet(_)
+                | Msg::PrinterSet(_)
+                | Msg::MessageSettingsSet(_)
+                | Msg::SweepScheduleSet(_)
+                | Msg::Restore(_)
+                | Msg::RefundRequested(_)
+                | Msg::SyncKeySet(_)
+                | Msg::SyncSales
+                | Msg::ShiftOpened(_)
+                | Msg::ShiftClosed(_)
+                | Msg::PinSet(_)
+                | Msg::CashierAdded(_)
+                | Msg::CashierRemoved(_)
+                | Msg::MerchantSet(_)
+                | Msg::ExportConfig
+                | Msg::Redeem
+                | Msg::ClaimedTokensCleared
+                | Msg::ConfigImported(_)
+                | Msg::SignerTrusted(_)
+                | Msg::AddRelayView
+                | Msg::SetMintView
+                | Msg::SetPubkeyView
+                | Msg::SetSplitsView
+                | Msg::SetMeltView
+                | Msg::SetShopView
+                | Msg::SetMessageView
+                | Msg::SetPrinterView
+                | Msg::ShiftView
+                | Msg::ExportView
+                | Msg::SalesView
+                | Msg::SalesSyncView
+                | Msg::SetPinView
+                | Msg::CashiersView
+                | Msg::SetRemoteView
+                | Msg::ConfigTransferView
+                | Msg::DashboardView
+                | Msg::TerminalWalletView
+                | Msg::BackupView
+        );
+
+        // The setup screens shown on first start have nothing to protect yet
+        sensitive && self.is_configured()
+    }
+
+    /// Whether the first start setup is done, whatever view is shown
+    fn is_configured(&self) -> bool {
+        self.nostr_receice_pubkey.is_some()
+            && self.wallet.lock().unwrap().is_some()
+            && !self.relays.is_empty()
+    }
+
+    /// Views a customer may see while the settings are locked
+    fn is_public_view(&self) -> bool {
+        matches!(
+            self.view,
+            View::Loading
+                | View::StorageLocked
+                | View::Pos
+                | View::Invoice(_)
+                | View::InvoicePaid(_)
+                | View::Unlock
+        )
+    }
+
     async fn get_invoice(
         &self,
         amount: Amount,
         fiat_value: String,
-        invoice_cb: Callback<(Amount, String, RequestMintResponse)>,
+        reference: Option<String>,
+        invoice_cb: Callback<(Amount, String, Option<String>, MintQuote)>,
     ) -> Result<()> {
         let wallet = self.wallet.lock().unwrap().clone();
 
+        if self.seed.is_none() {
+            bail!("Wallet seed not loaded");
+        }
+
         if let Some(wallet) = wallet {
-            let invoice = wallet.request_mint(amount).await?;
+            let mint_url = &wallet.mint_url;
+
+            let description =
+                match sale::description(self.shop_name.as_deref(), reference.as_deref()) {
+                    Some(description) if mint_api::description_supported(mint_url).await => {
+                        Some(description)
+                    }
+                    _ => None,
+                };
+            let quote = mint_api::mint_quote(mint_url, amount, description).await?;
 
-            invoice_cb.emit((amount, fiat_value, invoice))
+            invoice_cb.emit((amount, fiat_value, reference, quote))
         }
 
         Ok(())
     }
 
+    /// Waits for the quote to be paid then mints it once
+    ///
+    /// Gives up without minting once `cancel` fires.
     async fn mint(
         &mut self,
         amount: Amount,
         hash: String,
-        mint_cb: Callback<(Amount, Token)>,
+        quote: MintQuote,
+        cancel: oneshot::Receiver<()>,
+        mint_cb: Callback<(String, Amount, Token)>,
     ) -> Result<()> {
         let wallet = self.wallet.lock().unwrap().clone();
 
-        if let Some(wallet) = wallet {
-            loop {
-                if let Ok(proofs) = wallet.mint(amount, &hash).await {
-                    let token = Token::new(wallet.client.mint_url, proofs, None);
-                    self.unpaid_invoices.remove(&hash);
+        if let (Some(wallet), Some(seed)) = (wallet, &self.seed) {
+            let mint_url = wallet.mint_url.clone();
 
-                    mint_cb.emit((amount, token));
-                    break;
-                }
-                sleep(Duration::from_secs(1)).await;
+            let paid = quote_watch::wait_until_paid(&mint_url, &quote, cancel).await;
+            self.quote_watchers.lock().unwrap().remove(&hash);
+            if !paid? {
+                return Ok(());
             }
+
+            // Outputs are derived from the seed so the proofs can be restored,
+            // and recorded so the quote can still be minted after a reload
+            let keyset_id = wallet.keyset.id.clone();
+            let (counter, _) = seed.premint(&keyset_id, amount)?;
+            let pending = PendingMint {
+                hash: hash.clone(),
+                quote_id: quote.id.clone(),
+                amount,
+                keyset_id,
+                seed: seed.fingerprint(),
+                counter,
+                refund: false,
+            };
+            pending_mint::add(pending.clone());
+
+            let proofs = pending_mint::complete(&wallet, seed, &pending).await?;
+            pending_mint::settle(&pending.quote_id);
+
+            let token = Token::new(mint_url, proofs, None);
+            self.unpaid_invoices.remove(&hash);
+
+            mint_cb.emit((hash, amount, token));
         }
 
         Ok(())
     }
 
-    async fn send_token(&self, token: Token) -> Result<()> {
-        if let (Some(nostr_client), Some(nostr_rec)) = (
+    /// Splits the minted token between the split recipients and the receiver
+    /// and DMs each of them their share
+    ///
+    /// Shares are of the sale `amount`, the tip in the token goes to the receiver.
+    ///
+    /// When auto melt or sweeps are enabled the receiver's share is kept on the
+    /// terminal instead.
+    async fn send_token(
+        &self,
+        hash: String,
+        amount: Amount,
+        token: Token,
+        settled_cb: Callback<(String, Vec<SaleLeg>, Amount)>,
+    ) -> Result<()> {
+        let wallet = self.wallet.lock().unwrap().clone();
+
+        if let (Some(nostr_client), Some(nostr_rec), Some(wallet)) = (
             self.nostr_client.lock().await.clone(),
             self.nostr_receice_pubkey.clone(),
+            wallet,
         ) {
-            let _ = nostr_client
-                .send_direct_msg(nostr_rec.public_key(), token.convert_to_string()?)
+            let mint_url = wallet.mint_url.clone();
+            let mut proofs: Proofs = token.token.into_iter().flat_map(|t| t.proofs).collect();
+            let mut legs = vec![];
+
+            let sale = self.sales.iter().find(|s| s.hash == hash);
+            let memo = sale
+                .and_then(|s| sale::description(self.shop_name.as_deref(), s.reference.as_deref()));
+
+            for (pubkey, leg_amount) in split::split_amount(amount, &self.splits) {
+                // Swap for the exact amount so each recipient gets their own proofs
+                let send_proofs = match wallet
+                    .send(self.seed.as_ref(), leg_amount, proofs.clone())
+                    .await
+                {
+                    Ok(send_proofs) => send_proofs,
+                    Err(err) => {
+                        // Anything that could not be split goes to the receiver
+                        warn!("Could not split proofs {:?}", err);
+                        break;
+                    }
+                };
+                proofs = send_proofs.change_proofs;
+
+                legs.push(
+                    send_proofs_dm(
+                        &nostr_client,
+                        &mint_url,
+                        pubkey,
+                        send_proofs.send_proofs,
+                        memo.clone(),
+                        sale,
+                        &self.message_settings,
+                    )
+                    .await,
+                );
+            }
+
+            let dm_receiver = self.melt_settings.as_ref().is_some_and(|m| m.dm_receiver);
+            // Proofs kept while the stored ones are unreadable would be lost on reload
+            let can_retain = wallet_store::proofs_loaded();
+            let mut retained = Amount::default();
+            if can_retain
+                && ((self.melt_settings.is_some() && !dm_receiver) || self.sweep_schedule.is_some())
+            {
+                retained = melt::proofs_amount(&proofs);
+                self.retain_proofs(&wallet, proofs).await;
+            } else if !proofs.is_empty() {
+                let leg = send_proofs_dm(
+                    &nostr_client,
+                    &mint_url,
+                    nostr_rec.public_key(),
+                    proofs.clone(),
+                    memo,
+                    sale,
+                    &self.message_settings,
+                )
                 .await;
+
+                // Whatever could not be DMed is melted with the rest of the balance
+                if !leg.delivered && dm_receiver && can_retain {
+                    retained = leg.amount;
+                    self.retain_proofs(&wallet, proofs).await;
+                }
+                legs.push(leg);
+            }
+
+            settled_cb.emit((hash, legs, retained));
         }
 
         Ok(())
     }
 
-    async fn add_relay(&self, relay: Url) -> Result<()> {
-        log::debug!("i{:?}", relay);
+    /// Signs a receipt for the sale with the terminal key and publishes it
+    async fn create_receipt(
+        &self,
+        hash: String,
+        receipt_cb: Callback<(String, String)>,
+    ) -> Result<()> {
+        let sale = match self.sales.iter().find(|s| s.hash == hash) {
+            Some(sale) => sale,
+            None => bail!("Unknown sale {}", hash),
+        };
+
         if let Some(nostr_client) = self.nostr_client.lock().await.clone() {
-            log::debug!("client some");
-            nostr_client.add_relay(&relay.to_string()).await?;
-            nostr_client.connect().await;
+            let receipt = Receipt::new(
+                sale,
+                self.shop_name.clone(),
+                self.message_settings.terminal_name.clone(),
+            );
+            let event = receipt.to_event(&nostr_client.keys())?;
+
+            // The QR can be verified without the relays so still show it
+            if let Err(err) = nostr_client.send_event(event.clone()).await {
+                warn!("Could not publish receipt {:?}", err);
+            }
+
+            receipt_cb.emit((hash, event.as_json()));
         }
+
         Ok(())
     }
-}
 
-impl Component for App {
-    type Message = Msg;
-    type Properties = ();
-
-    fn create(ctx: &Context<Self>) -> Self {
-        let mint_url: Option<Url> = LocalStorage::get::<Url>(MINT_URL_KEY).ok();
+    /// Adds proofs to the terminal balance and melts it once over the threshold
+    async fn retain_proofs(&self, wallet: &Wallet, proofs: Proofs) {
+        let mut balance = self.balance.lock().await;
+        balance.extend(proofs);
+        if let Err(err) = wallet_store::save_proofs(&balance).await {
+            warn!("Could not save terminal wallet {:?}", err);
+        }
 
-        let nostr_rec_key: Option<Keys> = LocalStorage::get::<String>(NOSTR_KEY)
-            .ok()
-            .and_then(|u| serde_json::from_str(u.as_str()).map(|k: String| k).ok())
-            .and_then(|u| Keys::from_pk_str(&u).ok());
+        let melt_settings = match &self.melt_settings {
+            Some(melt_settings) => melt_settings,
+            None => return,
+        };
 
-        let relays: HashSet<Url> = LocalStorage::get(RELAYS_KEY).unwrap_or_default();
+        if melt::proofs_amount(&balance).to_sat() < melt_settings.threshold {
+            return;
+        }
 
-        let relays_vec = relays.iter().cloned().collect();
+        match melt::melt_balance(wallet, self.seed.as_ref(), melt_settings, balance.clone()).await {
+            Ok(change) => {
+                *balance = change;
+                if let Err(err) = wallet_store::save_proofs(&balance).await {
+                    warn!("Could not save terminal wallet {:?}", err);
+                }
+            }
+            Err(err) => warn!("Could not melt balance {:?}", err),
+        }
+    }
 
-        match (mint_url, nostr_rec_key) {
-            (Some(url), Some(pubkey)) => {
-                let keys = utls::handle_keys(None).unwrap();
+    /// Sends the whole terminal balance to the receiver as a single token
+    async fn sweep(&self, balance_cb: Callback<Amount>) -> Result<()> {
+        let wallet = self.wallet.lock().unwrap().clone();
 
-                let client_cb = ctx.link().callback(Msg::ClientCreated);
-                let wallet_cb = ctx.link().callback(Msg::WalletCreated);
+        if let (Some(nostr_client), Some(nostr_rec), Some(wallet)) = (
+            self.nostr_client.lock().await.clone(),
+            self.nostr_receice_pubkey.clone(),
+            wallet,
+        ) {
+            let mut balance = self.balance.lock().await;
+            let amount = melt::proofs_amount(&balance);
 
-                spawn_local(async move {
-                    // TODO: Set relays
+            if amount.to_sat() > 0 {
+                // Swap to consolidate the many small sale proofs
+                let send_proofs = wallet
+                    .send(self.seed.as_ref(), amount, balance.clone())
+                    .await?;
+                let mint_url = wallet.mint_url.clone();
 
-                    create_client(&keys, relays_vec, client_cb).await.unwrap();
-                    create_wallet(&url, wallet_cb).await.unwrap();
-                });
+                let leg = send_proofs_dm(
+                    &nostr_client,
+                    &mint_url,
+                    nostr_rec.public_key(),
+                    send_proofs.send_proofs.clone(),
+                    None,
+                    None,
+                    &self.message_settings,
+                )
+                .await;
 
-                Self {
-                    view: View::Pos,
-                    nostr_receice_pubkey: Some(pubkey),
-                    relays,
-                    ..Default::default()
+                // The old proofs are spent by the swap so keep the new ones if the DM failed
+                *balance = send_proofs.change_proofs;
+                if !leg.delivered {
+                    balance.extend(send_proofs.send_proofs);
                 }
-            }
-            // Mint Url is not set
-            (None, None) => Self {
-                relays,
-                ..Default::default()
-            },
-            // Mint url is set but user not logged in
-            (Some(url), None) => {
-                let wallet_cb = ctx.link().callback(Msg::WalletCreated);
+                wallet_store::save_proofs(&balance).await?;
 
-                spawn_local(async move {
-                    create_wallet(&url, wallet_cb).await.unwrap();
-                });
-
-                Self {
-                    view: View::SetRecKey,
-                    relays,
-                    ..Default::default()
+                if !leg.delivered {
+                    balance_cb.emit(melt::proofs_amount(&balance));
+                    bail!("Could not send swept token");
                 }
             }
-            (None, Some(pubkey)) => Self {
-                nostr_receice_pubkey: Some(pubkey),
-                view: View::SetMint,
-                relays,
-                ..Default::default()
-            },
+
+            // Only a sweep that went through counts, so a failed one is retried
+            LocalStorage::set(LAST_SWEEP_KEY, sale::unix_time()).ok();
+            balance_cb.emit(melt::proofs_amount(&balance));
         }
+
+        Ok(())
     }
 
-    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
-        match msg {
-            Msg::ClientCreated(client) => {
-                self.nostr_client = Arc::new(TokioMutex::new(Some(client)));
-                self.view = self.app_view();
-                true
-            }
-            Msg::WalletCreated(wallet) => {
-                self.wallet = Arc::new(Mutex::new(Some(wallet)));
-                self.view = self.app_view();
-                true
-            }
-            Msg::NostrRecKeySet(rec_key) => {
-                LocalStorage::set(
-                    NOSTR_KEY,
-                    serde_json::to_string(&rec_key.public_key()).unwrap(),
-                )
-                .ok();
+    /// Keys of this terminal and of those it syncs sales with
+    fn terminal_keys(&self) -> HashSet<XOnlyPublicKey> {
+        self.pos_pubkey
+            .into_iter()
+            .chain(
+                self.synced_sales
+                    .iter()
+                    .filter_map(|s| s.terminal.as_deref())
+                    .filter_map(|k| Keys::from_pk_str(k).ok())
+                    .map(|k| k.public_key()),
+            )
+            .collect()
+    }
 
-                self.nostr_receice_pubkey = Some(rec_key);
-                self.view = self.app_view();
+    fn refund_memo(&self, hash: &str) -> String {
+        let memo = self
+            .sales
+            .iter()
+            .find(|s| s.hash == hash)
+            .and_then(|s| sale::description(self.shop_name.as_deref(), s.reference.as_deref()));
 
-                true
-            }
-            Msg::MintUrlSet(url) => {
-                let url_clone = url.clone();
-                LocalStorage::set(MINT_URL_KEY, url).ok();
+        match memo {
+            Some(memo) => format!("Refund for {}", memo),
+            None => "Refund".to_string(),
+        }
+    }
 
-                let create_wallet_cb = ctx.link().callback(Msg::WalletCreated);
+    /// Swaps the refund out of the terminal balance into a token for the customer
+    ///
+    /// If the balance is too low an invoice is requested for the merchant to
+    /// pay instead.
+    async fn refund(
+        &self,
+        hash: String,
+        amount: Amount,
+        invoice_cb: Callback<(String, Amount, MintQuote)>,
+        refunded_cb: Callback<(String, Refund, bool)>,
+    ) -> Result<()> {
+        let wallet = self
+            .wallet
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(anyhow!("No wallet"))?;
+        let memo = self.refund_memo(&hash);
 
-                spawn_local(async move {
-                    if let Err(err) = create_wallet(&url_clone, create_wallet_cb).await {
-                        warn!("Could not create wallet {:?}", err);
-                    }
-                });
-                true
-            }
-            Msg::AmountSet((amount, fiat_value)) => {
-                let get_invoice_cb = ctx.link().callback(Msg::InvoiceSet);
-                let app = self.clone();
-                spawn_local(async move {
-                    if let Err(err) = app.get_invoice(amount, fiat_value, get_invoice_cb).await {
-                        warn!("Could not create wallet {:?}", err);
-                    }
-                });
-                true
-            }
-            Msg::InvoiceSet((amount, fiat_value, invoice_response)) => {
-                self.view = View::Invoice((invoice_response.pr, fiat_value));
-                self.unpaid_invoices.insert(invoice_response.hash.clone());
+        let mut balance = self.balance.lock().await;
 
-                let invoice_paid_cb = ctx.link().callback(Msg::InvoicePaid);
-                let mut app = self.clone();
-                spawn_local(async move {
-                    if let Err(err) = app
-                        .mint(amount, invoice_response.hash, invoice_paid_cb)
-                        .await
-                    {
-                        warn!("Could not create wallet {:?}", err);
-                    }
-                });
-                true
-            }
-            Msg::InvoicePaid((_amount, token)) => {
-                let app = self.clone();
-                self.view = View::InvoicePaid;
-                spawn_local(async move {
-                    let _ = app.send_token(token).await;
-                });
+        // Refunds finished after this one was requested are only in storage
+        let sales: Vec<Sale> = LocalStorage::get(SALES_KEY).unwrap_or_default();
+        let refundable = sales
+            .iter()
+            .find(|s| s.hash == hash)
+            .map(|s| s.refundable())
+            .unwrap_or_default();
+        if amount.to_sat() > refundable.to_sat() {
+            bail!("Only {} sats can be refunded", refundable.to_sat());
+        }
 
-                true
-            }
-            Msg::Home => {
-                self.view = self.app_view();
+        if melt::proofs_amount(&balance).to_sat() < amount.to_sat() {
+            let quote = mint_api::mint_quote(&wallet.mint_url, amount, Some(memo)).await?;
+            invoice_cb.emit((hash, amount, quote));
 
-                true
-            }
-            Msg::AddRelay(relay) => {
-                log::debug!("Msg: {:?}", relay);
-                let app = self.clone();
-                let relay_clone = relay.clone();
-                spawn_local(async move {
-                    let _ = app.add_relay(relay).await;
-                });
-                self.relays.insert(relay_clone);
+            return Ok(());
+        }
 
-                log::debug!("relays: {:?}", self.relays);
-                LocalStorage::set(RELAYS_KEY, self.relays.clone()).ok();
+        let send_proofs = wallet
+            .send(self.seed.as_ref(), amount, balance.clone())
+            .await?;
+        *balance = send_proofs.change_proofs;
+        wallet_store::save_proofs(&balance).await?;
 
-                true
-            }
-            Msg::RelaysSet => {
-                self.view = self.app_view();
+        finish_refund(
+            wallet.mint_url,
+            hash,
+            send_proofs.send_proofs,
+            memo,
+            refunded_cb,
+        )
+        .await
+    }
 
-                true
-            }
-            Msg::Settings => {
-                self.view = View::Settings;
-                true
-            }
-            Msg::SetPubkeyView => {
-                self.view = View::SetRecKey;
-                true
-            }
-            Msg::SetMintView => {
-                self.view = View::SetMint;
-                true
-            }
-            Msg::AddRelayView => {
-                self.view = View::SetRelays;
-                true
-            }
+    /// Mints a refund once the merchant has paid its invoice
+    async fn fund_refund(
+        &self,
+        hash: String,
+        amount: Amount,
+        quote: MintQuote,
+        cancel: oneshot::Receiver<()>,
+        refunded_cb: Callback<(String, Refund, bool)>,
+    ) -> Result<()> {
+        let wallet = self
+            .wallet
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(anyhow!("No wallet"))?;
+        let seed = self.seed.as_ref().ok_or(anyhow!("No wallet seed"))?;
+
+        let paid = quote_watch::wait_until_paid(&wallet.mint_url, &quote, cancel).await;
+        self.quote_watchers.lock().unwrap().remove(&quote.id);
+        if !paid? {
+            return Ok(());
         }
-    }
 
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        log::debug!("{:?}", self.view);
-        html! {
-            <main>
+        // Recorded like sale mints so a reload can't lose what the merchant paid
+        let keyset_id = wallet.keyset.id.clone();
+        let (counter, _) = seed.premint(&keyset_id, amount)?;
+        let pending = PendingMint {
+            hash: hash.clone(),
+            quote_id: quote.id.clone(),
+            amount,
+            keyset_id,
+            seed: seed.fingerprint(),
+            counter,
+            refund: true,
+        };
+        pending_mint::add(pending.clone());
 
-                    {
+        let proofs = pending_mint::complete(&wallet, seed, &pending).await?;
+        pending_mint::settle(&pending.quote_id);
 
-                match &self.view {
-                    View::Pos => {
-                        let amount_cb = ctx.link().callback(Msg::AmountSet);
-                        let settings_cb = ctx.link().callback(|_| Msg::Settings);
+        finish_refund(
+            wallet.mint_url,
+            hash.clone(),
+            proofs,
+            self.refund_memo(&hash),
+            refunded_cb,
+        )
+        .await
+    }
 
-                        html!{
-                            <>
-                            <div style="display: flex; justify-content: flex-end;">
-                              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={settings_cb.clone()}>{"Settings"}</button>
-                            </div>
-                            <Pos {amount_cb} />
-                            </>
-                        }
+    /// Current config in the remote config schema
+    fn current_config(&self) -> RemoteConfig {
+        let wallet = self.wallet.lock().unwrap().clone();
 
-                    }
-                    View::SetMint => {
-                        let mint_set_cb = ctx.link().callback(Msg::MintUrlSet);
+        RemoteConfig {
+            mint_url: wallet.map(|w| w.mint_url.clone()),
+            relays: Some(self.relays.clone()),
+            receiver: self
+                .nostr_receice_pubkey
+                .as_ref()
+                .map(|k| k.public_key().to_string()),
+            splits: Some(self.splits.clone()),
+            shop_name: self.shop_name.clone(),
+            auto_return: self.auto_return,
+            message: Some(self.message_settings.clone()),
+        }
+    }
 
-                        html! {
-                            <>
-                             <SetMint {mint_set_cb} />
-                            </>
-                        }
-                    }
-                    View::SetRecKey => {
-                        let set_rec_key = ctx.link().callback(Msg::NostrRecKeySet);
+    /// Applies a validated remote config update
+    fn apply_remote_config(&mut self, ctx: &Context<Self>, config: RemoteConfig) {
+        let receiver = config
+            .receiver
+            .and_then(|receiver| Keys::from_pk_str(&receiver).ok());
 
-                        html!{
+        if let Some(splits) = &config.splits {
+            self.splits = splits.clone();
+        }
+        if let Some(shop_name) = config.shop_name {
+            if shop_name.is_empty() {
+                LocalStorage::delete(SHOP_NAME_KEY);
+                self.shop_name = None;
+            } else {
+                LocalStorage::set(SHOP_NAME_KEY, &shop_name).ok();
+                self.shop_name = Some(shop_name);
+            }
+        }
+        if let Some(auto_return) = config.auto_return {
+            LocalStorage::set(AUTO_RETURN_KEY, auto_return).ok();
+            self.auto_return = Some(auto_return);
+        }
+        if let Some(message_settings) = config.message {
+            LocalStorage::set(MESSAGE_KEY, &message_settings).ok();
+            self.message_settings = message_settings;
+        }
+        if let Some(relays) = &config.relays {
+            let added: Vec<Url> = relays.difference(&self.relays).cloned().collect();
+            let removed: Vec<Url> = self.relays.difference(relays).cloned().collect();
+            let app = self.clone();
+            spawn_local(async move {
+                for relay in added {
+                    if let Err(err) = app.add_relay(relay).await {
+                        warn!("Could not add relay {:?}", err);
+                    }
+                }
+                for relay in removed {
+                    if let Err(err) = app.remove_relay(relay).await {
+                        warn!("Could not remove relay {:?}", err);
+                    }
+                }
+            });
+            self.relays = relays.clone();
+        }
+        if let Some(receiver) = &receiver {
+            self.nostr_receice_pubkey = Some(receiver.clone());
+        }
+
+        let mint_url = config.mint_url;
+        let relays = config.relays;
+        let splits = config.splits;
+        let nostr_rec = receiver.map(|k| k.public_key().to_string());
+        let wallet_cb = ctx.link().callback(Msg::WalletCreated);
+        spawn_local(async move {
+            let new_mint = mint_url.clone();
+            if let Err(err) = wallet_store::update_config(|c| {
+                if let Some(mint_url) = mint_url {
+                    c.mint_url = Some(mint_url);
+                }
+                if let Some(relays) = relays {
+                    c.relays = relays;
+                }
+                if let Some(nostr_rec) = nostr_rec {
+                    c.nostr_rec = Some(nostr_rec);
+                }
+                if let Some(splits) = splits {
+                    c.splits = splits;
+                }
+            })
+            .await
+            {
+                warn!("Could not save remote config {:?}", err);
+            }
+
+            if let Some(mint_url) = new_mint {
+                if let Err(err) = create_wallet(&mint_url, wallet_cb).await {
+                    warn!("Could not create wallet {:?}", err);
+                }
+            }
+        });
+    }
+
+    /// Re-encrypts the store under a key wrapped by the admin PIN
+    async fn protect_storage(&self, pin: Option<&str>) -> Result<()> {
+        // Held so no sale writes proofs with the old key meanwhile
+        let _balance = self.balance.lock().await;
+
+        wallet_store::set_pin(pin).await
+    }
+
+    /// Restores proofs derived from the seed into the terminal wallet
+    async fn restore(&self, seed: Seed, restored_cb: Callback<String>) -> Result<()> {
+        let wallet = self.wallet.lock().unwrap().clone();
+
+        if let Some(wallet) = wallet {
+            // Minted proofs DMed as they were belong to their recipients
+            let delivered: HashSet<&str> = self
+                .sales
+                .iter()
+                .filter(|s| s.retained.to_sat() == 0 && s.legs.iter().any(|l| l.delivered))
+                .map(|s| s.hash.as_str())
+                .collect();
+            let fingerprint = seed.fingerprint();
+            let exclude: Vec<_> = pending_mint::minted()
+                .iter()
+                .filter(|m| {
+                    m.seed == fingerprint
+                        && m.keyset_id == wallet.keyset.id
+                        && (m.refund || delivered.contains(m.hash.as_str()))
+                })
+                .map(|m| m.counters())
+                .collect();
+
+            let proofs = seed.restore(&wallet, &exclude).await?;
+
+            let mut balance = self.balance.lock().await;
+            // Skip proofs the terminal wallet still has
+            let proofs: Proofs = proofs
+                .into_iter()
+                .filter(|p| !balance.iter().any(|b| b.secret == p.secret))
+                .collect();
+            let amount = melt::proofs_amount(&proofs);
+            balance.extend(proofs);
+            wallet_store::save_proofs(&balance).await?;
+
+            restored_cb.emit(format!(
+                "Restored {} sats to the terminal wallet",
+                amount.to_sat()
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn get_balance(&self, balance_cb: Callback<Amount>) {
+        let balance = self.balance.lock().await;
+        balance_cb.emit(melt::proofs_amount(&balance));
+    }
+
+    /// Saves the sales without the refund tokens kept encrypted
+    fn save_sales(&self) {
+        let mut sales = self.sales.clone();
+        for sale in &mut sales {
+            for refund in &mut sale.refunds {
+                if self.refund_tokens.contains(&refund.token_key(&sale.hash)) {
+                    refund.token.clear();
+                }
+            }
+        }
+
+        LocalStorage::set(SALES_KEY, sales).ok();
+    }
+
+    /// Sales of this terminal followed by those synced from the others
+    fn all_sales(&self) -> Vec<Sale> {
+        let mut sales = self.sales.clone();
+        sales.extend(
+            self.synced_sales
+                .iter()
+                .filter(|synced| !self.sales.iter().any(|s| s.hash == synced.hash))
+                .cloned(),
+        );
+
+        sales
+    }
+
+    /// Publishes the paid sales in `hashes` for the other terminals
+    fn publish_sales(&self, hashes: Vec<String>) {
+        let sync_keys = match &self.sync_keys {
+            Some(sync_keys) => sync_keys.clone(),
+            None => return,
+        };
+        let sales: Vec<Sale> = self
+            .sales
+            .iter()
+            .filter(|s| s.paid && hashes.contains(&s.hash))
+            .cloned()
+            .collect();
+
+        let nostr_client = self.nostr_client.clone();
+        spawn_local(async move {
+            if let Some(nostr_client) = nostr_client.lock().await.clone() {
+                for sale in sales {
+                    if let Err(err) = sale_sync::publish(&nostr_client, &sync_keys, &sale).await {
+                        warn!("Could not publish sale {:?}", err);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn add_relay(&self, relay: Url) -> Result<()> {
+        log::debug!("i{:?}", relay);
+        if let Some(nostr_client) = self.nostr_client.lock().await.clone() {
+            log::debug!("client some");
+            nostr_client.add_relay(&relay.to_string()).await?;
+            nostr_client.connect().await;
+        }
+        Ok(())
+    }
+
+    async fn remove_relay(&self, relay: Url) -> Result<()> {
+        if let Some(nostr_client) = self.nostr_client.lock().await.clone() {
+            nostr_client.remove_relay(relay.to_string()).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Component for App {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let local = LocalSettings::load();
+
+        let balance = Arc::new(TokioMutex::new(vec![]));
+        let balance_clone = balance.clone();
+        let locked_cb = ctx.link().callback(|_| Msg::StorageLocked);
+        let callbacks = Self::storage_callbacks(ctx);
+        spawn_local(async move {
+            if wallet_store::is_locked().await {
+                locked_cb.emit(());
+            } else {
+                storage::load_storage(balance_clone, callbacks).await;
+            }
+        });
+
+        let sweep_tick_cb = ctx.link().callback(|_| Msg::SweepTick);
+        spawn_local(async move {
+            loop {
+                sleep(Duration::from_secs(60)).await;
+                sweep_tick_cb.emit(());
+            }
+        });
+
+        let auto_lock_tick_cb = ctx.link().callback(|_| Msg::AutoLockTick);
+        spawn_local(async move {
+            loop {
+                sleep(Duration::from_secs(10)).await;
+                auto_lock_tick_cb.emit(());
+            }
+        });
+
+        Self {
+            view: View::Loading,
+            sales: local.sales,
+            synced_sales: local.synced_sales,
+            sweep_schedule: local.sweep_schedule,
+            balance,
+            seed_backed_up: local.seed_backed_up,
+            qr_payload: local.qr_payload,
+            shop_name: local.shop_name,
+            message_settings: local.message_settings,
+            auto_return: local.auto_return,
+            printer_settings: local.printer_settings,
+            shift: local.shift,
+            z_reports: local.z_reports,
+            storage_lockout: local.storage_lockout,
+            remote_config_at: local.remote_config_at,
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        if self.requires_manager(&msg) && !self.is_unlocked() {
+            warn!("Settings are locked");
+            self.pin_error = None;
+            self.view = View::Unlock;
+            return true;
+        }
+
+        if matches!(msg, Msg::AmountSet(_)) && !self.cashiers.is_empty() && self.cashier.is_none() {
+            self.view = View::SignIn;
+            return true;
+        }
+
+        if msg.is_user_input() {
+            if let Some(session) = &mut self.admin_session {
+                session.touch(sale::unix_time());
+            }
+        }
+
+        match msg {
+            Msg::ClientCreated(client) => {
+                self.pos_pubkey = Some(client.keys().public_key());
+
+                let ecash_cb = ctx.link().callback(Msg::EcashReceived);
+                let listen_client = client.clone();
+                spawn_local(async move {
+                    if let Err(err) = payment_request::listen(listen_client, ecash_cb).await {
+                        warn!("Could not listen for ecash payments {:?}", err);
+                    }
+                });
+
+                let remote_config_cb = ctx.link().callback(Msg::RemoteConfigReceived);
+                let listen_client = client.clone();
+                let since =
+                    LocalStorage::get(REMOTE_CONFIG_AT_KEY).unwrap_or_else(|_| sale::unix_time());
+                spawn_local(async move {
+                    if let Err(err) =
+                        remote_config::listen(listen_client, since, remote_config_cb).await
+                    {
+                        warn!("Could not listen for remote config {:?}", err);
+                    }
+                });
+
+                // Finishes applying updates saved before a reload
+                let saved_cb = ctx.link().callback(Msg::RemoteConfigSaved);
+                spawn_local(async move {
+                    let applied_at: u64 =
+                        LocalStorage::get(REMOTE_CONFIG_AT_KEY).unwrap_or_default();
+                    match wallet_store::get_encrypted::<AppliedConfig>(REMOTE_CONFIG_KEY).await {
+                        Ok(Some(applied)) if applied.created_at > applied_at => {
+                            saved_cb.emit(applied)
+                        }
+                        Ok(_) => (),
+                        Err(err) => warn!("Could not load remote config {:?}", err),
+                    }
+                });
+
+                self.nostr_client = Arc::new(TokioMutex::new(Some(client)));
+                self.view = self.app_view();
+                true
+            }
+            Msg::ConfigLoaded(config) => {
+                // Also the end of unlocking the storage
+                self.pin_checking = false;
+                self.storage_lockout = PinLockout::default();
+                LocalStorage::delete(STORAGE_LOCKOUT_KEY);
+
+                self.splits = config.splits;
+                self.melt_settings = config.melt_settings;
+                self.onchain_address = config.onchain_address;
+                self.admin_pin = config.admin_pin;
+                self.cashiers = config.cashiers;
+                let nostr_rec_key = config.nostr_rec.and_then(|k| Keys::from_pk_str(&k).ok());
+                let relays_vec = config.relays.iter().cloned().collect();
+                self.relays = config.relays;
+                self.merchant = config
+                    .merchant
+                    .and_then(|k| Keys::from_pk_str(&k).ok())
+                    .map(|k| k.public_key());
+                self.sync_keys = config.sync_key.and_then(|k| Keys::from_sk_str(&k).ok());
+                self.trusted_signers = config
+                    .trusted_signers
+                    .iter()
+                    .filter_map(|k| Keys::from_pk_str(k).ok())
+                    .map(|k| k.public_key())
+                    .collect();
+
+                match (config.mint_url, nostr_rec_key) {
+                    (Some(url), Some(pubkey)) => {
+                        let client_cb = ctx.link().callback(Msg::ClientCreated);
+                        let wallet_cb = ctx.link().callback(Msg::WalletCreated);
+
+                        spawn_local(async move {
+                            let keys = match storage::pos_keys().await {
+                                Ok(keys) => keys,
+                                Err(err) => {
+                                    warn!("Could not load terminal keys {:?}", err);
+                                    utls::handle_keys(None).unwrap()
+                                }
+                            };
+
+                            create_client(&keys, relays_vec, client_cb).await.unwrap();
+                            create_wallet(&url, wallet_cb).await.unwrap();
+                        });
+
+                        self.nostr_receice_pubkey = Some(pubkey);
+                        self.view = View::Pos;
+                    }
+                    // Mint url is set but user not logged in
+                    (Some(url), None) => {
+                        let wallet_cb = ctx.link().callback(Msg::WalletCreated);
+
+                        spawn_local(async move {
+                            create_wallet(&url, wallet_cb).await.unwrap();
+                        });
+
+                        self.view = View::SetRecKey;
+                    }
+                    (None, Some(pubkey)) => {
+                        self.nostr_receice_pubkey = Some(pubkey);
+                        self.view = View::SetMint;
+                    }
+                    // Mint Url is not set
+                    (None, None) => self.view = View::SetMint,
+                }
+
+                true
+            }
+            Msg::StorageLocked => {
+                self.pin_error = None;
+                self.view = View::StorageLocked;
+
+                true
+            }
+            Msg::StorageUnlock(pin) => {
+                if self.pin_checking || self.storage_lockout.locked_for(sale::unix_time()).is_some()
+                {
+                    return true;
+                }
+                self.pin_checking = true;
+
+                let balance = self.balance.clone();
+                let failed_cb = ctx.link().callback(|_| Msg::StorageUnlockFailed);
+                let callbacks = Self::storage_callbacks(ctx);
+                spawn_local(async move {
+                    if wallet_store::unlock(&pin).await {
+                        storage::load_storage(balance, callbacks).await;
+                    } else {
+                        failed_cb.emit(());
+                    }
+                });
+
+                false
+            }
+            Msg::StorageUnlockFailed => {
+                self.pin_checking = false;
+                self.storage_lockout.failed(sale::unix_time());
+                self.pin_error = Some("Wrong PIN".to_string());
+                LocalStorage::set(STORAGE_LOCKOUT_KEY, self.storage_lockout.clone()).ok();
+
+                true
+            }
+            Msg::WalletCreated(wallet) => {
+                if let Some(seed) = self.seed.clone() {
+                    let resumed_cb = ctx.link().callback(Msg::MintResumed);
+                    let refunded_cb = ctx.link().callback(Msg::Refunded);
+                    for pending in pending_mint::load() {
+                        if self.unpaid_invoices.contains(&pending.hash) {
+                            continue;
+                        }
+
+                        let wallet = wallet.clone();
+                        let seed = seed.clone();
+                        let resumed_cb = resumed_cb.clone();
+                        let refunded_cb = refunded_cb.clone();
+                        let memo = self.refund_memo(&pending.hash);
+                        spawn_local(async move {
+                            let proofs =
+                                match pending_mint::complete(&wallet, &seed, &pending).await {
+                                    Ok(proofs) => proofs,
+                                    Err(err) => {
+                                        warn!("Could not resume mint {:?}", err);
+                                        return;
+                                    }
+                                };
+                            pending_mint::settle(&pending.quote_id);
+
+                            if pending.refund {
+                                if let Err(err) = finish_refund(
+                                    wallet.mint_url,
+                                    pending.hash,
+                                    proofs,
+                                    memo,
+                                    refunded_cb,
+                                )
+                                .await
+                                {
+                                    warn!("Could not resume refund {:?}", err);
+                                }
+                            } else {
+                                let token = Token::new(wallet.mint_url, proofs, None);
+                                resumed_cb.emit((pending.hash, pending.amount, token));
+                            }
+                        });
+                    }
+                }
+
+                self.wallet = Arc::new(Mutex::new(Some(wallet)));
+                self.view = self.app_view();
+                true
+            }
+            Msg::NostrRecKeySet(rec_key) => {
+                let nostr_rec = rec_key.public_key().to_string();
+                spawn_local(async move {
+                    if let Err(err) =
+                        wallet_store::update_config(|c| c.nostr_rec = Some(nostr_rec)).await
+                    {
+                        warn!("Could not save receiver {:?}", err);
+                    }
+                });
+
+                self.nostr_receice_pubkey = Some(rec_key);
+                self.view = self.app_view();
+
+                true
+            }
+            Msg::MintUrlSet(url) => {
+                let create_wallet_cb = ctx.link().callback(Msg::WalletCreated);
+
+                spawn_local(async move {
+                    let mint_url = url.clone();
+                    if let Err(err) =
+                        wallet_store::update_config(|c| c.mint_url = Some(mint_url)).await
+                    {
+                        warn!("Could not save mint url {:?}", err);
+                    }
+
+                    if let Err(err) = create_wallet(&url, create_wallet_cb).await {
+                        warn!("Could not create wallet {:?}", err);
+                    }
+                });
+                true
+            }
+            Msg::AmountSet((amount, fiat_value, reference)) => {
+                let get_invoice_cb = ctx.link().callback(Msg::InvoiceSet);
+                let app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app
+                        .get_invoice(amount, fiat_value, reference, get_invoice_cb)
+                        .await
+                    {
+                        warn!("Could not create wallet {:?}", err);
+                    }
+                });
+                true
+            }
+            Msg::InvoiceSet((amount, fiat_value, reference, quote)) => {
+                let hash = quote.invoice.payment_hash().to_string();
+                let description =
+                    sale::description(self.shop_name.as_deref(), reference.as_deref());
+
+                self.sales.push(Sale::new(
+                    hash.clone(),
+                    amount,
+                    fiat_value.clone(),
+                    reference,
+                    self.cashier.as_ref().map(|c| c.name.clone()),
+                ));
+                self.save_sales();
+
+                let mint_url = self.wallet.lock().unwrap().clone().map(|w| w.mint_url);
+                let relays = self.relays.iter().map(|r| r.to_string()).collect();
+                let transports = match self.pos_pubkey.map(|p| Transport::nostr(p, relays)) {
+                    Some(Ok(transport)) => vec![transport],
+                    Some(Err(err)) => {
+                        warn!("Could not create nostr transport {:?}", err);
+                        vec![]
+                    }
+                    None => vec![],
+                };
+                let payment_request = match mint_url {
+                    Some(mint_url) => {
+                        let mut request =
+                            PaymentRequest::new(hash.clone(), amount, &mint_url, transports);
+                        request.description = description;
+
+                        request.encode().unwrap_or_else(|err| {
+                            warn!("Could not encode payment request {:?}", err);
+                            String::new()
+                        })
+                    }
+                    None => String::new(),
+                };
+
+                self.view = View::Invoice((quote.clone(), fiat_value, payment_request));
+                self.unpaid_invoices.insert(hash.clone());
+
+                let (cancel_tx, cancel) = oneshot::channel();
+                self.quote_watchers
+                    .lock()
+                    .unwrap()
+                    .insert(hash.clone(), cancel_tx);
+
+                let invoice_paid_cb = ctx.link().callback(Msg::InvoicePaid);
+                let mut app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app.mint(amount, hash, quote, cancel, invoice_paid_cb).await {
+                        warn!("Could not create wallet {:?}", err);
+                    }
+                });
+                true
+            }
+            Msg::RegenerateInvoice => {
+                if let View::Invoice((quote, fiat_value, _)) = &self.view {
+                    let hash = quote.invoice.payment_hash().to_string();
+                    self.unpaid_invoices.remove(&hash);
+
+                    let reference = self
+                        .sales
+                        .iter()
+                        .find(|s| s.hash == hash)
+                        .and_then(|s| s.reference.clone());
+
+                    let amount =
+                        Amount::from_msat(quote.invoice.amount_milli_satoshis().unwrap_or(0));
+                    let fiat_value = fiat_value.clone();
+                    let amount_cb = ctx.link().callback(Msg::AmountSet);
+                    spawn_local(async move {
+                        // Requote at the current rate, keeping the old value if it can't be fetched
+                        let fiat_value = match pos::sats_per_usd().await {
+                            Ok(sat_per_usd) => pos::fiat_value(amount.to_sat(), sat_per_usd),
+                            Err(_) => fiat_value,
+                        };
+
+                        amount_cb.emit((amount, fiat_value, reference));
+                    });
+                }
+
+                false
+            }
+            Msg::EcashReceived(payload) => {
+                let sale = payload
+                    .id
+                    .as_ref()
+                    .and_then(|id| self.sales.iter().find(|s| &s.hash == id && !s.paid));
+                let wallet = self.wallet.lock().unwrap().clone();
+
+                let (sale, wallet) = match (sale, wallet) {
+                    (Some(sale), Some(wallet)) => (sale.clone(), wallet),
+                    _ => return false,
+                };
+
+                if let Err(err) = payload.validate(sale.amount, &wallet.mint_url) {
+                    warn!("Rejected ecash payment {:?}", err);
+                    return false;
+                }
+
+                // The same payment arrives from every relay
+                if !self.unpaid_invoices.contains(&sale.hash)
+                    || !self.ecash_receiving.insert(sale.hash.clone())
+                {
+                    return false;
+                }
+
+                let paid_cb = ctx.link().callback(Msg::EcashPaid);
+                let failed_cb = ctx.link().callback(Msg::EcashFailed);
+                let seed = self.seed.clone();
+                spawn_local(async move {
+                    let hash = sale.hash.clone();
+                    if let Err(err) = receive_ecash(&wallet, seed, sale, payload, paid_cb).await {
+                        warn!("Could not receive ecash payment {:?}", err);
+                        failed_cb.emit(hash);
+                    }
+                });
+
+                false
+            }
+            Msg::EcashPaid((hash, amount, paid, token)) => {
+                self.ecash_receiving.remove(&hash);
+                self.unpaid_invoices.remove(&hash);
+
+                if let Some(cancel) = self.quote_watchers.lock().unwrap().remove(&hash) {
+                    cancel.send(()).ok();
+                }
+                if let Some(sale) = self.sales.iter_mut().find(|s| s.hash == hash) {
+                    sale.tip = Amount::from_sat(paid.to_sat().saturating_sub(amount.to_sat()));
+                }
+
+                ctx.link()
+                    .send_message(Msg::InvoicePaid((hash, amount, token)));
+
+                false
+            }
+            Msg::EcashFailed(hash) => {
+                self.ecash_receiving.remove(&hash);
+
+                false
+            }
+            Msg::QrPayloadSet(qr_payload) => {
+                self.qr_payload = qr_payload;
+                LocalStorage::set(QR_PAYLOAD_KEY, qr_payload).ok();
+
+                true
+            }
+            Msg::InvoicePaid((hash, amount, token)) => {
+                if let Some(sale) = self.sales.iter_mut().find(|s| s.hash == hash) {
+                    sale.paid = true;
+                }
+                self.save_sales();
+
+                let settled_cb = ctx.link().callback(Msg::SaleSettled);
+                let app = self.clone();
+                self.view = View::InvoicePaid(hash.clone());
+                self.receipt = None;
+                spawn_local(async move {
+                    if let Err(err) = app.send_token(hash, amount, token, settled_cb).await {
+                        warn!("Could not send token {:?}", err);
+                    }
+                });
+
+                true
+            }
+            Msg::MintResumed((hash, amount, token)) => {
+                if let Some(sale) = self.sales.iter_mut().find(|s| s.hash == hash) {
+                    sale.paid = true;
+                }
+                self.save_sales();
+
+                let settled_cb = ctx.link().callback(Msg::SaleSettled);
+                let app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app.send_token(hash, amount, token, settled_cb).await {
+                        warn!("Could not send token {:?}", err);
+                    }
+                });
+
+                false
+            }
+            Msg::SaleSettled((hash, legs, retained)) => {
+                if let Some(sale) = self.sales.iter_mut().find(|s| s.hash == hash) {
+                    sale.legs = legs;
+                    sale.retained = retained;
+                }
+                self.save_sales();
+                self.publish_sales(vec![hash.clone()]);
+
+                // Show the delivery status if the sale is still on screen
+                matches!(&self.view, View::InvoicePaid(paid) if paid == &hash)
+            }
+            Msg::Home => {
+                self.view = self.app_view();
+
+                true
+            }
+            Msg::AddRelay(relay) => {
+                log::debug!("Msg: {:?}", relay);
+                let app = self.clone();
+                let relay_clone = relay.clone();
+                spawn_local(async move {
+                    let _ = app.add_relay(relay).await;
+                });
+                self.relays.insert(relay_clone);
+
+                log::debug!("relays: {:?}", self.relays);
+                let relays = self.relays.clone();
+                spawn_local(async move {
+                    if let Err(err) = wallet_store::update_config(|c| c.relays = relays).await {
+                        warn!("Could not save relays {:?}", err);
+                    }
+                });
+
+                true
+            }
+            Msg::RelaysSet => {
+                self.view = self.app_view();
+
+                true
+            }
+            Msg::AddSplit(split) => {
+                self.splits.push(split);
+                self.save_splits();
+
+                true
+            }
+            Msg::ClearSplits => {
+                self.splits.clear();
+                self.save_splits();
+
+                true
+            }
+            Msg::MeltSettingsSet(melt_settings) => {
+                let settings = melt_settings.clone();
+                spawn_local(async move {
+                    if let Err(err) =
+                        wallet_store::update_config(|c| c.melt_settings = settings).await
+                    {
+                        warn!("Could not save melt settings {:?}", err);
+                    }
+                });
+                self.melt_settings = melt_settings;
+                self.view = View::Settings;
+
+                true
+            }
+            Msg::ShopNameSet(shop_name) => {
+                match &shop_name {
+                    Some(shop_name) => {
+                        LocalStorage::set(SHOP_NAME_KEY, shop_name).ok();
+                    }
+                    None => LocalStorage::delete(SHOP_NAME_KEY),
+                }
+                self.shop_name = shop_name;
+                self.view = View::Settings;
+
+                true
+            }
+            Msg::OnchainAddressSet(onchain_address) => {
+                let address = onchain_address.clone();
+                spawn_local(async move {
+                    if let Err(err) =
+                        wallet_store::update_config(|c| c.onchain_address = address).await
+                    {
+                        warn!("Could not save onchain address {:?}", err);
+                    }
+                });
+                self.onchain_address = onchain_address;
+
+                false
+            }
+            Msg::Receipt(hash) => {
+                let receipt_cb = ctx.link().callback(Msg::ReceiptCreated);
+                let app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app.create_receipt(hash, receipt_cb).await {
+                        warn!("Could not create receipt {:?}", err);
+                    }
+                });
+
+                false
+            }
+            Msg::ReceiptCreated(receipt) => {
+                self.receipt = Some(receipt);
+
+                true
+            }
+            Msg::Print(hash) => {
+                let sale = self.sales.iter().find(|s| s.hash == hash);
+
+                if let (Some(sale), Some(printer_settings)) = (sale, &self.printer_settings) {
+                    let receipt = Receipt::new(
+                        sale,
+                        self.shop_name.clone(),
+                        self.message_settings.terminal_name.clone(),
+                    );
+                    let bytes = escpos::render(&receipt, printer_settings);
+
+                    spawn_local(async move {
+                        if bindings::print_escpos(bytes).await.as_bool() != Some(true) {
+                            warn!("Could not print receipt");
+                        }
+                    });
+                }
+
+                false
+            }
+            Msg::ShiftOpened(cashier) => {
+                let shift = Shift::open(cashier);
+                LocalStorage::set(SHIFT_KEY, &shift).ok();
+                self.shift = Some(shift);
+
+                true
+            }
+            Msg::ShiftClosed(dm_report) => {
+                let mut shift = match self.shift.take() {
+                    Some(shift) => shift,
+                    None => return false,
+                };
+                shift.closed_at = Some(sale::unix_time());
+                LocalStorage::delete(SHIFT_KEY);
+
+                let report = ZReport::new(&shift, &self.sales);
+                self.z_reports.push(report.clone());
+                LocalStorage::set(Z_REPORTS_KEY, &self.z_reports).ok();
+
+                if dm_report {
+                    let app = self.clone();
+                    spawn_local(async move {
+                        if let (Some(nostr_client), Some(nostr_rec)) = (
+                            app.nostr_client.lock().await.clone(),
+                            app.nostr_receice_pubkey.clone(),
+                        ) {
+                            if let Err(err) = nostr_client
+                                .send_direct_msg(nostr_rec.public_key(), report.to_text())
+                                .await
+                            {
+                                warn!("Could not send Z-report {:?}", err);
+                            }
+                        }
+                    });
+                }
+
+                true
+            }
+            Msg::PrinterSet(printer_settings) => {
+                match &printer_settings {
+                    Some(settings) => {
+                        LocalStorage::set(PRINTER_KEY, settings).ok();
+                    }
+                    None => LocalStorage::delete(PRINTER_KEY),
+                }
+                self.printer_settings = printer_settings;
+                self.view = View::Settings;
+
+                true
+            }
+            Msg::AutoReturnSet(auto_return) => {
+                match auto_return {
+                    Some(seconds) => {
+                        LocalStorage::set(AUTO_RETURN_KEY, seconds).ok();
+                    }
+                    None => LocalStorage::delete(AUTO_RETURN_KEY),
+                }
+                self.auto_return = auto_return;
+
+                false
+            }
+            Msg::MessageSettingsSet(message_settings) => {
+                LocalStorage::set(MESSAGE_KEY, &message_settings).ok();
+                self.message_settings = message_settings;
+                self.view = View::Settings;
+
+                true
+            }
+            Msg::SweepScheduleSet(sweep_schedule) => {
+                match &sweep_schedule {
+                    Some(schedule) => {
+                        LocalStorage::set(SWEEP_KEY, schedule).ok();
+                    }
+                    None => LocalStorage::delete(SWEEP_KEY),
+                }
+                self.sweep_schedule = sweep_schedule;
+
+                true
+            }
+            Msg::SweepTick => {
+                let last_sweep: u64 = LocalStorage::get(LAST_SWEEP_KEY).unwrap_or_default();
+                let hour = js_sys::Date::new_0().get_hours() as u8;
+
+                if let Some(schedule) = self.sweep_schedule {
+                    if schedule.is_due(last_sweep, sale::unix_time(), hour) {
+                        ctx.link().send_message(Msg::Sweep);
+                    }
+                }
+
+                false
+            }
+            Msg::Sweep => {
+                let balance_cb = ctx.link().callback(Msg::BalanceLoaded);
+                let app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app.sweep(balance_cb).await {
+                        warn!("Could not sweep terminal wallet {:?}", err);
+                    }
+                });
+
+                false
+            }
+            Msg::BalanceLoaded(amount) => {
+                self.balance_amount = Some(amount);
+
+                true
+            }
+            Msg::WalletLoadFailed(err) => {
+                self.wallet_error = Some(err);
+
+                true
+            }
+            Msg::SeedLoaded(seed) => {
+                self.seed = Some(seed);
+
+                true
+            }
+            Msg::SeedBackedUp => {
+                self.seed_backed_up = true;
+                LocalStorage::set(SEED_BACKED_UP_KEY, true).ok();
+                self.restore_status = None;
+                self.view = self.app_view();
+
+                true
+            }
+            Msg::Restore(mnemonic) => {
+                match mnemonic.parse::<Seed>() {
+                    Ok(seed) => {
+                        self.restore_status = Some("Restoring...".to_string());
+
+                        let seed_cb = ctx.link().callback(Msg::SeedLoaded);
+                        let previous_cb = ctx.link().callback(Msg::PreviousSeedsLoaded);
+                        let restored_cb = ctx.link().callback(Msg::Restored);
+                        let current = self.seed.clone();
+                        let app = self.clone();
+                        spawn_local(async move {
+                            // The current seed may still be needed to restore its own proofs
+                            match storage::replace_seed(current, &seed).await {
+                                Ok(previous) => previous_cb.emit(previous),
+                                Err(err) => {
+                                    warn!("Could not save wallet seed {:?}", err);
+                                    restored_cb.emit(
+                                        "Could not save the backup words, nothing was restored"
+                                            .to_string(),
+                                    );
+                                    return;
+                                }
+                            }
+                            seed_cb.emit(seed.clone());
+
+                            if let Err(err) = app.restore(seed, restored_cb.clone()).await {
+                                warn!("Could not restore {:?}", err);
+                                restored_cb.emit("Restore failed".to_string());
+                            }
+                        });
+                    }
+                    Err(_) => self.restore_status = Some("Invalid backup words".to_string()),
+                }
+
+                true
+            }
+            Msg::Restored(status) => {
+                self.restore_status = Some(status);
+
+                true
+            }
+            Msg::PreviousSeedsLoaded(previous_seeds) => {
+                self.previous_seeds = previous_seeds;
+
+                true
+            }
+            Msg::Settings => {
+                if self.is_unlocked() {
+                    self.view = View::Settings;
+                } else {
+                    self.pin_error = None;
+                    self.view = View::Unlock;
+                }
+                true
+            }
+            Msg::Unlock(pin) => {
+                if self.pin_checking {
+                    return true;
+                }
+
+                let pins = self.unlock_pins();
+                if pins.is_empty() {
+                    self.pin_error = Some("No PIN configured".to_string());
+                    return true;
+                }
+
+                // Locked accounts are not tried
+                let now = sale::unix_time();
+                let pins: Vec<(String, PinHash)> = pins
+                    .into_iter()
+                    .filter(|(account, _)| self.pin_lockouts.locked_for(account, now).is_none())
+                    .collect();
+                if pins.is_empty() {
+                    return true;
+                }
+
+                self.pin_checking = true;
+                let unlocked_cb = ctx.link().callback(Msg::Unlocked);
+                spawn_local(async move {
+                    let tried = pins.iter().map(|(account, _)| account.clone()).collect();
+                    for (account, pin_hash) in pins {
+                        match pin_hash.verify(&pin).await {
+                            Ok(true) => return unlocked_cb.emit((tried, Some(account))),
+                            Ok(false) => (),
+                            Err(err) => warn!("Could not verify PIN {:?}", err),
+                        }
+                    }
+                    unlocked_cb.emit((tried, None));
+                });
+
+                false
+            }
+            Msg::Unlocked((tried, unlocked)) => {
+                let now = sale::unix_time();
+                self.pin_checking = false;
+
+                match unlocked {
+                    Some(account) => {
+                        self.pin_lockouts.reset(&account);
+                        self.pin_error = None;
+                        self.admin_session = Some(AdminSession::start(now));
+                        self.view = View::Settings;
+                    }
+                    None => {
+                        for account in tried {
+                            self.pin_lockouts.failed(&account, now);
+                        }
+                        self.pin_error = Some("Wrong PIN".to_string());
+                    }
+                }
+                self.save_pin_lockouts();
+
+                true
+            }
+            Msg::PinSet(pin) => {
+                if pin.is_none() {
+                    if let Err(err) = admin::check_remove_pin(&self.cashiers) {
+                        warn!("Could not remove PIN {:?}", err);
+                        return false;
+                    }
+                }
+
+                let pin_saved_cb = ctx.link().callback(Msg::PinSaved);
+                let app = self.clone();
+                spawn_local(async move {
+                    let admin_pin = match &pin {
+                        Some(pin) => match PinHash::new(pin).await {
+                            Ok(admin_pin) => Some(admin_pin),
+                            Err(err) => return warn!("Could not hash PIN {:?}", err),
+                        },
+                        None => None,
+                    };
+
+                    match app.protect_storage(pin.as_deref()).await {
+                        Ok(()) => pin_saved_cb.emit(admin_pin),
+                        Err(err) => warn!("Could not encrypt storage with PIN {:?}", err),
+                    }
+                });
+
+                false
+            }
+            Msg::PinSaved(admin_pin) => {
+                self.admin_session = admin_pin
+                    .as_ref()
+                    .map(|_| AdminSession::start(sale::unix_time()));
+                let pin = admin_pin.clone();
+                spawn_local(async move {
+                    if let Err(err) = wallet_store::update_config(|c| c.admin_pin = pin).await {
+                        warn!("Could not save admin PIN {:?}", err);
+                    }
+                });
+                self.admin_pin = admin_pin;
+                self.view = View::Settings;
+
+                true
+            }
+            Msg::AutoLockTick => {
+                if self.is_unlocked() {
+                    // Keep the lockout countdown on the PIN screens current
+                    return matches!(self.view, View::Unlock | View::SignIn)
+                        && !self.pin_lockouts.locked(sale::unix_time()).is_empty();
+                }
+
+                self.admin_session = None;
+                if !self.is_public_view() {
+                    self.view = self.app_view();
+                }
+
+                true
+            }
+            Msg::SetPinView => {
+                self.view = View::SetPin;
+
+                true
+            }
+            Msg::SignIn((name, pin)) => {
+                if self.pin_checking
+                    || self
+                        .pin_lockouts
+                        .locked_for(&name, sale::unix_time())
+                        .is_some()
+                {
+                    return true;
+                }
+
+                let cashier = match self.cashiers.iter().find(|c| c.name == name).cloned() {
+                    Some(cashier) => cashier,
+                    None => {
+                        self.pin_error = Some(format!("No account named {}", name));
+                        return true;
+                    }
+                };
+
+                self.pin_checking = true;
+                let signed_in_cb = ctx.link().callback(Msg::SignedIn);
+                spawn_local(async move {
+                    let signed_in = match cashier.pin.verify(&pin).await {
+                        Ok(signed_in) => signed_in,
+                        Err(err) => {
+                            warn!("Could not verify PIN {:?}", err);
+                            false
+                        }
+                    };
+                    signed_in_cb.emit((cashier, signed_in));
+                });
+
+                false
+            }
+            Msg::SignedIn((cashier, signed_in)) => {
+                let now = sale::unix_time();
+                self.pin_checking = false;
+
+                if signed_in {
+                    self.pin_lockouts.reset(&cashier.name);
+                    self.pin_error = None;
+                    self.cashier = Some(cashier);
+                    self.view = self.app_view();
+                } else {
+                    self.pin_lockouts.failed(&cashier.name, now);
+                    self.pin_error = Some("Wrong PIN".to_string());
+                }
+                self.save_pin_lockouts();
+
+                true
+            }
+            Msg::SignOut => {
+                self.cashier = None;
+                self.admin_session = None;
+                self.view = self.app_view();
+
+                true
+            }
+            Msg::CashierAdded((name, role, pin)) => {
+                if let Err(err) = admin::check_add(&self.cashiers, role, self.admin_pin.is_some()) {
+                    warn!("Could not add cashier {:?}", err);
+                    return false;
+                }
+
+                let cashier_saved_cb = ctx.link().callback(Msg::CashierSaved);
+                spawn_local(async move {
+                    match PinHash::new(&pin).await {
+                        Ok(pin) => cashier_saved_cb.emit(Cashier { name, role, pin }),
+                        Err(err) => warn!("Could not hash PIN {:?}", err),
+                    }
+                });
+
+                false
+            }
+            Msg::CashierSaved(cashier) => {
+                // Checked again as the list may have changed while hashing
+                if let Err(err) =
+                    admin::check_add(&self.cashiers, cashier.role, self.admin_pin.is_some())
+                {
+                    warn!("Could not add cashier {:?}", err);
+                    return false;
+                }
+
+                self.cashiers.push(cashier);
+                self.save_cashiers();
+
+                true
+            }
+            Msg::CashierRemoved(name) => {
+                if let Err(err) =
+                    admin::check_remove(&self.cashiers, &name, self.admin_pin.is_some())
+                {
+                    warn!("Could not remove cashier {:?}", err);
+                    return false;
+                }
+
+                self.cashiers.retain(|c| c.name != name);
+                self.save_cashiers();
+
+                if self.cashier.as_ref().is_some_and(|c| c.name == name) {
+                    self.cashier = None;
+                }
+
+                true
+            }
+            Msg::MerchantSet(keys) => {
+                self.merchant = keys.map(|k| k.public_key());
+
+                let merchant = self.merchant.map(|k| k.to_string());
+                spawn_local(async move {
+                    if let Err(err) = wallet_store::update_config(|c| c.merchant = merchant).await {
+                        warn!("Could not save merchant key {:?}", err);
+                    }
+                });
+                // Only updates sent after pairing apply
+                self.remote_config_at = sale::unix_time();
+                LocalStorage::set(REMOTE_CONFIG_AT_KEY, self.remote_config_at).ok();
+
+                true
+            }
+            Msg::RemoteConfigReceived(updates) => {
+                let updates = updates
+                    .into_iter()
+                    .filter(|u| Some(u.author) == self.merchant)
+                    .collect();
+
+                let applied = match remote_config::merge_updates(updates, self.remote_config_at) {
+                    Some(applied) => applied,
+                    None => return false,
+                };
+
+                // Saved whole first so a reload can't leave it half applied, and
+                // only counted as received once saved
+                let saved_cb = ctx.link().callback(Msg::RemoteConfigSaved);
+                spawn_local(async move {
+                    match wallet_store::set_encrypted(REMOTE_CONFIG_KEY, &applied).await {
+                        Ok(()) => saved_cb.emit(applied),
+                        Err(err) => warn!("Could not save remote config {:?}", err),
+                    }
+                });
+
+                false
+            }
+            Msg::RemoteConfigSaved(applied) => {
+                // A newer update was saved meanwhile
+                if applied.created_at <= self.remote_config_at {
+                    return false;
+                }
+
+                self.remote_config_at = applied.created_at;
+                self.apply_remote_config(ctx, applied.config);
+                LocalStorage::set(REMOTE_CONFIG_AT_KEY, self.remote_config_at).ok();
+
+                true
+            }
+            Msg::ExportConfig => {
+                let bundle = ConfigBundle::new(self.current_config());
+                let exported_cb = ctx.link().callback(Msg::ConfigExported);
+                spawn_local(async move {
+                    let event = match storage::pos_keys().await {
+                        Ok(keys) => bundle.to_event(&keys),
+                        Err(err) => Err(err),
+                    };
+
+                    match event {
+                        Ok(event) => exported_cb.emit(event.as_json()),
+                        Err(err) => warn!("Could not sign config bundle {:?}", err),
+                    }
+                });
+
+                false
+            }
+            Msg::ConfigExported(bundle) => {
+                self.config_bundle = Some(bundle);
+
+                true
+            }
+            Msg::ConfigImported(config) => {
+                self.apply_remote_config(ctx, config);
+                self.view = self.app_view();
+
+                true
+            }
+            Msg::SignerTrusted(key) => {
+                if !self.trusted_signers.contains(&key) {
+                    self.trusted_signers.push(key);
+                }
+
+                let trusted_signers = self.trusted_signers.iter().map(|k| k.to_string()).collect();
+                spawn_local(async move {
+                    if let Err(err) =
+                        wallet_store::update_config(|c| c.trusted_signers = trusted_signers).await
+                    {
+                        warn!("Could not save trusted signers {:?}", err);
+                    }
+                });
+
+                false
+            }
+            Msg::ConfigTransferView => {
+                self.config_bundle = None;
+                self.view = View::ConfigTransfer;
+
+                true
+            }
+            Msg::DashboardView => {
+                self.dashboard_error = None;
+                self.redeem_status = None;
+                self.view = View::Dashboard;
+
+                let loaded_cb = ctx.link().callback(Msg::ClaimedTokensLoaded);
+                let statuses_cb = ctx.link().callback(Msg::TokenStatusesLoaded);
+                spawn_local(async move {
+                    match wallet_store::get_encrypted(CLAIMED_TOKENS_KEY).await {
+                        Ok(tokens) => loaded_cb.emit(tokens.unwrap_or_default()),
+                        Err(err) => warn!("Could not load claimed tokens {:?}", err),
+                    }
+                    match dashboard::load_statuses().await {
+                        Ok(statuses) => statuses_cb.emit(statuses),
+                        Err(err) => warn!("Could not load token status {:?}", err),
+                    }
+                });
+
+                true
+            }
+            Msg::DashboardLogin((relays, login)) => {
+                let logged_in_cb = ctx.link().callback(Msg::DashboardLoggedIn);
+                let failed_cb = ctx.link().callback(Msg::DashboardFailed);
+                let item_cb = ctx.link().callback(Msg::FeedItemReceived);
+                let session = self.dashboard_session.clone();
+                let terminals = self.terminal_keys();
+                spawn_local(async move {
+                    // Held until logged in so logins don't overlap
+                    let mut current = session.lock().await;
+                    if let Some(previous) = current.take() {
+                        previous.shutdown().await;
+                    }
+
+                    let (signer, merchant) = match Signer::login(login).await {
+                        Ok(login) => login,
+                        Err(err) => {
+                            warn!("Could not log in to dashboard {:?}", err);
+                            return failed_cb.emit(err.to_string());
+                        }
+                    };
+
+                    // Only reads, so the client key is never used to sign
+                    let client = Client::new(&Keys::generate());
+                    let relays: Vec<String> = relays.into_iter().map(|r| r.to_string()).collect();
+                    if let Err(err) = client.add_relays(relays).await {
+                        warn!("Could not add dashboard relays {:?}", err);
+                        return failed_cb.emit(err.to_string());
+                    }
+                    client.connect().await;
+
+                    let new_session = Session { client, signer };
+                    *current = Some(new_session.clone());
+                    drop(current);
+                    logged_in_cb.emit(merchant);
+
+                    let since = sale::unix_time().saturating_sub(dashboard::FEED_SECS);
+                    if let Err(err) =
+                        dashboard::listen(new_session, merchant, terminals, since, item_cb).await
+                    {
+                        warn!("Could not listen for sales {:?}", err);
+                    }
+                });
+
+                false
+            }
+            Msg::DashboardLoggedIn(merchant) => {
+                self.dashboard_merchant = Some(merchant);
+                self.dashboard_error = None;
+                self.feed.clear();
+
+                true
+            }
+            Msg::DashboardFailed(err) => {
+                self.dashboard_error = Some(err);
+
+                true
+            }
+            Msg::FeedItemReceived(mut item) => {
+                if self.feed.iter().any(|i| i.id == item.id) {
+                    return false;
+                }
+                item.status = dashboard::token_status(&item.token, &self.token_statuses);
+
+                let at = self
+                    .feed
+                    .partition_point(|i| i.created_at <= item.created_at);
+                self.feed.insert(at, item);
+
+                matches!(self.view, View::Dashboard)
+            }
+            Msg::Redeem => {
+                let tokens: HashSet<String> = self
+                    .feed
+                    .iter()
+                    .filter(|i| i.status.is_none())
+                    .map(|i| i.token.clone())
+                    .collect();
+                if tokens.is_empty() {
+                    return false;
+                }
+                self.redeem_status = Some("Redeeming...".to_string());
+
+                let redeemed_cb = ctx.link().callback(Msg::Redeemed);
+                let failed_cb = ctx.link().callback(Msg::RedeemFailed);
+                spawn_local(async move {
+                    match dashboard::redeem(tokens.into_iter().collect()).await {
+                        Ok(redemption) => redeemed_cb.emit(redemption),
+                        Err(err) => {
+                            warn!("Could not redeem {:?}", err);
+                            failed_cb.emit(err.to_string());
+                        }
+                    }
+                });
+
+                true
+            }
+            Msg::Redeemed(redemption) => {
+                // Already saved by the redemption
+                dashboard::add_statuses(&mut self.token_statuses, &redemption.statuses);
+                for item in self.feed.iter_mut() {
+                    item.status = dashboard::token_status(&item.token, &self.token_statuses);
+                }
+
+                self.redeem_status = Some(redemption.status());
+                self.claimed_tokens.extend(redemption.tokens);
+
+                true
+            }
+            Msg::RedeemFailed(err) => {
+                self.redeem_status = Some(format!("Could not redeem: {}", err));
+
+                true
+            }
+            Msg::ClaimedTokensLoaded(tokens) => {
+                // Keeps tokens claimed before the store was read
+                for token in tokens.into_iter().rev() {
+                    if !self.claimed_tokens.contains(&token) {
+                        self.claimed_tokens.insert(0, token);
+                    }
+                }
+
+                true
+            }
+            Msg::TokenStatusesLoaded(statuses) => {
+                // Statuses from a redemption since are newer
+                for (token, mints) in statuses {
+                    let current = self.token_statuses.entry(token).or_default();
+                    for (mint_url, status) in mints {
+                        current.entry(mint_url).or_insert(status);
+                    }
+                }
+                for item in self.feed.iter_mut() {
+                    item.status = dashboard::token_status(&item.token, &self.token_statuses);
+                }
+
+                true
+            }
+            Msg::ClaimedTokensCleared => {
+                self.claimed_tokens.clear();
+                LocalStorage::delete(CLAIMED_TOKENS_KEY);
+
+                true
+            }
+            Msg::SetRemoteView => {
+                self.view = View::SetRemote;
+
+                true
+            }
+            Msg::CashiersView => {
+                self.view = View::Cashiers;
+
+                true
+            }
+            Msg::SetPubkeyView => {
+                self.view = View::SetRecKey;
+                true
+            }
+            Msg::SetMintView => {
+                self.view = View::SetMint;
+                true
+            }
+            Msg::AddRelayView => {
+                self.view = View::SetRelays;
+                true
+            }
+            Msg::SetSplitsView => {
+                self.view = View::SetSplits;
+                true
+            }
+            Msg::SetShopView => {
+                self.view = View::SetShop;
+
+                true
+            }
+            Msg::ShiftView => {
+                self.view = View::Shift;
+
+                true
+            }
+            Msg::ExportView => {
+                self.view = View::Export;
+
+                true
+            }
+            Msg::SalesView => {
+                self.refund_status = None;
+                self.view = View::Sales;
+
+                true
+            }
+            Msg::RefundRequested((hash, amount)) => {
+                if self.refunding {
+                    return false;
+                }
+                self.refunding = true;
+                self.refund_status = Some("Refunding...".to_string());
+
+                let invoice_cb = ctx.link().callback(Msg::RefundInvoice);
+                let refunded_cb = ctx.link().callback(Msg::Refunded);
+                let failed_cb = ctx.link().callback(Msg::RefundFailed);
+                let app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app.refund(hash, amount, invoice_cb, refunded_cb).await {
+                        warn!("Could not refund {:?}", err);
+                        failed_cb.emit(err.to_string());
+                    }
+                });
+
+                true
+            }
+            Msg::RefundInvoice((hash, amount, quote)) => {
+                self.refund_status = None;
+                self.refund_invoice = Some((hash.clone(), quote.clone()));
+
+                let (cancel_tx, cancel) = oneshot::channel();
+                self.quote_watchers
+                    .lock()
+                    .unwrap()
+                    .insert(quote.id.clone(), cancel_tx);
+
+                let refunded_cb = ctx.link().callback(Msg::Refunded);
+                let failed_cb = ctx.link().callback(Msg::RefundFailed);
+                let app = self.clone();
+                spawn_local(async move {
+                    if let Err(err) = app
+                        .fund_refund(hash, amount, quote, cancel, refunded_cb)
+                        .await
+                    {
+                        warn!("Could not fund refund {:?}", err);
+                        failed_cb.emit(err.to_string());
+                    }
+                });
+
+                true
+            }
+            Msg::RefundInvoiceCancelled => {
+                if let Some((_, quote)) = self.refund_invoice.take() {
+                    if let Some(cancel) = self.quote_watchers.lock().unwrap().remove(&quote.id) {
+                        cancel.send(()).ok();
+                    }
+                }
+                self.refunding = false;
+
+                true
+            }
+            Msg::Refunded((hash, refund, stored)) => {
+                if stored {
+                    self.refund_tokens.insert(refund.token_key(&hash));
+                }
+                if let Some(sale) = self.sales.iter_mut().find(|s| s.hash == hash) {
+                    sale.refunds.push(refund);
+                }
+                self.save_sales();
+                self.publish_sales(vec![hash]);
+                self.refund_status = None;
+                self.refund_invoice = None;
+                self.refunding = false;
+
+                true
+            }
+            Msg::RefundFailed(err) => {
+                self.refund_status = Some(err);
+                self.refund_invoice = None;
+                self.refunding = false;
+
+                true
+            }
+            Msg::PinLockoutsLoaded(lockouts) => {
+                self.pin_lockouts = lockouts;
+
+                false
+            }
+            Msg::RefundTokensLoaded(tokens) => {
+                // Tokens of older versions are still in the sale record
+                let mut plaintext = HashMap::new();
+                for sale in &mut self.sales {
+                    for refund in &mut sale.refunds {
+                        let key = refund.token_key(&sale.hash);
+                        match tokens.get(&key) {
+                            Some(token) => refund.token = token.clone(),
+                            None if !refund.token.is_empty() => {
+                                plaintext.insert(key, refund.token.clone());
+                            }
+                            None => (),
+                        }
+                    }
+                }
+                self.refund_tokens = tokens.into_keys().collect();
+
+                if !plaintext.is_empty() {
+                    let saved_cb = ctx.link().callback(Msg::RefundTokensSaved);
+                    spawn_local(async move {
+                        let keys = plaintext.keys().cloned().collect();
+                        match storage::save_refund_tokens(plaintext).await {
+                            Ok(()) => saved_cb.emit(keys),
+                            Err(err) => warn!("Could not encrypt refund tokens {:?}", err),
+                        }
+                    });
+                }
+
+                true
+            }
+            Msg::RefundTokensSaved(keys) => {
+                self.refund_tokens.extend(keys);
+                self.save_sales();
+
+                false
+            }
+            Msg::SalesSyncView => {
+                self.sync_status = None;
+                self.view = View::SalesSync;
+
+                true
+            }
+            Msg::SyncKeySet(keys) => {
+                self.sync_keys = keys;
+                self.sync_status = None;
+
+                let sync_key = self
+                    .sync_keys
+                    .as_ref()
+                    .and_then(|k| k.secret_key().ok())
+                    .map(|k| k.display_secret().to_string());
+                spawn_local(async move {
+                    if let Err(err) = wallet_store::update_config(|c| c.sync_key = sync_key).await {
+                        warn!("Could not save sync key {:?}", err);
+                    }
+                });
+
+                if self.sync_keys.is_some() {
+                    // Sales made before joining show up on the other terminals too
+                    self.publish_sales(self.sales.iter().map(|s| s.hash.clone()).collect());
+                } else {
+                    self.synced_sales.clear();
+                    LocalStorage::delete(SYNCED_SALES_KEY);
+                }
+
+                true
+            }
+            Msg::SyncSales => {
+                let sync_keys = match &self.sync_keys {
+                    Some(sync_keys) => sync_keys.clone(),
+                    None => return false,
+                };
+                self.sync_status = Some("Pulling sales...".to_string());
+
+                let synced_cb = ctx.link().callback(Msg::SalesSynced);
+                let failed_cb = ctx.link().callback(Msg::SyncFailed);
+                let nostr_client = self.nostr_client.clone();
+                spawn_local(async move {
+                    let nostr_client = match nostr_client.lock().await.clone() {
+                        Some(nostr_client) => nostr_client,
+                        None => return failed_cb.emit("Not connected to relays".to_string()),
+                    };
+
+                    match sale_sync::pull(&nostr_client, &sync_keys).await {
+                        Ok(sales) => synced_cb.emit(sales),
+                        Err(err) => {
+                            warn!("Could not pull sales {:?}", err);
+                            failed_cb.emit(err.to_string());
+                        }
+                    }
+                });
+
+                true
+            }
+            Msg::SalesSynced(sales) => {
+                let pulled = sales.len();
+                for sale in sales {
+                    match self.synced_sales.iter_mut().find(|s| s.hash == sale.hash) {
+                        Some(synced) => *synced = sale,
+                        None => self.synced_sales.push(sale),
+                    }
+                }
+                self.synced_sales.sort_by_key(|s| s.created_at);
+                LocalStorage::set(SYNCED_SALES_KEY, &self.synced_sales).ok();
+                self.sync_status = Some(format!("Pulled {} sales", pulled));
+
+                true
+            }
+            Msg::SyncFailed(err) => {
+                self.sync_status = Some(err);
+
+                true
+            }
+            Msg::SetPrinterView => {
+                self.view = View::SetPrinter;
+
+                true
+            }
+            Msg::SetMessageView => {
+                self.view = View::SetMessage;
+
+                true
+            }
+            Msg::SetMeltView => {
+                self.view = View::SetMelt;
+                true
+            }
+            Msg::BackupView => {
+                let previous_cb = ctx.link().callback(Msg::PreviousSeedsLoaded);
+                spawn_local(async move {
+                    match wallet_store::get_encrypted(PREVIOUS_SEEDS_KEY).await {
+                        Ok(previous) => previous_cb.emit(previous.unwrap_or_default()),
+                        Err(err) => warn!("Could not load previous seeds {:?}", err),
+                    }
+                });
+
+                self.view = View::Backup;
+                true
+            }
+            Msg::TerminalWalletView => {
+                let balance_cb = ctx.link().callback(Msg::BalanceLoaded);
+                let app = self.clone();
+                spawn_local(async move {
+                    app.get_balance(balance_cb).await;
+                });
+
+                self.balance_amount = None;
+                self.view = View::TerminalWallet;
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        log::debug!("{:?}", self.view);
+        html! {
+            <main>
+
+                    {
+
+                match &self.view {
+                    View::Pos => {
+                        let amount_cb = ctx.link().callback(Msg::AmountSet);
+                        let settings_cb = ctx.link().callback(|_| Msg::Settings);
+                        let sign_out_cb = ctx.link().callback(|_| Msg::SignOut);
+
+                        html!{
+                            <>
+                            <div style="display: flex; justify-content: flex-end;">
+                              {
+                                  if let Some(cashier) = &self.cashier {
+                                      html! {
+                                          <>
+                                          <p class="px-6 py-2 mt-2 dark:text-gray-400">{&cashier.name}</p>
+                                          <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={sign_out_cb}>{"Sign out"}</button>
+                                          </>
+                                      }
+                                  } else {
+                                      html! {}
+                                  }
+                              }
+                              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={settings_cb.clone()}>{"Settings"}</button>
+                            </div>
+                            <Pos {amount_cb} />
+                            </>
+                        }
+
+                    }
+                    View::SetMint => {
+                        let mint_set_cb = ctx.link().callback(Msg::MintUrlSet);
+                        let import_cb = ctx.link().callback(|_| Msg::ConfigTransferView);
+                        let dashboard_cb = ctx.link().callback(|_| Msg::DashboardView);
+
+                        html! {
+                            <>
+                             <SetMint {mint_set_cb} />
+                             <div class="flex justify-center">
+                               <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={import_cb}>{"Import config"}</button>
+                               <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={dashboard_cb}>{"Merchant dashboard"}</button>
+                             </div>
+                            </>
+                        }
+                    }
+                    View::SetRecKey => {
+                        let set_rec_key = ctx.link().callback(Msg::NostrRecKeySet);
+
+                        html!{
                         <>
                             <SetRecKey logged_in_callback={set_rec_key}/>
                         </>
                         }
                     }
-                    View::Invoice((invoice, fiat_value)) => {
+                    View::Invoice((quote, fiat_value, payment_request)) => {
                         let home_cb = ctx.link().callback(|_| Msg::Home);
+                        let regenerate_cb = ctx.link().callback(|_| Msg::RegenerateInvoice);
+                        let qr_payload_cb = ctx.link().callback(Msg::QrPayloadSet);
+                        let expiry = quote.expiry.unwrap_or_else(|| mint_api::invoice_expiry(&quote.invoice));
                         html!{
-                            <InvoiceView invoice={invoice.clone()} fiat_value={fiat_value.clone()} {home_cb} />
+                            <InvoiceView key={quote.id.clone()} invoice={quote.invoice.clone()} payment_request={payment_request.clone()} qr_payload={self.qr_payload} {qr_payload_cb} onchain_address={self.onchain_address.clone()} fiat_value={fiat_value.clone()} {expiry} {regenerate_cb} {home_cb} />
                         }
                     }
-                    View::InvoicePaid => {
+                    View::InvoicePaid(hash) => {
                         let home_cb = ctx.link().callback(|_| Msg::Home);
+                        let sale = self.sales.iter().find(|s| &s.hash == hash).cloned();
+                        let receipt = self
+                            .receipt
+                            .as_ref()
+                            .filter(|(receipt_hash, _)| receipt_hash == hash)
+                            .map(|(_, receipt)| receipt.clone());
+                        let print_hash = hash.clone();
+                        let print_cb = self
+                            .printer_settings
+                            .map(|_| ctx.link().callback(move |_| Msg::Print(print_hash.clone())));
+                        let receipt_hash = hash.clone();
+                        let receipt_cb = ctx.link().callback(move |_| Msg::Receipt(receipt_hash.clone()));
                         html!{
-                            <InvoicePaid {home_cb} />
+                            <InvoicePaid {sale} auto_return={self.auto_return} {receipt} {receipt_cb} {print_cb} {home_cb} />
                         }
                     }
                     View::SetRelays => {
@@ -409,14 +2961,228 @@ impl Component for App {
                             <SetRelays {add_relay_cb} {relays_set_cb} />
                         }
                     }
+                    View::SetSplits => {
+                        let add_split_cb = ctx.link().callback(Msg::AddSplit);
+                        let clear_splits_cb = ctx.link().callback(|_| Msg::ClearSplits);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetSplits splits={self.splits.clone()} {add_split_cb} {clear_splits_cb} {home_cb} />
+                        }
+                    }
+                    View::SetMelt => {
+                        let melt_set_cb = ctx.link().callback(Msg::MeltSettingsSet);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetMelt melt_settings={self.melt_settings.clone()} {melt_set_cb} {home_cb} />
+                        }
+                    }
+                    View::SetShop => {
+                        let shop_name_cb = ctx.link().callback(Msg::ShopNameSet);
+                        let auto_return_cb = ctx.link().callback(Msg::AutoReturnSet);
+                        let onchain_address_cb = ctx.link().callback(Msg::OnchainAddressSet);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetShop shop_name={self.shop_name.clone()} {shop_name_cb} auto_return={self.auto_return} {auto_return_cb} onchain_address={self.onchain_address.clone()} {onchain_address_cb} {home_cb} />
+                        }
+                    }
+                    View::SetMessage => {
+                        let message_settings_cb = ctx.link().callback(Msg::MessageSettingsSet);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetMessage message_settings={self.message_settings.clone()} {message_settings_cb} {home_cb} />
+                        }
+                    }
+                    View::SetPrinter => {
+                        let printer_set_cb = ctx.link().callback(Msg::PrinterSet);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetPrinter printer_settings={self.printer_settings} {printer_set_cb} {home_cb} />
+                        }
+                    }
+                    View::Shift => {
+                        let open_cb = ctx.link().callback(Msg::ShiftOpened);
+                        let close_cb = ctx.link().callback(Msg::ShiftClosed);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+                        let report = match &self.shift {
+                            Some(shift) => Some(ZReport::new(shift, &self.sales)),
+                            None => self.z_reports.last().cloned(),
+                        };
+                        let combined = match &report {
+                            Some(report) if !self.synced_sales.is_empty() => {
+                                Some(ZReport::new(&report.shift, &self.all_sales()))
+                            }
+                            _ => None,
+                        };
+
+                        html!{
+                            <ShiftView shift={self.shift.clone()} {report} {combined} {open_cb} {close_cb} {home_cb} />
+                        }
+                    }
+                    View::Export => {
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <ExportView sales={self.all_sales()} {home_cb} />
+                        }
+                    }
+                    View::Dashboard => {
+                        let login_cb = ctx.link().callback(Msg::DashboardLogin);
+                        let redeem_cb = ctx.link().callback(|_| Msg::Redeem);
+                        let clear_cb = ctx.link().callback(|_| Msg::ClaimedTokensCleared);
+                        let home_cb = ctx.link().callback(|_| Msg::Home);
+                        let merchant = self
+                            .dashboard_merchant
+                            .and_then(|k| k.to_bech32().ok());
+                        let relays = self.relays.iter().cloned().collect::<Vec<_>>();
+
+                        html!{
+                            <Dashboard {merchant} {relays} feed={self.feed.clone()} error={self.dashboard_error.clone()} claimed_tokens={self.claimed_tokens.clone()} redeem_status={self.redeem_status.clone()} {login_cb} {redeem_cb} {clear_cb} {home_cb} />
+                        }
+                    }
+                    View::SalesSync => {
+                        let sync_key_cb = ctx.link().callback(Msg::SyncKeySet);
+                        let pull_cb = ctx.link().callback(|_| Msg::SyncSales);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+                        let sync_key = self
+                            .sync_keys
+                            .as_ref()
+                            .and_then(|k| k.secret_key().ok())
+                            .and_then(|k| k.to_bech32().ok());
+
+                        html!{
+                            <SalesSync {sync_key} synced={self.synced_sales.len()} status={self.sync_status.clone()} {sync_key_cb} {pull_cb} {home_cb} />
+                        }
+                    }
+                    View::Loading => html! {},
+                    View::StorageLocked => {
+                        let unlock_cb = ctx.link().callback(Msg::StorageUnlock);
+                        let locked_for = self.storage_lockout.locked_for(sale::unix_time());
+
+                        html!{
+                            <Unlock {locked_for} error={self.pin_error.clone()} {unlock_cb} />
+                        }
+                    }
+                    View::Unlock => {
+                        let unlock_cb = ctx.link().callback(Msg::Unlock);
+                        let home_cb = ctx.link().callback(|_| Msg::Home);
+                        let accounts = self.unlock_pins().into_iter().map(|(account, _)| account).collect::<Vec<_>>();
+                        let locked_for = self.pin_lockouts.all_locked_for(&accounts, sale::unix_time());
+
+                        html!{
+                            <Unlock {locked_for} error={self.pin_error.clone()} {unlock_cb} home_cb={Some(home_cb)} />
+                        }
+                    }
+                    View::SignIn => {
+                        let sign_in_cb = ctx.link().callback(Msg::SignIn);
+                        let settings_cb = ctx.link().callback(|_| Msg::Settings);
+                        let names = self.cashiers.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+                        let locked = self.pin_lockouts.locked(sale::unix_time());
+
+                        html!{
+                            <SignIn {names} {locked} error={self.pin_error.clone()} {sign_in_cb} {settings_cb} />
+                        }
+                    }
+                    View::ConfigTransfer => {
+                        let export_cb = ctx.link().callback(|_| Msg::ExportConfig);
+                        let apply_cb = ctx.link().callback(Msg::ConfigImported);
+                        let trust_cb = ctx.link().callback(Msg::SignerTrusted);
+                        let trusted: Vec<XOnlyPublicKey> =
+                            self.merchant.iter().chain(&self.trusted_signers).copied().collect();
+                        // Also opened from the first setup screen
+                        let home_cb = match self.app_view() {
+                            View::Pos | View::SignIn => ctx.link().callback(|_| Msg::Settings),
+                            _ => ctx.link().callback(|_| Msg::Home),
+                        };
+
+                        html!{
+                            <ConfigTransfer bundle={self.config_bundle.clone()} current={self.current_config()} {trusted} {export_cb} {apply_cb} {trust_cb} {home_cb} />
+                        }
+                    }
+                    View::SetRemote => {
+                        let merchant_cb = ctx.link().callback(Msg::MerchantSet);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetRemote pos_pubkey={self.pos_pubkey} merchant={self.merchant} {merchant_cb} {home_cb} />
+                        }
+                    }
+                    View::Cashiers => {
+                        let add_cb = ctx.link().callback(Msg::CashierAdded);
+                        let remove_cb = ctx.link().callback(Msg::CashierRemoved);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <Cashiers cashiers={self.cashiers.clone()} admin_pin={self.admin_pin.is_some()} {add_cb} {remove_cb} {home_cb} />
+                        }
+                    }
+                    View::SetPin => {
+                        let pin_cb = ctx.link().callback(Msg::PinSet);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <SetPin pin_set={self.admin_pin.is_some()} cashiers={self.cashiers.clone()} {pin_cb} {home_cb} />
+                        }
+                    }
+                    View::Sales => {
+                        let refund_cb = ctx.link().callback(Msg::RefundRequested);
+                        let cancel_refund_cb = ctx.link().callback(|_| Msg::RefundInvoiceCancelled);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+                        let refund_invoice = self
+                            .refund_invoice
+                            .as_ref()
+                            .map(|(hash, quote)| (hash.clone(), quote.invoice.to_string()));
+
+                        html!{
+                            <SalesView sales={self.sales.clone()} refund_status={self.refund_status.clone()} refunding={self.refunding} {refund_invoice} {refund_cb} {cancel_refund_cb} {home_cb} />
+                        }
+                    }
+                    View::TerminalWallet => {
+                        let sweep_schedule_cb = ctx.link().callback(Msg::SweepScheduleSet);
+                        let sweep_cb = ctx.link().callback(|_| Msg::Sweep);
+                        let home_cb = ctx.link().callback(|_| Msg::Settings);
+
+                        html!{
+                            <TerminalWallet balance={self.balance_amount} load_error={self.wallet_error.clone()} sweep_schedule={self.sweep_schedule} {sweep_schedule_cb} {sweep_cb} {home_cb} />
+                        }
+                    }
+                    View::Backup => {
+                        let mnemonic = self.seed.as_ref().map(|s| s.mnemonic().to_string());
+                        let restore_cb = ctx.link().callback(Msg::Restore);
+                        let backed_up_cb = ctx.link().callback(|_| Msg::SeedBackedUp);
+
+                        html!{
+                            <Backup {mnemonic} previous_mnemonics={self.previous_seeds.clone()} restore_status={self.restore_status.clone()} {restore_cb} {backed_up_cb} />
+                        }
+                    }
                     View::Settings => {
                         let add_relay_cb = ctx.link().callback(|_| Msg::AddRelayView);
                         let set_pubkey_cb = ctx.link().callback(|_| Msg::SetPubkeyView);
                         let set_mint_cb = ctx.link().callback(|_| Msg::SetMintView);
+                        let set_splits_cb = ctx.link().callback(|_| Msg::SetSplitsView);
+                        let set_melt_cb = ctx.link().callback(|_| Msg::SetMeltView);
+                        let set_shop_cb = ctx.link().callback(|_| Msg::SetShopView);
+                        let set_message_cb = ctx.link().callback(|_| Msg::SetMessageView);
+                        let set_printer_cb = ctx.link().callback(|_| Msg::SetPrinterView);
+                        let shift_cb = ctx.link().callback(|_| Msg::ShiftView);
+                        let export_cb = ctx.link().callback(|_| Msg::ExportView);
+                        let sync_cb = ctx.link().callback(|_| Msg::SalesSyncView);
+                        let sales_cb = ctx.link().callback(|_| Msg::SalesView);
+                        let set_pin_cb = ctx.link().callback(|_| Msg::SetPinView);
+                        let cashiers_cb = ctx.link().callback(|_| Msg::CashiersView);
+                        let remote_cb = ctx.link().callback(|_| Msg::SetRemoteView);
+                        let transfer_cb = ctx.link().callback(|_| Msg::ConfigTransferView);
+                        let dashboard_cb = ctx.link().callback(|_| Msg::DashboardView);
+                        let wallet_cb = ctx.link().callback(|_| Msg::TerminalWalletView);
+                        let backup_cb = ctx.link().callback(|_| Msg::BackupView);
                         let home_cb = ctx.link().callback(|_| Msg::Home);
 
                         html! {
-                            <Settings {add_relay_cb} {set_pubkey_cb} {set_mint_cb} {home_cb} />
+                            <Settings {add_relay_cb} {set_pubkey_cb} {set_mint_cb} {set_splits_cb} {set_melt_cb} {set_shop_cb} {set_message_cb} {set_printer_cb} {shift_cb} {sales_cb} {export_cb} {sync_cb} {set_pin_cb} {cashiers_cb} {remote_cb} {transfer_cb} {dashboard_cb} {wallet_cb} {backup_cb} {home_cb} />
                         }
 
                     }
@@ -427,3 +3193,40 @@ impl Component for App {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn background_messages_do_not_extend_admin_session() {
+        let mut session = AdminSession::start(0);
+
+        for (now, msg) in [
+            (30, Msg::SweepTick),
+            (60, Msg::AutoLockTick),
+            (90, Msg::BalanceLoaded(Amount::ZERO)),
+            (110, Msg::SyncFailed("offline".to_string())),
+        ] {
+            if msg.is_user_input() {
+                session.touch(now);
+            }
+        }
+
+        assert!(!session.is_active(120));
+    }
+
+    #[test]
+    fn user_input_extends_admin_session() {
+        let mut session = AdminSession::start(0);
+
+        for (now, msg) in [(30, Msg::SweepTick), (100, Msg::SalesView)] {
+            if msg.is_user_input() {
+                session.touch(now);
+            }
+        }
+
+        assert!(session.is_active(120));
+        assert!(!session.is_active(220));
+    }
+}
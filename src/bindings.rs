@@ -8,5 +8,27 @@ extern "C" {
     #[wasm_bindgen]
     pub async fn encrypt_content(pubkey: String, content: String) -> JsValue;
     #[wasm_bindgen]
+    pub async fn decrypt_content(pubkey: String, content: String) -> JsValue;
+    #[wasm_bindgen]
     pub async fn sign_event(created_at: i64, content: String, pubkey: String) -> JsValue;
+    #[wasm_bindgen]
+    pub async fn encrypt_local(content: String) -> JsValue;
+    #[wasm_bindgen]
+    pub async fn decrypt_local(content: String) -> JsValue;
+    #[wasm_bindgen]
+    pub async fn storage_locked() -> JsValue;
+    #[wasm_bindgen]
+    pub async fn unlock_storage(pin: String) -> JsValue;
+    #[wasm_bindgen]
+    pub async fn storage_key_staged() -> JsValue;
+    #[wasm_bindgen]
+    pub async fn stage_storage_pin(pin: Option<String>) -> JsValue;
+    #[wasm_bindgen]
+    pub async fn commit_storage_pin() -> JsValue;
+    #[wasm_bindgen]
+    pub async fn print_escpos(bytes: Vec<u8>) -> JsValue;
+    #[wasm_bindgen]
+    pub async fn pbkdf2(pin: String, salt: String, iterations: u32) -> JsValue;
+    #[wasm_bindgen]
+    pub fn download(filename: &str, content: &str, mime: &str);
 }
@@ -0,0 +1,161 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub mnemonic: Option<String>,
+    /// Words replaced by earlier restores
+    pub previous_mnemonics: Vec<String>,
+    pub restore_status: Option<String>,
+    pub restore_cb: Callback<String>,
+    pub backed_up_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    RestoreSubmitted,
+    Confirmed,
+    Cancelled,
+}
+
+#[derive(Default)]
+pub struct Backup {
+    mnemonic_node_ref: NodeRef,
+    /// Words entered to restore, waiting for confirmation
+    confirming: Option<String>,
+}
+
+fn render_words(mnemonic: &str) -> Html {
+    mnemonic
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, word)| {
+            html! {
+                <p class="text-xl lg:text-lg">{format!("{}. {}", i + 1, word)}</p>
+            }
+        })
+        .collect::<Html>()
+}
+
+impl Backup {
+    fn view_confirm(&self, ctx: &Context<Self>) -> Html {
+        let confirm = ctx.link().callback(|_| Msg::Confirmed);
+        let cancel = ctx.link().callback(|_| Msg::Cancelled);
+
+        html! {
+            <>
+            <p class="mb-4 text-red-500">{"Restoring replaces the backup words above. Write them down before continuing, they are still needed for payments made with them."}</p>
+            <p class="mb-4 dark:text-gray-400">{"Tokens already sent to recipients are skipped when the sales are still on this terminal. Otherwise any they have not claimed yet are taken back to this terminal."}</p>
+            <div class="flex justify-center mt-8">
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={confirm}>{"Replace and restore"}</button>
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={cancel}>{"Cancel"}</button>
+            </div>
+            </>
+        }
+    }
+}
+
+impl Component for Backup {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::RestoreSubmitted => {
+                if let Some(mnemonic_input) = self.mnemonic_node_ref.cast::<HtmlInputElement>() {
+                    let mnemonic = mnemonic_input.value().trim().to_string();
+                    mnemonic_input.set_value("");
+
+                    let current = ctx.props().mnemonic.as_deref().unwrap_or_default();
+                    if mnemonic.split_whitespace().eq(current.split_whitespace()) {
+                        // Nothing is replaced when restoring the current words
+                        ctx.props().restore_cb.emit(mnemonic);
+                    } else if !mnemonic.is_empty() {
+                        self.confirming = Some(mnemonic);
+                    }
+                }
+
+                true
+            }
+            Msg::Confirmed => {
+                if let Some(mnemonic) = self.confirming.take() {
+                    ctx.props().restore_cb.emit(mnemonic);
+                }
+
+                true
+            }
+            Msg::Cancelled => {
+                self.confirming = None;
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let restore = ctx.link().callback(|_| Msg::RestoreSubmitted);
+
+        let words = match &ctx.props().mnemonic {
+            Some(mnemonic) => render_words(mnemonic),
+            None => html! { <p>{"Loading..."}</p> },
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Write down these words. They recover any payments lost from this terminal."}</p>
+              <div class="grid grid-cols-3 gap-2 mb-8">
+                { words }
+              </div>
+              {
+                  if ctx.props().previous_mnemonics.is_empty() {
+                      html! {}
+                  } else {
+                      html! {
+                          <>
+                          <p class="mb-4 dark:text-gray-400">{"Words replaced by earlier restores"}</p>
+                          {
+                              ctx.props().previous_mnemonics.iter().map(|mnemonic| {
+                                  html! { <div class="grid grid-cols-3 gap-2 mb-8">{ render_words(mnemonic) }</div> }
+                              }).collect::<Html>()
+                          }
+                          </>
+                      }
+                  }
+              }
+              {
+                  if self.confirming.is_some() {
+                      self.view_confirm(ctx)
+                  } else {
+                      html! {
+                          <>
+                          <input type="text" name="restore_mnemonic" id="restore_mnemonic" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Backup words to restore"} ref={self.mnemonic_node_ref.clone()} />
+                          {
+                              if let Some(status) = &ctx.props().restore_status {
+                                  html! { <p class="mt-2 dark:text-gray-400">{status}</p> }
+                              } else {
+                                  html! {}
+                              }
+                          }
+                          <div class="flex justify-center mt-8">
+                            <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={restore}>{"Restore"}</button>
+                            <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().backed_up_cb.clone()}>{"Done"}</button>
+                          </div>
+                          </>
+                      }
+                  }
+              }
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
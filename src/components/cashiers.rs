@@ -0,0 +1,140 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::admin::{self, Cashier, Role};
+
+/// Shortest PIN accepted
+const MIN_PIN_LEN: usize = 4;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub cashiers: Vec<Cashier>,
+    /// Cashiers need a manager or the admin PIN to open the settings
+    pub admin_pin: bool,
+    /// Name, role and PIN of a new cashier
+    pub add_cb: Callback<(String, Role, String)>,
+    /// Name of the cashier to remove
+    pub remove_cb: Callback<String>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    CashierSubmitted,
+    CashierRemoved(String),
+}
+
+#[derive(Default)]
+pub struct Cashiers {
+    name_node_ref: NodeRef,
+    role_node_ref: NodeRef,
+    pin_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for Cashiers {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::CashierSubmitted => {
+                if let (Some(name_input), Some(role_input), Some(pin_input)) = (
+                    self.name_node_ref.cast::<HtmlInputElement>(),
+                    self.role_node_ref.cast::<HtmlSelectElement>(),
+                    self.pin_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let name = name_input.value().trim().to_string();
+                    let pin = pin_input.value();
+                    let role = match role_input.value().as_str() {
+                        "Manager" => Role::Manager,
+                        _ => Role::Cashier,
+                    };
+
+                    if name.is_empty() {
+                        self.error = Some("Enter a name".to_string());
+                    } else if ctx.props().cashiers.iter().any(|c| c.name == name) {
+                        self.error = Some(format!("{} already exists", name));
+                    } else if pin.chars().count() < MIN_PIN_LEN {
+                        self.error = Some(format!("PIN must be at least {} digits", MIN_PIN_LEN));
+                    } else if let Err(err) =
+                        admin::check_add(&ctx.props().cashiers, role, ctx.props().admin_pin)
+                    {
+                        self.error = Some(err.to_string());
+                    } else {
+                        self.error = None;
+                        name_input.set_value("");
+                        pin_input.set_value("");
+                        ctx.props().add_cb.emit((name, role, pin));
+                    }
+                }
+
+                true
+            }
+            Msg::CashierRemoved(name) => {
+                match admin::check_remove(&ctx.props().cashiers, &name, ctx.props().admin_pin) {
+                    Ok(()) => {
+                        self.error = None;
+                        ctx.props().remove_cb.emit(name);
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let add = ctx.link().callback(|_| Msg::CashierSubmitted);
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              {
+                  ctx.props().cashiers.iter().map(|cashier| {
+                      let name = cashier.name.clone();
+                      let remove = ctx.link().callback(move |_| Msg::CashierRemoved(name.clone()));
+
+                      html! {
+                          <div class="flex justify-between items-center my-2 text-5xl lg:text-lg dark:text-gray-400">
+                            <p>{format!("{} ({})", cashier.name, cashier.role.label())}</p>
+                            <button class="px-6 py-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={remove}>{"Remove"}</button>
+                          </div>
+                      }
+                  }).collect::<Html>()
+              }
+              <input type="text" name="cashier_name" id="cashier_name" class="block py-4 px-6 mt-8 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Name"} ref={self.name_node_ref.clone()} />
+              <select class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 dark:text-white dark:bg-gray-800 dark:border-gray-600" ref={self.role_node_ref.clone()}>
+                {
+                    Role::ALL.iter().map(|role| {
+                        html! { <option value={role.label()}>{role.label()}</option> }
+                    }).collect::<Html>()
+                }
+              </select>
+              <input type="password" inputmode="numeric" name="cashier_pin" id="cashier_pin" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"PIN"} ref={self.pin_node_ref.clone()} />
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={add}>{"Add"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use nostr_sdk::prelude::{ToBech32, XOnlyPublicKey};
+use web_sys::HtmlTextAreaElement;
+use yew::prelude::*;
+
+use crate::bindings;
+use crate::components::invoice::render_qr;
+use crate::config_bundle::{self, ConfigBundle, Signer};
+use crate::remote_config::RemoteConfig;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    /// Signed bundle of the current config once exported
+    pub bundle: Option<String>,
+    pub current: RemoteConfig,
+    /// Paired merchant and keys trusted to sign bundles
+    pub trusted: Vec<XOnlyPublicKey>,
+    pub export_cb: Callback<()>,
+    pub apply_cb: Callback<RemoteConfig>,
+    /// Trusts the signer of an applied bundle from then on
+    pub trust_cb: Callback<XOnlyPublicKey>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    Export,
+    Download,
+    Preview,
+    Apply,
+    Cancel,
+}
+
+/// Validated bundle waiting to be applied
+struct Preview {
+    config: RemoteConfig,
+    changes: Vec<String>,
+    signer: Signer,
+}
+
+#[derive(Default)]
+pub struct ConfigTransfer {
+    bundle_node_ref: NodeRef,
+    preview: Option<Preview>,
+    error: Option<String>,
+}
+
+impl ConfigTransfer {
+    fn view_preview(&self, ctx: &Context<Self>, preview: &Preview) -> Html {
+        let apply = ctx.link().callback(|_| Msg::Apply);
+        let cancel = ctx.link().callback(|_| Msg::Cancel);
+
+        let npub = |key: XOnlyPublicKey| key.to_bech32().unwrap_or_else(|_| key.to_string());
+
+        let (signer, apply_label) = match preview.signer {
+            Signer::Trusted(key) => (
+                html! { <p class="mb-4 break-all dark:text-gray-400">{format!("Signed by {}", npub(key))}</p> },
+                "Apply",
+            ),
+            Signer::Unknown(key) => (
+                html! { <p class="mb-4 break-all text-red-500">{format!("Signed by {}, which is not the paired merchant or a trusted key. Only trust keys of terminals you control.", npub(key))}</p> },
+                "Trust signer and apply",
+            ),
+            Signer::Unsigned => (
+                html! { <p class="mb-4 text-red-500">{"Not signed, anyone could have made this bundle. Check every change before applying it."}</p> },
+                "Apply unsigned",
+            ),
+        };
+
+        html! {
+            <>
+            { signer }
+            {
+                if preview.changes.is_empty() {
+                    html! { <p class="dark:text-gray-400">{"No changes"}</p> }
+                } else {
+                    preview.changes.iter().map(|change| {
+                        html! { <p class="break-all text-xl lg:text-lg dark:text-gray-400">{change}</p> }
+                    }).collect::<Html>()
+                }
+            }
+            <div class="flex justify-center mt-8">
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={apply}>{apply_label}</button>
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={cancel}>{"Cancel"}</button>
+            </div>
+            </>
+        }
+    }
+
+    fn view_transfer(&self, ctx: &Context<Self>) -> Html {
+        let export = ctx.link().callback(|_| Msg::Export);
+        let download = ctx.link().callback(|_| Msg::Download);
+        let preview = ctx.link().callback(|_| Msg::Preview);
+
+        let exported = match &ctx.props().bundle {
+            Some(bundle) => html! {
+                <>
+                { render_qr(bundle) }
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={download}>{"Download"}</button>
+                </>
+            },
+            None => html! {
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={export}>{"Export"}</button>
+            },
+        };
+
+        html! {
+            <>
+            { exported }
+            <textarea name="bundle" id="bundle" rows="6" class="block py-4 px-6 mt-8 w-full text-xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Paste a config bundle to import"} ref={self.bundle_node_ref.clone()} />
+            {
+                if let Some(error) = &self.error {
+                    html! { <p class="mt-2 text-red-500">{error}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="flex justify-center mt-8">
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={preview}>{"Import"}</button>
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+            </div>
+            </>
+        }
+    }
+}
+
+impl Component for ConfigTransfer {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Export => {
+                ctx.props().export_cb.emit(());
+
+                false
+            }
+            Msg::Download => {
+                if let Some(bundle) = &ctx.props().bundle {
+                    bindings::download("cashcrab-config.json", bundle, "application/json");
+                }
+
+                false
+            }
+            Msg::Preview => {
+                if let Some(bundle_input) = self.bundle_node_ref.cast::<HtmlTextAreaElement>() {
+                    match ConfigBundle::parse(&bundle_input.value()) {
+                        Ok((bundle, signer)) => {
+                            self.error = None;
+                            self.preview = Some(Preview {
+                                changes: config_bundle::diff(&ctx.props().current, &bundle.config),
+                                config: bundle.config,
+                                signer: Signer::new(signer, &ctx.props().trusted),
+                            });
+                        }
+                        Err(err) => self.error = Some(format!("Invalid bundle: {}", err)),
+                    }
+                }
+
+                true
+            }
+            Msg::Apply => {
+                if let Some(preview) = self.preview.take() {
+                    if let Signer::Unknown(key) = preview.signer {
+                        ctx.props().trust_cb.emit(key);
+                    }
+                    ctx.props().apply_cb.emit(preview.config);
+                }
+
+                true
+            }
+            Msg::Cancel => {
+                self.preview = None;
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let content = match &self.preview {
+            Some(preview) => self.view_preview(ctx, preview),
+            None => self.view_transfer(ctx),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              { content }
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
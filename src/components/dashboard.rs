@@ -0,0 +1,271 @@
+use std::str::FromStr;
+
+use url::Url;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::bindings;
+use crate::components::invoice::render_qr;
+use crate::dashboard::{self, FeedItem, Login, TokenStatus};
+use crate::nip46::BunkerUri;
+use crate::sale;
+
+/// Feed entries shown below the totals
+const FEED_LIMIT: usize = 50;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    /// Merchant npub once logged in
+    pub merchant: Option<String>,
+    /// Relays to read from, editable before logging in
+    pub relays: Vec<Url>,
+    pub feed: Vec<FeedItem>,
+    pub error: Option<String>,
+    /// Fresh tokens from redeeming the feed, not yet taken to a wallet
+    pub claimed_tokens: Vec<String>,
+    pub redeem_status: Option<String>,
+    /// Relays to read the feed from and how the merchant signs in
+    pub login_cb: Callback<(Vec<Url>, Login)>,
+    pub redeem_cb: Callback<()>,
+    pub clear_cb: Callback<()>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    /// With a signer extension, or the bunker URI field when `true`
+    LoginSubmitted(bool),
+    Redeem,
+    Download,
+    Clear,
+}
+
+#[derive(Default)]
+pub struct Dashboard {
+    relays_node_ref: NodeRef,
+    bunker_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Dashboard {
+    fn view_login(&self, ctx: &Context<Self>) -> Html {
+        let login = ctx.link().callback(|_| Msg::LoginSubmitted(false));
+        let login_bunker = ctx.link().callback(|_| Msg::LoginSubmitted(true));
+        let relays = ctx
+            .props()
+            .relays
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        html! {
+            <>
+            <p class="mb-4 dark:text-gray-400">{"Log in with the receiver key in a nostr signer extension or remote signer to watch sales"}</p>
+            <input type="text" name="relays" id="relays" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Relay Urls"} value={relays} ref={self.relays_node_ref.clone()} />
+            {
+                if ctx.props().relays.is_empty() {
+                    html! { <p class="mt-2 dark:text-gray-400">{"Enter the relays the terminals send tokens to, separated by spaces, like wss://relay.damus.io"}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <button class="block w-full text-5xl lg:text-xl p-6 my-2 mt-8 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={login}>{"Log in with extension"}</button>
+            <input type="text" name="bunker" id="bunker" class="block py-4 px-6 mt-8 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"bunker://..."} ref={self.bunker_node_ref.clone()} />
+            <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={login_bunker}>{"Log in with remote signer"}</button>
+            </>
+        }
+    }
+
+    fn view_claimed(&self, ctx: &Context<Self>) -> Html {
+        let download = ctx.link().callback(|_| Msg::Download);
+        let clear = ctx.link().callback(|_| Msg::Clear);
+
+        html! {
+            <>
+            <p class="mt-4 text-xl lg:text-lg font-bold dark:text-gray-400">{"Claimed tokens"}</p>
+            {
+                ctx.props().claimed_tokens.iter().map(|token| {
+                    html! {
+                        <>
+                        { render_qr(token) }
+                        <p class="mb-4 break-all text-xl lg:text-lg dark:text-gray-400">{token}</p>
+                        </>
+                    }
+                }).collect::<Html>()
+            }
+            <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={download}>{"Download"}</button>
+            <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={clear}>{"Received in wallet"}</button>
+            </>
+        }
+    }
+
+    fn view_feed(&self, ctx: &Context<Self>, merchant: &str) -> Html {
+        let redeem = ctx.link().callback(|_| Msg::Redeem);
+        let feed = &ctx.props().feed;
+        let total: u64 = feed.iter().map(|i| i.amount.to_sat()).sum();
+        let unredeemed = feed.iter().filter(|i| i.status.is_none()).count();
+
+        html! {
+            <>
+            <p class="mb-4 break-all dark:text-gray-400">{format!("Watching {}", merchant)}</p>
+            <p class="text-5xl lg:text-3xl dark:text-white">{format!("{} sats", total)}</p>
+            <p class="mb-4 dark:text-gray-400">{format!("{} tokens in the last 24 hours", feed.len())}</p>
+            {
+                if unredeemed > 0 {
+                    html! { <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={redeem}>{format!("Redeem {} tokens", unredeemed)}</button> }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if let Some(status) = &ctx.props().redeem_status {
+                    html! { <p class="mt-2 dark:text-gray-400">{status}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if ctx.props().claimed_tokens.is_empty() {
+                    html! {}
+                } else {
+                    self.view_claimed(ctx)
+                }
+            }
+
+            <p class="mt-4 text-xl lg:text-lg font-bold dark:text-gray-400">{"Per terminal"}</p>
+            {
+                dashboard::per_terminal(feed).into_iter().map(|(terminal, (amount, count))| {
+                    html! { <p class="break-all text-xl lg:text-lg dark:text-gray-400">{format!("{}: {} sats in {} tokens", terminal, amount.to_sat(), count)}</p> }
+                }).collect::<Html>()
+            }
+
+            <p class="mt-4 text-xl lg:text-lg font-bold dark:text-gray-400">{"Per hour"}</p>
+            {
+                dashboard::per_hour(feed).into_iter().rev().map(|(hour, amount)| {
+                    html! { <p class="text-xl lg:text-lg dark:text-gray-400">{format!("{}: {} sats", sale::format_time(hour), amount.to_sat())}</p> }
+                }).collect::<Html>()
+            }
+
+            <p class="mt-4 text-xl lg:text-lg font-bold dark:text-gray-400">{"Latest"}</p>
+            {
+                feed.iter().rev().take(FEED_LIMIT).map(|item| {
+                    let sale = match item.sale_amount {
+                        Some(sale_amount) => format!(" of {}", sale_amount.to_sat()),
+                        None => String::new(),
+                    };
+                    let status = match item.status {
+                        Some(TokenStatus::Claimed) => " (claimed)",
+                        Some(TokenStatus::Spent) => " (already spent)",
+                        None => "",
+                    };
+                    let verified = match item.verified {
+                        true => "",
+                        false => " (unverified sender)",
+                    };
+
+                    html! { <p class="break-all text-xl lg:text-lg dark:text-gray-400">{format!("{} {} sats{} from {}{}{}", sale::format_time(item.created_at), item.amount.to_sat(), sale, item.terminal, verified, status)}</p> }
+                }).collect::<Html>()
+            }
+            </>
+        }
+    }
+}
+
+impl Component for Dashboard {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::LoginSubmitted(bunker) => {
+                if let (Some(relays_input), Some(bunker_input)) = (
+                    self.relays_node_ref.cast::<HtmlInputElement>(),
+                    self.bunker_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let relays: Result<Vec<Url>, _> = relays_input
+                        .value()
+                        .split_whitespace()
+                        .map(Url::from_str)
+                        .collect();
+                    let login = match bunker {
+                        true => BunkerUri::from_str(&bunker_input.value()).map(Login::Bunker),
+                        false => Ok(Login::Extension),
+                    };
+
+                    match (relays, login) {
+                        (Ok(relays), _) if relays.is_empty() => {
+                            self.error = Some("Enter at least one valid relay".to_string())
+                        }
+                        (Err(_), _) => {
+                            self.error = Some("Enter at least one valid relay".to_string())
+                        }
+                        (Ok(_), Err(err)) => {
+                            self.error = Some(format!("Invalid bunker URI: {}", err))
+                        }
+                        (Ok(relays), Ok(login)) => {
+                            self.error = None;
+                            ctx.props().login_cb.emit((relays, login));
+                        }
+                    }
+                }
+
+                true
+            }
+            Msg::Redeem => {
+                ctx.props().redeem_cb.emit(());
+
+                false
+            }
+            Msg::Download => {
+                bindings::download(
+                    "cashcrab-tokens.txt",
+                    &ctx.props().claimed_tokens.join("\n"),
+                    "text/plain",
+                );
+
+                false
+            }
+            Msg::Clear => {
+                ctx.props().clear_cb.emit(());
+
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let content = match &ctx.props().merchant {
+            Some(merchant) => self.view_feed(ctx, merchant),
+            None => self.view_login(ctx),
+        };
+        let error = self.error.as_ref().or(ctx.props().error.as_ref());
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              { content }
+              {
+                  if let Some(error) = error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::bindings;
+use crate::export::{self, Column};
+use crate::sale::Sale;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub sales: Vec<Sale>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    ColumnToggled(Column),
+    Csv,
+    Json,
+}
+
+pub struct ExportView {
+    from_node_ref: NodeRef,
+    to_node_ref: NodeRef,
+    columns: Vec<Column>,
+    error: Option<String>,
+}
+
+/// Unix time of a `YYYY-MM-DD` date input at local `time`
+fn parse_date(node_ref: &NodeRef, time: &str) -> Result<Option<u64>, String> {
+    let value = match node_ref.cast::<HtmlInputElement>() {
+        Some(input) => input.value(),
+        None => return Ok(None),
+    };
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let millis = js_sys::Date::new(&format!("{}T{}", value, time).into()).get_time();
+    if millis.is_nan() {
+        return Err(format!("Invalid date {}", value));
+    }
+
+    Ok(Some((millis / 1000.0) as u64))
+}
+
+impl ExportView {
+    fn range(&self) -> Result<(Option<u64>, Option<u64>), String> {
+        Ok((
+            parse_date(&self.from_node_ref, "00:00:00")?,
+            parse_date(&self.to_node_ref, "23:59:59")?,
+        ))
+    }
+
+    /// Selected columns in schema order
+    fn columns(&self) -> Vec<Column> {
+        Column::ALL
+            .into_iter()
+            .filter(|c| self.columns.contains(c))
+            .collect()
+    }
+}
+
+impl Component for ExportView {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            from_node_ref: NodeRef::default(),
+            to_node_ref: NodeRef::default(),
+            columns: Column::ALL.to_vec(),
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ColumnToggled(column) => {
+                if self.columns.contains(&column) {
+                    self.columns.retain(|c| c != &column);
+                } else {
+                    self.columns.push(column);
+                }
+
+                true
+            }
+            Msg::Csv => {
+                match self.range() {
+                    Ok((from, to)) => {
+                        self.error = None;
+                        let csv = export::to_csv(&ctx.props().sales, from, to, &self.columns());
+                        bindings::download("sales.csv", &csv, "text/csv");
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+
+                true
+            }
+            Msg::Json => {
+                match self.range() {
+                    Ok((from, to)) => {
+                        match export::to_json(&ctx.props().sales, from, to, &self.columns()) {
+                            Ok(json) => {
+                                self.error = None;
+                                bindings::download("sales.json", &json, "application/json");
+                            }
+                            Err(err) => self.error = Some(err.to_string()),
+                        }
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let csv = ctx.link().callback(|_| Msg::Csv);
+        let json = ctx.link().callback(|_| Msg::Json);
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Sales created between these dates, leave empty for all"}</p>
+              <input type="date" name="from" id="from" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" ref={self.from_node_ref.clone()} />
+              <input type="date" name="to" id="to" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" ref={self.to_node_ref.clone()} />
+              {
+                  Column::ALL.into_iter().map(|column| {
+                      let toggle = ctx.link().callback(move |_| Msg::ColumnToggled(column));
+                      html! {
+                          <label class="flex items-center mt-4 text-5xl lg:text-lg dark:text-gray-400">
+                            <input type="checkbox" class="mr-2" checked={self.columns.contains(&column)} onclick={toggle} />
+                            {column.name()}
+                          </label>
+                      }
+                  }).collect::<Html>()
+              }
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={csv}>{"CSV"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={json}>{"JSON"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -1,49 +1,221 @@
 use cashu_crab::{Amount, Invoice};
+use gloo::timers::callback::Interval;
 use qrcode::render::svg;
+use qrcode::types::QrError;
 use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlSelectElement;
 use yew::prelude::*;
 use yew::virtual_dom::VNode;
 
+use crate::sale::unix_time;
+
+/// What the payment QR encodes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QrPayload {
+    #[default]
+    LightningUri,
+    /// Uppercase BOLT11 fits the alphanumeric QR mode for a denser code
+    Bolt11Upper,
+    /// BIP21 unified URI
+    Bip21,
+    /// Cashu payment request (NUT-18)
+    CashuRequest,
+}
+
+impl QrPayload {
+    pub const ALL: [QrPayload; 4] = [
+        QrPayload::LightningUri,
+        QrPayload::Bolt11Upper,
+        QrPayload::Bip21,
+        QrPayload::CashuRequest,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            QrPayload::LightningUri => "Lightning",
+            QrPayload::Bolt11Upper => "Lightning (dense)",
+            QrPayload::Bip21 => "Bitcoin unified",
+            QrPayload::CashuRequest => "Cashu",
+        }
+    }
+
+    fn encode(&self, invoice: &Invoice, payment_request: &str, address: Option<&str>) -> String {
+        match self {
+            QrPayload::LightningUri => format!("lightning:{}", invoice),
+            QrPayload::Bolt11Upper => invoice.to_string().to_uppercase(),
+            QrPayload::Bip21 => match address {
+                Some(address) => bip21(
+                    address,
+                    invoice.amount_milli_satoshis().unwrap_or(0) / 1000,
+                    &invoice.to_string(),
+                ),
+                // A BIP21 URI needs an address, wallets reject `bitcoin:?`
+                None => invoice.to_string(),
+            },
+            // Empty when the request could not be encoded
+            QrPayload::CashuRequest if payment_request.is_empty() => {
+                format!("lightning:{}", invoice)
+            }
+            QrPayload::CashuRequest => payment_request.to_string(),
+        }
+    }
+
+    /// Whether the payload can be offered with the configured settings
+    fn available(&self, address: Option<&str>, payment_request: &str) -> bool {
+        match self {
+            QrPayload::Bip21 => address.is_some(),
+            QrPayload::CashuRequest => !payment_request.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+/// BTC amount without trailing zeros, `1500` sats is `0.000015`
+fn btc_amount(sats: u64) -> String {
+    let btc = format!("{}.{:08}", sats / 100_000_000, sats % 100_000_000);
+
+    btc.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// BIP21 URI paying `address` with a lightning fallback
+fn bip21(address: &str, sats: u64, bolt11: &str) -> String {
+    match sats {
+        0 => format!("bitcoin:{}?lightning={}", address, bolt11),
+        sats => format!(
+            "bitcoin:{}?amount={}&lightning={}",
+            address,
+            btc_amount(sats),
+            bolt11
+        ),
+    }
+}
+
+/// SVG QR code of `data`, fails if it is too long for a QR code
+fn qr_svg(data: &str) -> Result<String, QrError> {
+    Ok(QrCode::new(data.as_bytes())?
+        .render()
+        .min_dimensions(200, 200)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build())
+}
+
+/// QR code of `data`, or a note to copy the text if it does not fit
+pub fn render_qr(data: &str) -> Html {
+    match qr_svg(data) {
+        // escapes the string to make it html
+        Ok(qr_svg) => Html::from_html_unchecked(AttrValue::from(qr_svg)),
+        Err(_) => html! {
+            <p class="text-xl lg:text-lg dark:text-gray-400">{"Too long for a QR code, copy the text instead"}</p>
+        },
+    }
+}
+
 #[derive(Properties, PartialEq, Clone)]
 pub struct Props {
     pub invoice: Invoice,
+    /// Encoded NUT-18 request for paying the sale with ecash
+    pub payment_request: String,
+    pub qr_payload: QrPayload,
+    pub qr_payload_cb: Callback<QrPayload>,
+    /// On-chain address for the BIP21 payload
+    pub onchain_address: Option<String>,
     pub fiat_value: String,
+    /// Unix timestamp after which the invoice can no longer be paid
+    pub expiry: u64,
+    pub regenerate_cb: Callback<MouseEvent>,
     pub home_cb: Callback<MouseEvent>,
 }
 
+pub enum Msg {
+    Tick,
+    PayloadSelected,
+}
+
 #[derive(Default)]
 pub struct InvoiceView {
     amount: Amount,
+    payload: String,
     invoice_qr: VNode,
+    payload_node_ref: NodeRef,
+    remaining: u64,
+    _countdown: Option<Interval>,
 }
 
 impl Component for InvoiceView {
-    type Message = ();
+    type Message = Msg;
     type Properties = Props;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let qr_svg = QrCode::new(ctx.props().invoice.to_string().as_bytes())
-            .unwrap()
-            .render()
-            .min_dimensions(200, 200)
-            .dark_color(svg::Color("#000000"))
-            .light_color(svg::Color("#ffffff"))
-            .build();
-
-        // escapes the string to make it html
-        let invoice_qr_svg = Html::from_html_unchecked(AttrValue::from(qr_svg));
+        let props = ctx.props();
+        let payload = props.qr_payload.encode(
+            &props.invoice,
+            &props.payment_request,
+            props.onchain_address.as_deref(),
+        );
 
         let amount = ctx.props().invoice.amount_milli_satoshis().unwrap_or(0);
 
         let amount = Amount::from_msat(amount);
 
+        let link = ctx.link().clone();
+        let countdown = Interval::new(1000, move || link.send_message(Msg::Tick));
+
         Self {
-            invoice_qr: invoice_qr_svg,
+            invoice_qr: render_qr(&payload),
+            payload,
             amount,
+            remaining: ctx.props().expiry.saturating_sub(unix_time()),
+            _countdown: Some(countdown),
+            ..Default::default()
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        self.payload = props.qr_payload.encode(
+            &props.invoice,
+            &props.payment_request,
+            props.onchain_address.as_deref(),
+        );
+        self.invoice_qr = render_qr(&self.payload);
+
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Tick => {
+                self.remaining = ctx.props().expiry.saturating_sub(unix_time());
+
+                if self.remaining == 0 {
+                    self._countdown = None;
+                }
+
+                true
+            }
+            Msg::PayloadSelected => {
+                if let Some(select) = self.payload_node_ref.cast::<HtmlSelectElement>() {
+                    if let Some(payload) = select
+                        .value()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| QrPayload::ALL.get(i))
+                    {
+                        ctx.props().qr_payload_cb.emit(*payload);
+                    }
+                }
+
+                false
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let expired = self.remaining == 0;
+        let payload_selected = ctx.link().callback(|_| Msg::PayloadSelected);
+
         html! {
             <>
 
@@ -53,10 +225,37 @@ impl Component for InvoiceView {
             <h1 class="text-3xl mb-4 font-semibold leadi text-center">{format!("{} sats", self.amount.to_sat())}</h1>
             <h1 class="text-3xl mb-4 font-light leadi text-center">{format!("{} USD", ctx.props().fiat_value.clone())}</h1>
             <div class="flex flex-col items-center">
+                <select class="block py-2 px-4 mb-4 text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 dark:text-white dark:bg-gray-800 dark:border-gray-600" onchange={payload_selected} ref={self.payload_node_ref.clone()}>
+                {
+                    QrPayload::ALL.iter().enumerate().filter(|(_, payload)| payload.available(ctx.props().onchain_address.as_deref(), &ctx.props().payment_request)).map(|(i, payload)| {
+                        html! {
+                            <option value={i.to_string()} selected={*payload == ctx.props().qr_payload}>{payload.label()}</option>
+                        }
+                    }).collect::<Html>()
+                }
+                </select>
 
-                { self.invoice_qr.clone() }
+                <div class={classes!(expired.then_some("opacity-25"))}>
+                    { self.invoice_qr.clone() }
+                </div>
 
-                <p class="flex-1 dark:text-gray-400" style="max-width: 33vw; word-wrap: break-word;">{ctx.props().invoice.to_string() }</p>
+                {
+                    if expired {
+                        html! {
+                            <>
+                            <p class="text-xl mt-2 dark:text-gray-400">{"Invoice expired"}</p>
+                            <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().regenerate_cb.clone()}>{"New invoice"}</button>
+                            </>
+                        }
+                    } else {
+                        html! {
+                            <>
+                            <p class="text-xl mt-2 dark:text-gray-400">{format!("Expires in {}:{:02}", self.remaining / 60, self.remaining % 60)}</p>
+                            <p class="flex-1 dark:text-gray-400" style="max-width: 33vw; word-wrap: break-word;">{self.payload.clone()}</p>
+                            </>
+                        }
+                    }
+                }
                 <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().home_cb.clone()}>{"Cancel"}</button>
 
             </div>
@@ -66,3 +265,62 @@ impl Component for InvoiceView {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const INVOICE: &str = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+
+    #[test]
+    fn btc_amount_trims_zeros() {
+        assert_eq!(btc_amount(0), "0");
+        assert_eq!(btc_amount(1), "0.00000001");
+        assert_eq!(btc_amount(1500), "0.000015");
+        assert_eq!(btc_amount(100_000_000), "1");
+        assert_eq!(btc_amount(150_000_000), "1.5");
+    }
+
+    #[test]
+    fn bip21_includes_address() {
+        assert_eq!(
+            bip21("bc1qexample", 1500, "lnbc15u1"),
+            "bitcoin:bc1qexample?amount=0.000015&lightning=lnbc15u1"
+        );
+        assert_eq!(
+            bip21("bc1qexample", 0, "lnbc1"),
+            "bitcoin:bc1qexample?lightning=lnbc1"
+        );
+    }
+
+    #[test]
+    fn qr_too_long_is_an_error() {
+        assert!(qr_svg("lightning:lnbc1").is_ok());
+        assert!(qr_svg(&"a".repeat(8000)).is_err());
+    }
+
+    #[test]
+    fn bip21_needs_address() {
+        assert!(QrPayload::Bip21.available(Some("bc1qexample"), ""));
+        assert!(!QrPayload::Bip21.available(None, ""));
+        assert!(QrPayload::LightningUri.available(None, ""));
+    }
+
+    #[test]
+    fn cashu_request_needs_encoded_request() {
+        assert!(QrPayload::CashuRequest.available(None, "creqA1"));
+        assert!(!QrPayload::CashuRequest.available(None, ""));
+
+        let invoice = Invoice::from_str(INVOICE).unwrap();
+        assert_eq!(
+            QrPayload::CashuRequest.encode(&invoice, "creqA1", None),
+            "creqA1"
+        );
+        assert_eq!(
+            QrPayload::CashuRequest.encode(&invoice, "", None),
+            format!("lightning:{}", INVOICE)
+        );
+    }
+}
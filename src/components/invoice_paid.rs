@@ -1,22 +1,137 @@
+use gloo::timers::callback::Interval;
 use yew::prelude::*;
 
+use crate::components::invoice::render_qr;
+use crate::sale::{self, Sale};
+
 #[derive(Properties, PartialEq, Clone)]
 pub struct Props {
-    pub home_cb: Callback<MouseEvent>,
+    pub sale: Option<Sale>,
+    /// Seconds before returning to the keypad
+    pub auto_return: Option<u32>,
+    /// Signed receipt event
+    pub receipt: Option<String>,
+    pub receipt_cb: Callback<()>,
+    /// Set when a receipt printer is configured
+    pub print_cb: Option<Callback<()>>,
+    pub home_cb: Callback<()>,
+}
+
+pub enum Msg {
+    Tick,
+    Receipt,
+    Home,
 }
 
 #[derive(Default)]
-pub struct InvoicePaid;
+pub struct InvoicePaid {
+    remaining: Option<u32>,
+    _countdown: Option<Interval>,
+}
 
 impl Component for InvoicePaid {
-    type Message = ();
+    type Message = Msg;
     type Properties = Props;
 
-    fn create(_ctx: &Context<Self>) -> Self {
-        Self
+    fn create(ctx: &Context<Self>) -> Self {
+        let countdown = ctx.props().auto_return.map(|_| {
+            let link = ctx.link().clone();
+            Interval::new(1000, move || link.send_message(Msg::Tick))
+        });
+
+        Self {
+            remaining: ctx.props().auto_return,
+            _countdown: countdown,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        // Stay on screen while the customer scans their receipt
+        if ctx.props().receipt.is_some() {
+            self.remaining = None;
+            self._countdown = None;
+        }
+
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Tick => {
+                self.remaining = self.remaining.map(|r| r.saturating_sub(1));
+
+                if self.remaining == Some(0) {
+                    self._countdown = None;
+                    ctx.props().home_cb.emit(());
+                }
+
+                true
+            }
+            Msg::Receipt => {
+                ctx.props().receipt_cb.emit(());
+
+                false
+            }
+            Msg::Home => {
+                ctx.props().home_cb.emit(());
+
+                false
+            }
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let home = ctx.link().callback(|_| Msg::Home);
+        let receipt = ctx.link().callback(|_| Msg::Receipt);
+
+        let summary = match &ctx.props().sale {
+            Some(sale) => {
+                let delivery = if sale.legs.is_empty() && sale.retained.to_sat() == 0 {
+                    html! { <p class="dark:text-gray-400">{"Sending token..."}</p> }
+                } else {
+                    html! {
+                        <>
+                        {
+                            sale.legs.iter().map(|leg| {
+                                let status = match leg.delivered {
+                                    true => "sent",
+                                    false => "not delivered",
+                                };
+                                html! {
+                                    <p class="dark:text-gray-400">{format!("{} sats {}", leg.amount.to_sat(), status)}</p>
+                                }
+                            }).collect::<Html>()
+                        }
+                        {
+                            if sale.retained.to_sat() > 0 {
+                                html! { <p class="dark:text-gray-400">{format!("{} sats kept on terminal", sale.retained.to_sat())}</p> }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        </>
+                    }
+                };
+
+                html! {
+                    <>
+                    <h1 class="text-3xl mb-2 font-semibold leadi text-center">{format!("{} sats", sale.amount.to_sat())}</h1>
+                    <h1 class="text-3xl mb-2 font-light leadi text-center">{format!("{} USD", sale.fiat_value)}</h1>
+                    <p class="mb-2 dark:text-gray-400">{sale::format_time(sale.created_at)}</p>
+                    {
+                        if let Some(reference) = &sale.reference {
+                            html! { <p class="mb-2 dark:text-gray-400">{format!("Order {}", reference)}</p> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    <div class="mb-4 text-center">{ delivery }</div>
+                    </>
+                }
+            }
+            None => html! {},
+        };
+
         html! {
         <div class="flex items-center justify-center">
           <a class="block p-6 bg-white border border-gray-200 rounded-lg shadow hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700">
@@ -28,7 +143,39 @@ impl Component for InvoicePaid {
                 </svg>
               </div>
 
-              <button class="px-6 py-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().home_cb.clone()}>{"Home"}</button>
+              { summary }
+
+              {
+                  if let Some(print_cb) = &ctx.props().print_cb {
+                      let print_cb = print_cb.clone();
+                      html! {
+                          <button class="px-6 py-2 mb-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={move |_| print_cb.emit(())}>{"Print"}</button>
+                      }
+                  } else {
+                      html! {}
+                  }
+              }
+              {
+                  match &ctx.props().receipt {
+                      Some(receipt) => html! {
+                          <div class="mb-4">
+                              { render_qr(receipt) }
+                          </div>
+                      },
+                      None => html! {
+                          <button class="px-6 py-2 mb-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={receipt}>{"Receipt"}</button>
+                      },
+                  }
+              }
+
+              <button class="px-6 py-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={home}>
+                {
+                    match self.remaining {
+                        Some(remaining) => format!("Home ({})", remaining),
+                        None => "Home".to_string(),
+                    }
+                }
+              </button>
             </div>
           </a>
         </div>
@@ -1,7 +1,25 @@
+pub mod backup;
+pub mod cashiers;
+pub mod config_transfer;
+pub mod dashboard;
+pub mod export;
 pub mod invoice;
 pub mod invoice_paid;
 pub mod pos;
+pub mod sales;
+pub mod sales_sync;
+pub mod set_melt;
+pub mod set_message;
 pub mod set_mint;
+pub mod set_pin;
+pub mod set_printer;
 pub mod set_rec_key;
 pub mod set_relays;
+pub mod set_remote;
+pub mod set_shop;
+pub mod set_splits;
 pub mod settings;
+pub mod shift;
+pub mod sign_in;
+pub mod terminal_wallet;
+pub mod unlock;
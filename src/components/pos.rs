@@ -2,12 +2,16 @@ use anyhow::Result;
 use cashu_crab::Amount;
 use gloo_net::http::Request;
 use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
 use yew::platform::spawn_local;
 use yew::prelude::*;
 
+use crate::sale;
+
 #[derive(Properties, PartialEq, Clone)]
 pub struct Props {
-    pub amount_cb: Callback<(Amount, String)>,
+    /// Amount, fiat value and optional order reference of the sale
+    pub amount_cb: Callback<(Amount, String, Option<String>)>,
 }
 
 pub enum Msg {
@@ -25,6 +29,7 @@ pub struct Pos {
     fiat_value: String,
     enter_fiat: bool,
     disable_decimal: bool,
+    reference_node_ref: NodeRef,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,19 +42,28 @@ struct CoinGeckoPrice {
     bitcoin: UsdPrice,
 }
 
-async fn get_price(price_cb: Callback<u64>) -> Result<()> {
+/// Current price of a dollar in sats
+pub async fn sats_per_usd() -> Result<u64> {
     let url = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd";
     let price: CoinGeckoPrice = Request::get(url).send().await?.json().await?;
 
     log::debug!("{:?}", price);
 
-    let sats_per_dollar = (1.0 / (price.bitcoin.usd as f64) * 100000000.0).round() as u64;
+    Ok((1.0 / (price.bitcoin.usd as f64) * 100000000.0).round() as u64)
+}
 
-    price_cb.emit(sats_per_dollar);
+async fn get_price(price_cb: Callback<u64>) -> Result<()> {
+    price_cb.emit(sats_per_usd().await?);
 
     Ok(())
 }
 
+/// Dollar value of `amount` sats formatted for display
+pub fn fiat_value(amount: u64, sat_per_usd: u64) -> String {
+    let value = ((amount as f64 / sat_per_usd as f64) * 100.0).round() / 100.0;
+    format_fiat(&value.to_string())
+}
+
 fn format_fiat(amount: &str) -> String {
     let amount = amount.replace('.', "").trim_start_matches('0').to_string();
     let num_zeros = if amount.len().lt(&3) {
@@ -90,9 +104,22 @@ impl Component for Pos {
                 if let Ok(amount) = self.amount.parse() {
                     let amount = Amount::from_sat(amount);
 
+                    let reference = self
+                        .reference_node_ref
+                        .cast::<HtmlInputElement>()
+                        .map(|input| {
+                            input
+                                .value()
+                                .trim()
+                                .chars()
+                                .take(sale::MAX_REFERENCE_LEN)
+                                .collect::<String>()
+                        })
+                        .filter(|reference| !reference.is_empty());
+
                     ctx.props()
                         .amount_cb
-                        .emit((amount, self.fiat_value.clone()));
+                        .emit((amount, self.fiat_value.clone(), reference));
                 }
 
                 true
@@ -156,8 +183,7 @@ impl Component for Pos {
                     if let (Ok(amount), Some(sat_per_usd)) =
                         (new_amount.parse::<u64>(), self.sat_per_usd)
                     {
-                        let value = ((amount as f64 / sat_per_usd as f64) * 100.0).round() / 100.0;
-                        self.fiat_value = format_fiat(&value.to_string());
+                        self.fiat_value = fiat_value(amount, sat_per_usd);
                     }
 
                     self.amount = new_amount.trim_start_matches('0').to_string();
@@ -256,6 +282,7 @@ impl Component for Pos {
 
                       <div class="relative z-0 w-full mb-6 group">
                             // <input type="numeric" name="amount" id="amount" class="block py-4 px-6 w-full lg:text-lg sm:text-5xl text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Amount (sats)"} ref={self.amount_node_ref.clone()} />
+                        <input type="text" name="reference" id="reference" class="block py-4 px-6 mt-5 w-full lg:text-lg sm:text-5xl text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Order reference (optional)"} maxlength={sale::MAX_REFERENCE_LEN.to_string()} ref={self.reference_node_ref.clone()} />
                         <div class="flex justify-center">
                             <button class="px-8 py-4 mt-5 w-full rounded-sm shadow-lg dark:bg-green-600 dark:text-gray-900 lg:text-lg sm:text-5xl font-medium" onclick={set_pubkey}>{"Create Invoice"}</button>
                         </div>
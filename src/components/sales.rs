@@ -0,0 +1,196 @@
+use cashu_crab::Amount;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::invoice::render_qr;
+use crate::sale::{self, Sale};
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub sales: Vec<Sale>,
+    /// Progress or error of the last refund
+    pub refund_status: Option<String>,
+    /// A refund is in progress
+    pub refunding: bool,
+    /// Sale hash and invoice the merchant pays to fund its refund
+    pub refund_invoice: Option<(String, String)>,
+    /// Refunds the amount of the sale with the payment hash
+    pub refund_cb: Callback<(String, Amount)>,
+    pub cancel_refund_cb: Callback<MouseEvent>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    Select(Option<String>),
+    RefundSubmitted,
+}
+
+#[derive(Default)]
+pub struct SalesView {
+    /// Payment hash of the sale being refunded
+    selected: Option<String>,
+    amount_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl SalesView {
+    fn view_refund(&self, ctx: &Context<Self>, sale: &Sale) -> Html {
+        let refund = ctx.link().callback(|_| Msg::RefundSubmitted);
+        let back = ctx.link().callback(|_| Msg::Select(None));
+
+        // Show the last token until the customer has scanned it, its token is
+        // empty while the encrypted store is locked
+        let token = match sale.refunds.last() {
+            Some(refund) if !refund.token.is_empty() => html! {
+                <>
+                { render_qr(&refund.token) }
+                <p class="mb-4 text-xl lg:text-lg dark:text-gray-400">{format!("Refund of {} sats", refund.amount.to_sat())}</p>
+                </>
+            },
+            _ => html! {},
+        };
+
+        let status = self.error.as_ref().or(ctx.props().refund_status.as_ref());
+
+        let invoice = match &ctx.props().refund_invoice {
+            Some((hash, invoice)) if hash == &sale.hash => html! {
+                <>
+                <p class="mb-4 text-xl lg:text-lg dark:text-gray-400">{"The terminal balance is too low, pay this invoice to fund the refund"}</p>
+                { render_qr(&format!("lightning:{}", invoice)) }
+                <p class="mb-4 break-all dark:text-gray-400">{invoice}</p>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().cancel_refund_cb.clone()}>{"Cancel refund"}</button>
+                </>
+            },
+            _ => html! {},
+        };
+
+        html! {
+            <>
+            { token }
+            { invoice }
+            <p class="mb-4 dark:text-gray-400">{format!("Paid {} sats, refunded {} sats", sale.amount.to_sat() + sale.tip.to_sat(), sale.refunded().to_sat())}</p>
+            <input type="text" name="refund_amount" id="refund_amount" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={format!("Refund amount (max {} sats)", sale.refundable().to_sat())} ref={self.amount_node_ref.clone()} />
+            {
+                if let Some(status) = status {
+                    html! { <p class="mt-2 text-red-500">{status}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="flex justify-center mt-8">
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={refund} disabled={ctx.props().refunding}>{"Refund"}</button>
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={back}>{"Back"}</button>
+            </div>
+            </>
+        }
+    }
+
+    fn view_sales(&self, ctx: &Context<Self>) -> Html {
+        let sales = ctx
+            .props()
+            .sales
+            .iter()
+            .rev()
+            .filter(|s| s.paid)
+            .map(|sale| {
+                let hash = sale.hash.clone();
+                let select = ctx.link().callback(move |_| Msg::Select(Some(hash.clone())));
+                let refunded = sale.refunded().to_sat();
+
+                html! {
+                    <button class="block w-full text-left text-3xl lg:text-lg p-4 my-2 rounded-sm shadow-sm dark:bg-gray-700 dark:text-gray-300" onclick={select}>
+                      <p>{sale::format_time(sale.created_at)}</p>
+                      <p>{format!("{} sats {}", sale.amount.to_sat(), sale.reference.clone().unwrap_or_default())}</p>
+                      {
+                          if refunded > 0 {
+                              html! { <p>{format!("Refunded {} sats", refunded)}</p> }
+                          } else {
+                              html! {}
+                          }
+                      }
+                    </button>
+                }
+            })
+            .collect::<Html>();
+
+        html! {
+            <>
+            { sales }
+            <div class="flex justify-center mt-8">
+              <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+            </div>
+            </>
+        }
+    }
+}
+
+impl Component for SalesView {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Select(hash) => {
+                self.selected = hash;
+                self.error = None;
+
+                true
+            }
+            Msg::RefundSubmitted => {
+                let sale = ctx
+                    .props()
+                    .sales
+                    .iter()
+                    .find(|s| Some(&s.hash) == self.selected.as_ref());
+
+                if let (Some(sale), Some(amount_input)) =
+                    (sale, self.amount_node_ref.cast::<HtmlInputElement>())
+                {
+                    match amount_input.value().trim().parse::<u64>() {
+                        Ok(amount) if amount > 0 && amount <= sale.refundable().to_sat() => {
+                            self.error = None;
+                            amount_input.set_value("");
+                            ctx.props()
+                                .refund_cb
+                                .emit((sale.hash.clone(), Amount::from_sat(amount)));
+                        }
+                        _ => self.error = Some("Invalid refund amount".to_string()),
+                    }
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let sale = ctx
+            .props()
+            .sales
+            .iter()
+            .find(|s| Some(&s.hash) == self.selected.as_ref());
+
+        let content = match sale {
+            Some(sale) => self.view_refund(ctx, sale),
+            None => self.view_sales(ctx),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              { content }
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
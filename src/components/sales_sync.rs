@@ -0,0 +1,140 @@
+use nostr_sdk::prelude::FromSkStr;
+use nostr_sdk::Keys;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    /// Shared sync key as nsec, to be entered on the other terminals
+    pub sync_key: Option<String>,
+    /// Sales pulled from the other terminals
+    pub synced: usize,
+    pub status: Option<String>,
+    pub sync_key_cb: Callback<Option<Keys>>,
+    pub pull_cb: Callback<()>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    Generate,
+    JoinSubmitted,
+    Leave,
+    Pull,
+}
+
+#[derive(Default)]
+pub struct SalesSync {
+    sync_key_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl SalesSync {
+    fn view_joined(&self, ctx: &Context<Self>, sync_key: &str) -> Html {
+        let pull = ctx.link().callback(|_| Msg::Pull);
+        let leave = ctx.link().callback(|_| Msg::Leave);
+
+        html! {
+            <>
+            <p class="mb-4 dark:text-gray-400">{"Enter this sync key on the other terminals of the shop"}</p>
+            <p class="mb-4 break-all text-xl lg:text-lg dark:text-gray-400">{sync_key}</p>
+            <p class="dark:text-gray-400">{format!("{} sales from other terminals", ctx.props().synced)}</p>
+            {
+                if let Some(status) = &ctx.props().status {
+                    html! { <p class="mt-2 dark:text-gray-400">{status}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <button class="block w-full text-5xl lg:text-xl p-6 my-2 mt-8 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={pull}>{"Pull sales"}</button>
+            <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={leave}>{"Stop syncing"}</button>
+            </>
+        }
+    }
+
+    fn view_join(&self, ctx: &Context<Self>) -> Html {
+        let generate = ctx.link().callback(|_| Msg::Generate);
+        let join = ctx.link().callback(|_| Msg::JoinSubmitted);
+
+        html! {
+            <>
+            <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={generate}>{"New sync key"}</button>
+            <input type="password" name="sync_key" id="sync_key" class="block py-4 px-6 mt-8 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Sync key from another terminal"} ref={self.sync_key_node_ref.clone()} />
+            {
+                if let Some(error) = &self.error {
+                    html! { <p class="mt-2 text-red-500">{error}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={join}>{"Join"}</button>
+            </>
+        }
+    }
+}
+
+impl Component for SalesSync {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Generate => {
+                self.error = None;
+                ctx.props().sync_key_cb.emit(Some(Keys::generate()));
+
+                true
+            }
+            Msg::JoinSubmitted => {
+                if let Some(sync_key_input) = self.sync_key_node_ref.cast::<HtmlInputElement>() {
+                    match Keys::from_sk_str(sync_key_input.value().trim()) {
+                        Ok(keys) => {
+                            self.error = None;
+                            ctx.props().sync_key_cb.emit(Some(keys));
+                        }
+                        Err(_) => self.error = Some("Invalid sync key".to_string()),
+                    }
+                }
+
+                true
+            }
+            Msg::Leave => {
+                ctx.props().sync_key_cb.emit(None);
+
+                true
+            }
+            Msg::Pull => {
+                ctx.props().pull_cb.emit(());
+
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let content = match &ctx.props().sync_key {
+            Some(sync_key) => self.view_joined(ctx, sync_key),
+            None => self.view_join(ctx),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              { content }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
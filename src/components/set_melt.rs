@@ -0,0 +1,113 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::melt::MeltSettings;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub melt_settings: Option<MeltSettings>,
+    pub melt_set_cb: Callback<Option<MeltSettings>>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    MeltSubmitted,
+    MeltDisabled,
+}
+
+#[derive(Default)]
+pub struct SetMelt {
+    address_node_ref: NodeRef,
+    threshold_node_ref: NodeRef,
+    dm_receiver_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for SetMelt {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::MeltSubmitted => {
+                if let (Some(address_input), Some(threshold_input), Some(dm_receiver_input)) = (
+                    self.address_node_ref.cast::<HtmlInputElement>(),
+                    self.threshold_node_ref.cast::<HtmlInputElement>(),
+                    self.dm_receiver_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let lightning_address = address_input.value().trim().to_string();
+
+                    match threshold_input.value().trim().parse::<u64>() {
+                        Ok(threshold) if !lightning_address.is_empty() => {
+                            self.error = None;
+                            ctx.props().melt_set_cb.emit(Some(MeltSettings {
+                                lightning_address,
+                                threshold,
+                                dm_receiver: dm_receiver_input.checked(),
+                            }));
+                        }
+                        Ok(_) => self.error = Some("Lightning address required".to_string()),
+                        Err(_) => self.error = Some("Invalid threshold".to_string()),
+                    }
+                }
+
+                true
+            }
+            Msg::MeltDisabled => {
+                ctx.props().melt_set_cb.emit(None);
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_melt = ctx.link().callback(|_| Msg::MeltSubmitted);
+        let disable_melt = ctx.link().callback(|_| Msg::MeltDisabled);
+
+        let (address, threshold, dm_receiver) = match &ctx.props().melt_settings {
+            Some(settings) => (
+                settings.lightning_address.clone(),
+                settings.threshold.to_string(),
+                settings.dm_receiver,
+            ),
+            None => (String::new(), String::new(), false),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Keep the receiver's share on the terminal and melt it to a Lightning address once the balance reaches the threshold"}</p>
+              <input type="text" name="lightning_address" id="lightning_address" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Lightning Address or LNURL"} value={address} ref={self.address_node_ref.clone()} />
+              <input type="text" name="melt_threshold" id="melt_threshold" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Threshold (sats)"} value={threshold} ref={self.threshold_node_ref.clone()} />
+              <label class="flex items-center mt-4 text-5xl lg:text-lg dark:text-gray-400">
+                <input type="checkbox" name="dm_receiver" id="dm_receiver" class="mr-2" checked={dm_receiver} ref={self.dm_receiver_node_ref.clone()} />
+                {"Keep DMing the receiver and only melt tokens that could not be delivered"}
+              </label>
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={set_melt}>{"Save"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={disable_melt}>{"Disable"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::message::{MessageSettings, DEFAULT_TEMPLATE};
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub message_settings: MessageSettings,
+    pub message_settings_cb: Callback<MessageSettings>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    MessageSubmitted,
+    ResetTemplate,
+}
+
+#[derive(Default)]
+pub struct SetMessage {
+    terminal_node_ref: NodeRef,
+    template_node_ref: NodeRef,
+    json_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for SetMessage {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::MessageSubmitted => {
+                if let (Some(terminal_input), Some(template_input), Some(json_input)) = (
+                    self.terminal_node_ref.cast::<HtmlInputElement>(),
+                    self.template_node_ref.cast::<HtmlTextAreaElement>(),
+                    self.json_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let terminal_name = terminal_input.value().trim().to_string();
+
+                    let settings = MessageSettings {
+                        terminal_name: (!terminal_name.is_empty()).then_some(terminal_name),
+                        template: template_input.value(),
+                        json: json_input.checked(),
+                    };
+
+                    match settings.validate() {
+                        Ok(()) => {
+                            self.error = None;
+                            ctx.props().message_settings_cb.emit(settings);
+                        }
+                        Err(err) => self.error = Some(err.to_string()),
+                    }
+                }
+
+                true
+            }
+            Msg::ResetTemplate => {
+                if let Some(template_input) = self.template_node_ref.cast::<HtmlTextAreaElement>() {
+                    template_input.set_value(DEFAULT_TEMPLATE);
+                }
+
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_message = ctx.link().callback(|_| Msg::MessageSubmitted);
+        let reset_template = ctx.link().callback(|_| Msg::ResetTemplate);
+        let settings = &ctx.props().message_settings;
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Message sent with each token. Placeholders: {time} {amount} {share} {fiat} {rate} {reference} {terminal} {token}"}</p>
+              <input type="text" name="terminal_name" id="terminal_name" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Terminal name"} value={settings.terminal_name.clone().unwrap_or_default()} ref={self.terminal_node_ref.clone()} />
+              <textarea name="template" id="template" rows="8" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" value={settings.template.clone()} ref={self.template_node_ref.clone()} />
+              <label class="flex items-center mt-4 text-5xl lg:text-lg dark:text-gray-400">
+                <input type="checkbox" name="json" id="json" class="mr-2" checked={settings.json} ref={self.json_node_ref.clone()} />
+                {"Send JSON for merchant bots"}
+              </label>
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={set_message}>{"Save"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={reset_template}>{"Default"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::admin::{self, Cashier};
+
+/// Shortest PIN accepted
+const MIN_PIN_LEN: usize = 4;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub pin_set: bool,
+    /// The PIN is only removed while a manager can still open the settings
+    pub cashiers: Vec<Cashier>,
+    /// New PIN, or `None` to remove it
+    pub pin_cb: Callback<Option<String>>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    PinSubmitted,
+    PinRemoved,
+}
+
+#[derive(Default)]
+pub struct SetPin {
+    pin_node_ref: NodeRef,
+    confirm_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for SetPin {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::PinSubmitted => {
+                if let (Some(pin_input), Some(confirm_input)) = (
+                    self.pin_node_ref.cast::<HtmlInputElement>(),
+                    self.confirm_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let pin = pin_input.value();
+
+                    if pin.chars().count() < MIN_PIN_LEN {
+                        self.error = Some(format!("PIN must be at least {} digits", MIN_PIN_LEN));
+                    } else if pin != confirm_input.value() {
+                        self.error = Some("PINs do not match".to_string());
+                    } else {
+                        self.error = None;
+                        pin_input.set_value("");
+                        confirm_input.set_value("");
+                        ctx.props().pin_cb.emit(Some(pin));
+                    }
+                }
+
+                true
+            }
+            Msg::PinRemoved => {
+                match admin::check_remove_pin(&ctx.props().cashiers) {
+                    Ok(()) => {
+                        self.error = None;
+                        ctx.props().pin_cb.emit(None);
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_pin = ctx.link().callback(|_| Msg::PinSubmitted);
+        let remove_pin = ctx.link().callback(|_| Msg::PinRemoved);
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Required to open the settings"}</p>
+              <input type="password" inputmode="numeric" name="new_pin" id="new_pin" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"New PIN"} ref={self.pin_node_ref.clone()} />
+              <input type="password" inputmode="numeric" name="confirm_pin" id="confirm_pin" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Confirm PIN"} ref={self.confirm_node_ref.clone()} />
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={set_pin}>{"Save"}</button>
+                {
+                    if ctx.props().pin_set {
+                        html! { <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={remove_pin}>{"Remove"}</button> }
+                    } else {
+                        html! {}
+                    }
+                }
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
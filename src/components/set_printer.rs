@@ -0,0 +1,99 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::escpos::{PaperWidth, PrinterSettings};
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub printer_settings: Option<PrinterSettings>,
+    pub printer_set_cb: Callback<Option<PrinterSettings>>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    PrinterSubmitted,
+}
+
+#[derive(Default)]
+pub struct SetPrinter {
+    width_node_ref: NodeRef,
+    logo_node_ref: NodeRef,
+}
+
+impl Component for SetPrinter {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::PrinterSubmitted => {
+                if let (Some(width_input), Some(logo_input)) = (
+                    self.width_node_ref.cast::<HtmlSelectElement>(),
+                    self.logo_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let width = match width_input.value().as_str() {
+                        "58" => Some(PaperWidth::Mm58),
+                        "80" => Some(PaperWidth::Mm80),
+                        _ => None,
+                    };
+
+                    ctx.props()
+                        .printer_set_cb
+                        .emit(width.map(|width| PrinterSettings {
+                            width,
+                            logo: logo_input.checked(),
+                        }));
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_printer = ctx.link().callback(|_| Msg::PrinterSubmitted);
+
+        let (selected, logo) = match ctx.props().printer_settings {
+            Some(PrinterSettings {
+                width: PaperWidth::Mm58,
+                logo,
+            }) => ("58", logo),
+            Some(PrinterSettings {
+                width: PaperWidth::Mm80,
+                logo,
+            }) => ("80", logo),
+            None => ("off", false),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Print receipts on an ESC/POS thermal printer connected over USB or serial"}</p>
+              <select class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 dark:text-white dark:bg-gray-800 dark:border-gray-600" ref={self.width_node_ref.clone()}>
+                <option value="off" selected={selected == "off"}>{"No printer"}</option>
+                <option value="58" selected={selected == "58"}>{"58mm paper"}</option>
+                <option value="80" selected={selected == "80"}>{"80mm paper"}</option>
+              </select>
+              <label class="flex items-center mt-4 text-5xl lg:text-lg dark:text-gray-400">
+                <input type="checkbox" name="logo" id="logo" class="mr-2" checked={logo} ref={self.logo_node_ref.clone()} />
+                {"Print logo stored on the printer"}
+              </label>
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={set_printer}>{"Save"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use nostr_sdk::key::FromPkStr;
+use nostr_sdk::prelude::ToBech32;
+use nostr_sdk::prelude::XOnlyPublicKey;
+use nostr_sdk::Keys;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    /// Key the merchant sends configuration to
+    pub pos_pubkey: Option<XOnlyPublicKey>,
+    /// Paired merchant admin key
+    pub merchant: Option<XOnlyPublicKey>,
+    pub merchant_cb: Callback<Option<Keys>>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    PairSubmitted,
+    Unpair,
+}
+
+#[derive(Default)]
+pub struct SetRemote {
+    merchant_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for SetRemote {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::PairSubmitted => {
+                if let Some(merchant_input) = self.merchant_node_ref.cast::<HtmlInputElement>() {
+                    match Keys::from_pk_str(merchant_input.value().trim()) {
+                        Ok(keys) => {
+                            self.error = None;
+                            ctx.props().merchant_cb.emit(Some(keys));
+                        }
+                        Err(_) => self.error = Some("Invalid merchant key".to_string()),
+                    }
+                }
+
+                true
+            }
+            Msg::Unpair => {
+                self.error = None;
+                ctx.props().merchant_cb.emit(None);
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let pair = ctx.link().callback(|_| Msg::PairSubmitted);
+        let unpair = ctx.link().callback(|_| Msg::Unpair);
+
+        let terminal = ctx
+            .props()
+            .pos_pubkey
+            .and_then(|pubkey| pubkey.to_bech32().ok())
+            .unwrap_or_default();
+        let merchant = ctx
+            .props()
+            .merchant
+            .and_then(|pubkey| pubkey.to_bech32().ok())
+            .unwrap_or_default();
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 break-all dark:text-gray-400">{format!("Send configuration to {}", terminal)}</p>
+              <input type="text" name="merchant" id="merchant" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Merchant admin npub"} value={merchant} ref={self.merchant_node_ref.clone()} />
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={pair}>{"Pair"}</button>
+                {
+                    if ctx.props().merchant.is_some() {
+                        html! { <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={unpair}>{"Unpair"}</button> }
+                    } else {
+                        html! {}
+                    }
+                }
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+use bitcoin::address::{Address, NetworkUnchecked};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub shop_name: Option<String>,
+    pub shop_name_cb: Callback<Option<String>>,
+    pub auto_return: Option<u32>,
+    pub auto_return_cb: Callback<Option<u32>>,
+    pub onchain_address: Option<String>,
+    pub onchain_address_cb: Callback<Option<String>>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    ShopSubmitted,
+}
+
+#[derive(Default)]
+pub struct SetShop {
+    shop_name_node_ref: NodeRef,
+    auto_return_node_ref: NodeRef,
+    onchain_address_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for SetShop {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ShopSubmitted => {
+                if let (Some(shop_name_input), Some(auto_return_input), Some(address_input)) = (
+                    self.shop_name_node_ref.cast::<HtmlInputElement>(),
+                    self.auto_return_node_ref.cast::<HtmlInputElement>(),
+                    self.onchain_address_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let auto_return = match auto_return_input.value().trim() {
+                        "" => Ok(None),
+                        seconds => seconds.parse::<u32>().map(Some),
+                    };
+                    let address = address_input.value().trim().to_string();
+
+                    match auto_return {
+                        Err(_) => self.error = Some("Invalid delay".to_string()),
+                        Ok(_)
+                            if !address.is_empty()
+                                && Address::<NetworkUnchecked>::from_str(&address).is_err() =>
+                        {
+                            self.error = Some("Invalid bitcoin address".to_string())
+                        }
+                        Ok(auto_return) => {
+                            let shop_name = shop_name_input.value().trim().to_string();
+
+                            self.error = None;
+                            ctx.props().auto_return_cb.emit(auto_return);
+                            ctx.props()
+                                .onchain_address_cb
+                                .emit((!address.is_empty()).then_some(address));
+                            ctx.props()
+                                .shop_name_cb
+                                .emit((!shop_name.is_empty()).then_some(shop_name));
+                        }
+                    }
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_shop = ctx.link().callback(|_| Msg::ShopSubmitted);
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Shown to customers in their wallet along with the order reference"}</p>
+              <input type="text" name="shop_name" id="shop_name" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Shop name"} value={ctx.props().shop_name.clone().unwrap_or_default()} ref={self.shop_name_node_ref.clone()} />
+              <input type="text" name="auto_return" id="auto_return" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Return to keypad after paid (seconds)"} value={ctx.props().auto_return.map(|s| s.to_string()).unwrap_or_default()} ref={self.auto_return_node_ref.clone()} />
+              <input type="text" name="onchain_address" id="onchain_address" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Bitcoin address for the unified QR (optional)"} value={ctx.props().onchain_address.clone().unwrap_or_default()} ref={self.onchain_address_node_ref.clone()} />
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={set_shop}>{"Save"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
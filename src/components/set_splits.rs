@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use nostr_sdk::key::FromPkStr;
+use nostr_sdk::Keys;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::split::{Share, SplitRecipient};
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub splits: Vec<SplitRecipient>,
+    pub add_split_cb: Callback<SplitRecipient>,
+    pub clear_splits_cb: Callback<MouseEvent>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    SplitSubmitted,
+}
+
+#[derive(Default)]
+pub struct SetSplits {
+    pubkey_node_ref: NodeRef,
+    share_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for SetSplits {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SplitSubmitted => {
+                if let (Some(pubkey_input), Some(share_input)) = (
+                    self.pubkey_node_ref.cast::<HtmlInputElement>(),
+                    self.share_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let pubkey = Keys::from_pk_str(&pubkey_input.value());
+                    let share = Share::from_str(&share_input.value());
+
+                    match (pubkey, share) {
+                        (Ok(keys), Ok(share)) => {
+                            pubkey_input.set_value("");
+                            share_input.set_value("");
+                            self.error = None;
+
+                            ctx.props().add_split_cb.emit(SplitRecipient {
+                                pubkey: keys.public_key(),
+                                share,
+                            });
+                        }
+                        (Err(_), _) => self.error = Some("Invalid pubkey".to_string()),
+                        (_, Err(_)) => self.error = Some("Invalid share".to_string()),
+                    }
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let add_split = ctx.link().callback(|_| Msg::SplitSubmitted);
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <p class="mb-4 dark:text-gray-400">{"Splits are paid first, the receiver gets the rest and any tip"}</p>
+              {
+                  ctx.props().splits.iter().map(|split| {
+                      html! {
+                          <p class="flex-1 dark:text-gray-400" style="word-wrap: break-word;">{format!("{} {}", split.share, split.pubkey)}</p>
+                      }
+                  }).collect::<Html>()
+              }
+              <input type="text" name="split_pubkey" id="split_pubkey" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Nostr Pubkey"} ref={self.pubkey_node_ref.clone()} />
+              <input type="text" name="split_share" id="split_share" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Share (10% or 500 sats)"} ref={self.share_node_ref.clone()} />
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={add_split}>{"Add Split"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().clear_splits_cb.clone()}>{"Clear"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
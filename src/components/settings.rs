@@ -5,6 +5,22 @@ pub struct Props {
     pub add_relay_cb: Callback<MouseEvent>,
     pub set_pubkey_cb: Callback<MouseEvent>,
     pub set_mint_cb: Callback<MouseEvent>,
+    pub set_splits_cb: Callback<MouseEvent>,
+    pub set_melt_cb: Callback<MouseEvent>,
+    pub set_shop_cb: Callback<MouseEvent>,
+    pub set_message_cb: Callback<MouseEvent>,
+    pub set_printer_cb: Callback<MouseEvent>,
+    pub shift_cb: Callback<MouseEvent>,
+    pub sales_cb: Callback<MouseEvent>,
+    pub export_cb: Callback<MouseEvent>,
+    pub sync_cb: Callback<MouseEvent>,
+    pub set_pin_cb: Callback<MouseEvent>,
+    pub cashiers_cb: Callback<MouseEvent>,
+    pub remote_cb: Callback<MouseEvent>,
+    pub transfer_cb: Callback<MouseEvent>,
+    pub dashboard_cb: Callback<MouseEvent>,
+    pub wallet_cb: Callback<MouseEvent>,
+    pub backup_cb: Callback<MouseEvent>,
     pub home_cb: Callback<MouseEvent>,
 }
 
@@ -28,6 +44,22 @@ impl Component for Settings {
                 <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_pubkey_cb.clone()}>{"Set Receiver"}</button>
                 <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().add_relay_cb.clone()}>{"Add relay"}</button>
                 <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_mint_cb.clone()}>{"Set Mint"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_splits_cb.clone()}>{"Revenue Split"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_melt_cb.clone()}>{"Auto Melt"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_shop_cb.clone()}>{"Shop"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_message_cb.clone()}>{"Token Message"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_printer_cb.clone()}>{"Printer"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().shift_cb.clone()}>{"Shift"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().sales_cb.clone()}>{"Sales & Refunds"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().export_cb.clone()}>{"Export"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().sync_cb.clone()}>{"Sales Sync"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().set_pin_cb.clone()}>{"Admin PIN"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().cashiers_cb.clone()}>{"Cashiers"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().remote_cb.clone()}>{"Remote Config"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().transfer_cb.clone()}>{"Export & Import"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().dashboard_cb.clone()}>{"Merchant Dashboard"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().wallet_cb.clone()}>{"Terminal Wallet"}</button>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().backup_cb.clone()}>{"Backup & Restore"}</button>
                 <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={ctx.props().home_cb.clone()}>{"Home"}</button>
               </div>
             </a>
@@ -0,0 +1,149 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::bindings;
+use crate::shift::{Shift, ZReport};
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub shift: Option<Shift>,
+    /// Running totals of the open shift or the report of the last closed one
+    pub report: Option<ZReport>,
+    /// Same report over the sales synced from every terminal
+    #[prop_or_default]
+    pub combined: Option<ZReport>,
+    pub open_cb: Callback<String>,
+    /// Closes the shift, DMing the report to the receiver when set
+    pub close_cb: Callback<bool>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    OpenSubmitted,
+    CloseSubmitted,
+    Download,
+}
+
+#[derive(Default)]
+pub struct ShiftView {
+    cashier_node_ref: NodeRef,
+    dm_node_ref: NodeRef,
+}
+
+impl Component for ShiftView {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::OpenSubmitted => {
+                if let Some(cashier_input) = self.cashier_node_ref.cast::<HtmlInputElement>() {
+                    let cashier = cashier_input.value().trim().to_string();
+                    if !cashier.is_empty() {
+                        ctx.props().open_cb.emit(cashier);
+                    }
+                }
+
+                true
+            }
+            Msg::CloseSubmitted => {
+                let dm = self
+                    .dm_node_ref
+                    .cast::<HtmlInputElement>()
+                    .map(|input| input.checked())
+                    .unwrap_or_default();
+                ctx.props().close_cb.emit(dm);
+
+                true
+            }
+            Msg::Download => {
+                if let Some(report) = &ctx.props().report {
+                    if let Ok(json) = serde_json::to_string_pretty(report) {
+                        bindings::download(
+                            &format!("z-report-{}.json", report.shift.opened_at),
+                            &json,
+                            "application/json",
+                        );
+                    }
+                }
+
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let open = ctx.link().callback(|_| Msg::OpenSubmitted);
+        let close = ctx.link().callback(|_| Msg::CloseSubmitted);
+        let download = ctx.link().callback(|_| Msg::Download);
+
+        let report = match &ctx.props().report {
+            Some(report) => html! {
+                <>
+                {
+                    report.to_text().lines().map(|line| {
+                        html! { <p class="text-xl lg:text-lg dark:text-gray-400">{line}</p> }
+                    }).collect::<Html>()
+                }
+                <button class="px-6 py-2 mt-4 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={download}>{"Download"}</button>
+                </>
+            },
+            None => html! {},
+        };
+
+        let combined = match &ctx.props().combined {
+            Some(combined) => html! {
+                <>
+                <p class="mt-8 text-xl lg:text-lg font-bold dark:text-gray-400">{"All terminals"}</p>
+                {
+                    combined.to_text().lines().map(|line| {
+                        html! { <p class="text-xl lg:text-lg dark:text-gray-400">{line}</p> }
+                    }).collect::<Html>()
+                }
+                </>
+            },
+            None => html! {},
+        };
+
+        let controls = match &ctx.props().shift {
+            Some(_) => html! {
+                <>
+                <label class="flex items-center mt-8 text-5xl lg:text-lg dark:text-gray-400">
+                  <input type="checkbox" name="dm_report" id="dm_report" class="mr-2" checked={true} ref={self.dm_node_ref.clone()} />
+                  {"Send report to the receiver"}
+                </label>
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={close}>{"Close shift"}</button>
+                </>
+            },
+            None => html! {
+                <>
+                <input type="text" name="cashier" id="cashier" class="block py-4 px-6 mt-8 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Cashier name"} ref={self.cashier_node_ref.clone()} />
+                <button class="block w-full text-5xl lg:text-xl p-6 my-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900" onclick={open}>{"Open shift"}</button>
+                </>
+            },
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              { report }
+              { combined }
+              { controls }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
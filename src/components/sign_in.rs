@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub names: Vec<String>,
+    /// Seconds until the PIN of each locked account can be tried again
+    pub locked: HashMap<String, u64>,
+    pub error: Option<String>,
+    /// Name and PIN of the cashier
+    pub sign_in_cb: Callback<(String, String)>,
+    pub settings_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    NameSelected,
+    SignInSubmitted,
+}
+
+#[derive(Default)]
+pub struct SignIn {
+    /// Account picked, the first one until another is selected
+    name: Option<String>,
+    name_node_ref: NodeRef,
+    pin_node_ref: NodeRef,
+}
+
+impl Component for SignIn {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::NameSelected => {
+                self.name = self
+                    .name_node_ref
+                    .cast::<HtmlSelectElement>()
+                    .map(|input| input.value());
+
+                true
+            }
+            Msg::SignInSubmitted => {
+                if let (Some(name_input), Some(pin_input)) = (
+                    self.name_node_ref.cast::<HtmlSelectElement>(),
+                    self.pin_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let pin = pin_input.value();
+                    pin_input.set_value("");
+
+                    if !pin.is_empty() {
+                        ctx.props().sign_in_cb.emit((name_input.value(), pin));
+                    }
+                }
+
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let sign_in = ctx.link().callback(|_| Msg::SignInSubmitted);
+        let select = ctx.link().callback(|_| Msg::NameSelected);
+
+        let name = self.name.as_ref().or(ctx.props().names.first());
+        let locked_for = name.and_then(|name| ctx.props().locked.get(name).copied());
+
+        let error = match (locked_for, &ctx.props().error) {
+            (Some(seconds), _) => Some(format!(
+                "Too many attempts, try again in {} seconds",
+                seconds
+            )),
+            (None, error) => error.clone(),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <select class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 dark:text-white dark:bg-gray-800 dark:border-gray-600" onchange={select} ref={self.name_node_ref.clone()}>
+                {
+                    ctx.props().names.iter().map(|name| {
+                        html! { <option value={name.clone()}>{name}</option> }
+                    }).collect::<Html>()
+                }
+              </select>
+              <input type="password" inputmode="numeric" name="pin" id="pin" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"PIN"} disabled={locked_for.is_some()} ref={self.pin_node_ref.clone()} />
+              {
+                  if let Some(error) = error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={sign_in}>{"Sign in"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().settings_cb.clone()}>{"Settings"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use cashu_crab::Amount;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::wallet_store::SweepSchedule;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    pub balance: Option<Amount>,
+    /// Why the stored proofs could not be read
+    pub load_error: Option<String>,
+    pub sweep_schedule: Option<SweepSchedule>,
+    pub sweep_schedule_cb: Callback<Option<SweepSchedule>>,
+    pub sweep_cb: Callback<MouseEvent>,
+    pub home_cb: Callback<MouseEvent>,
+}
+
+pub enum Msg {
+    ScheduleSubmitted,
+}
+
+#[derive(Default)]
+pub struct TerminalWallet {
+    schedule_node_ref: NodeRef,
+    hour_node_ref: NodeRef,
+    error: Option<String>,
+}
+
+impl Component for TerminalWallet {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ScheduleSubmitted => {
+                if let (Some(schedule_input), Some(hour_input)) = (
+                    self.schedule_node_ref.cast::<HtmlSelectElement>(),
+                    self.hour_node_ref.cast::<HtmlInputElement>(),
+                ) {
+                    let schedule = match schedule_input.value().as_str() {
+                        "hourly" => Some(SweepSchedule::Hourly),
+                        "end_of_shift" => match hour_input.value().trim().parse::<u8>() {
+                            Ok(hour) if hour < 24 => Some(SweepSchedule::EndOfShift(hour)),
+                            _ => {
+                                self.error = Some("Invalid shift end hour".to_string());
+                                return true;
+                            }
+                        },
+                        "on_demand" => Some(SweepSchedule::OnDemand),
+                        _ => None,
+                    };
+
+                    self.error = None;
+                    ctx.props().sweep_schedule_cb.emit(schedule);
+                }
+
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let set_schedule = ctx.link().callback(|_| Msg::ScheduleSubmitted);
+
+        let balance = match ctx.props().balance {
+            Some(balance) => format!("{} sats", balance.to_sat()),
+            None => "Loading...".to_string(),
+        };
+
+        let (selected, hour) = match ctx.props().sweep_schedule {
+            Some(SweepSchedule::Hourly) => ("hourly", String::new()),
+            Some(SweepSchedule::EndOfShift(hour)) => ("end_of_shift", hour.to_string()),
+            Some(SweepSchedule::OnDemand) => ("on_demand", String::new()),
+            None => ("off", String::new()),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <h1 class="text-3xl mb-4 font-semibold leadi text-center">{balance}</h1>
+              {
+                  if let Some(load_error) = &ctx.props().load_error {
+                      html! { <p class="mb-4 text-red-500">{format!("{}. Nothing will be saved to the terminal wallet until it loads.", load_error)}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <p class="mb-4 dark:text-gray-400">{"Keep the receiver's share on the terminal and sweep it as one token"}</p>
+              <select class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 dark:text-white dark:bg-gray-800 dark:border-gray-600" ref={self.schedule_node_ref.clone()}>
+                <option value="off" selected={selected == "off"}>{"Off (send every sale)"}</option>
+                <option value="hourly" selected={selected == "hourly"}>{"Hourly"}</option>
+                <option value="end_of_shift" selected={selected == "end_of_shift"}>{"End of shift"}</option>
+                <option value="on_demand" selected={selected == "on_demand"}>{"On demand"}</option>
+              </select>
+              <input type="text" name="shift_end" id="shift_end" class="block py-4 px-6 mt-4 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Shift end hour (0-23)"} value={hour} ref={self.hour_node_ref.clone()} />
+              {
+                  if let Some(error) = &self.error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={set_schedule}>{"Save"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().sweep_cb.clone()}>{"Sweep Now"}</button>
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={ctx.props().home_cb.clone()}>{"Done"}</button>
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
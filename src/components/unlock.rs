@@ -0,0 +1,91 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct Props {
+    /// Seconds until the PIN can be tried again
+    pub locked_for: Option<u64>,
+    pub error: Option<String>,
+    pub unlock_cb: Callback<String>,
+    /// Not set when the PIN is needed to start the terminal
+    #[prop_or_default]
+    pub home_cb: Option<Callback<MouseEvent>>,
+}
+
+pub enum Msg {
+    PinSubmitted,
+}
+
+#[derive(Default)]
+pub struct Unlock {
+    pin_node_ref: NodeRef,
+}
+
+impl Component for Unlock {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::PinSubmitted => {
+                if let Some(pin_input) = self.pin_node_ref.cast::<HtmlInputElement>() {
+                    let pin = pin_input.value();
+                    pin_input.set_value("");
+
+                    if !pin.is_empty() {
+                        ctx.props().unlock_cb.emit(pin);
+                    }
+                }
+
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let unlock = ctx.link().callback(|_| Msg::PinSubmitted);
+
+        let error = match (ctx.props().locked_for, &ctx.props().error) {
+            (Some(seconds), _) => Some(format!(
+                "Too many attempts, try again in {} seconds",
+                seconds
+            )),
+            (None, error) => error.clone(),
+        };
+
+        html! {
+            <>
+            <div class="flex justify-center">
+          <a class="block p-8 bg-white border border-gray-200 rounded-lg shadow-lg hover:bg-gray-100 dark:bg-gray-800 dark:border-gray-700 dark:hover:bg-gray-700 w-full lg:w-1/2">
+            <div class="relative z-0 w-full mb-8 group">
+              <input type="password" inputmode="numeric" name="pin" id="pin" class="block py-4 px-6 w-full text-5xl lg:text-lg text-gray-900 bg-transparent border-2 border-gray-300 appearance-none dark:text-white dark:border-gray-600 dark:focus:border-blue-500 focus:outline-none focus:border-blue-600 peer" placeholder={"Admin PIN"} disabled={ctx.props().locked_for.is_some()} ref={self.pin_node_ref.clone()} />
+              {
+                  if let Some(error) = error {
+                      html! { <p class="mt-2 text-red-500">{error}</p> }
+                  } else {
+                      html! {}
+                  }
+              }
+              <div class="flex justify-center mt-8">
+                <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={unlock}>{"Unlock"}</button>
+                {
+                    if let Some(home_cb) = ctx.props().home_cb.clone() {
+                        html! { <button class="px-6 py-2 mt-2 rounded-sm shadow-sm dark:bg-violet-400 dark:text-gray-900 text-5xl lg:text-lg" onclick={home_cb}>{"Cancel"}</button> }
+                    } else {
+                        html! {}
+                    }
+                }
+              </div>
+            </div>
+          </a>
+        </div>
+        </>
+        }
+    }
+}
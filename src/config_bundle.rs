@@ -0,0 +1,260 @@
+//! Configuration export and import between terminals
+//!
+//! A bundle is the [`RemoteConfig`] schema with a version, exported as a
+//! NIP-78 event signed by the terminal key so the importing terminal can show
+//! where it came from. Bundles are trusted when signed by the paired merchant
+//! or a key the manager trusted before. Plain bundle JSON is accepted as well
+//! but flagged as unsigned.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use nostr_sdk::prelude::{Event, EventBuilder, FromBech32, Keys, Kind, Tag, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::remote_config::RemoteConfig;
+
+/// Current bundle schema version
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// NIP-78 application specific data
+const APP_DATA_KIND: u16 = 30078;
+
+const BUNDLE_IDENTIFIER: &str = "cashcrab:config";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub config: RemoteConfig,
+}
+
+impl ConfigBundle {
+    pub fn new(config: RemoteConfig) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            config,
+        }
+    }
+
+    /// Bundle as an event signed by `keys`
+    pub fn to_event(&self, keys: &Keys) -> Result<Event> {
+        let tags = [Tag::Identifier(BUNDLE_IDENTIFIER.to_string())];
+
+        Ok(EventBuilder::new(
+            Kind::ParameterizedReplaceable(APP_DATA_KIND),
+            &serde_json::to_string(self)?,
+            &tags,
+        )
+        .to_event(keys)?)
+    }
+
+    /// Parses and validates a signed or plain bundle, returning the signer
+    pub fn parse(bundle: &str) -> Result<(Self, Option<XOnlyPublicKey>)> {
+        let (content, signer) = match Event::from_json(bundle.trim()) {
+            Ok(event) => {
+                event.verify()?;
+                (event.content, Some(event.pubkey))
+            }
+            Err(_) => (bundle.trim().to_string(), None),
+        };
+
+        let bundle: ConfigBundle = serde_json::from_str(&content)?;
+        if bundle.version != BUNDLE_VERSION {
+            bail!("Unsupported bundle version {}", bundle.version);
+        }
+        bundle.config.validate()?;
+
+        Ok((bundle, signer))
+    }
+}
+
+/// Who a bundle comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signer {
+    /// The paired merchant or a key trusted on this terminal
+    Trusted(XOnlyPublicKey),
+    /// Signed by a key this terminal doesn't know
+    Unknown(XOnlyPublicKey),
+    Unsigned,
+}
+
+impl Signer {
+    pub fn new(signer: Option<XOnlyPublicKey>, trusted: &[XOnlyPublicKey]) -> Self {
+        match signer {
+            Some(signer) if trusted.contains(&signer) => Signer::Trusted(signer),
+            Some(signer) => Signer::Unknown(signer),
+            None => Signer::Unsigned,
+        }
+    }
+}
+
+/// Receiver as hex so the npub and hex forms of a key compare equal
+fn receiver_hex(receiver: &Option<String>) -> Option<String> {
+    receiver.as_ref().map(|receiver| {
+        XOnlyPublicKey::from_str(receiver)
+            .or_else(|_| XOnlyPublicKey::from_bech32(receiver))
+            .map(|key| key.to_string())
+            .unwrap_or_else(|_| receiver.clone())
+    })
+}
+
+fn change<T: PartialEq + std::fmt::Debug>(
+    changes: &mut Vec<String>,
+    name: &str,
+    current: &Option<T>,
+    new: &Option<T>,
+) {
+    if new.is_some() && new != current {
+        changes.push(format!("{}: {:?} → {:?}", name, current, new));
+    }
+}
+
+/// What importing `new` over `current` would change, one line per change
+pub fn diff(current: &RemoteConfig, new: &RemoteConfig) -> Vec<String> {
+    let mut changes = vec![];
+
+    change(&mut changes, "Mint", &current.mint_url, &new.mint_url);
+    change(
+        &mut changes,
+        "Receiver",
+        &receiver_hex(&current.receiver),
+        &receiver_hex(&new.receiver),
+    );
+
+    if let Some(relays) = &new.relays {
+        let current_relays = current.relays.clone().unwrap_or_default();
+        changes.extend(
+            relays
+                .difference(&current_relays)
+                .map(|relay| format!("Add relay {}", relay)),
+        );
+        changes.extend(
+            current_relays
+                .difference(relays)
+                .map(|relay| format!("Remove relay {}", relay)),
+        );
+    }
+
+    if let Some(splits) = &new.splits {
+        if Some(splits) != current.splits.as_ref() {
+            changes.push(format!(
+                "Splits: {}",
+                splits
+                    .iter()
+                    .map(|s| format!("{} to {}", s.share, s.pubkey))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    change(
+        &mut changes,
+        "Shop name",
+        &current.shop_name,
+        &new.shop_name,
+    );
+    change(
+        &mut changes,
+        "Return to keypad after",
+        &current.auto_return,
+        &new.auto_return,
+    );
+    if new.message.is_some() && new.message != current.message {
+        changes.push("Token message".to_string());
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use url::Url;
+
+    use super::*;
+
+    const KEY: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn relays(urls: &[&str]) -> Option<HashSet<Url>> {
+        Some(urls.iter().map(|url| Url::parse(url).unwrap()).collect())
+    }
+
+    #[test]
+    fn parse_plain_bundle() {
+        let json = r#"{"version":1,"config":{"shop_name":"Cafe"}}"#;
+
+        let (bundle, signer) = ConfigBundle::parse(json).unwrap();
+
+        assert_eq!(bundle.config.shop_name.as_deref(), Some("Cafe"));
+        assert_eq!(signer, None);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_bundles() {
+        // Newer schema
+        assert!(ConfigBundle::parse(r#"{"version":2,"config":{}}"#).is_err());
+        // Unknown fields
+        assert!(ConfigBundle::parse(r#"{"version":1,"config":{},"extra":1}"#).is_err());
+        assert!(ConfigBundle::parse(r#"{"version":1,"config":{"shop":"Cafe"}}"#).is_err());
+        // Fails validation
+        assert!(ConfigBundle::parse(r#"{"version":1,"config":{"relays":[]}}"#).is_err());
+        assert!(ConfigBundle::parse("not json").is_err());
+    }
+
+    #[test]
+    fn signer_trust() {
+        let key: XOnlyPublicKey = KEY.parse().unwrap();
+
+        assert_eq!(Signer::new(Some(key), &[key]), Signer::Trusted(key));
+        assert_eq!(Signer::new(Some(key), &[]), Signer::Unknown(key));
+        assert_eq!(Signer::new(None, &[key]), Signer::Unsigned);
+    }
+
+    #[test]
+    fn diff_lists_changes() {
+        let current = RemoteConfig {
+            relays: relays(&["wss://a.example", "wss://b.example"]),
+            shop_name: Some("Cafe".to_string()),
+            ..Default::default()
+        };
+        let new = RemoteConfig {
+            relays: relays(&["wss://b.example", "wss://c.example"]),
+            shop_name: Some("Bar".to_string()),
+            auto_return: None,
+            ..Default::default()
+        };
+
+        let changes = diff(&current, &new);
+
+        assert_eq!(
+            changes,
+            vec![
+                "Add relay wss://c.example/",
+                "Remove relay wss://a.example/",
+                "Shop name: Some(\"Cafe\") → Some(\"Bar\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_skips_unchanged_and_missing_fields() {
+        let current = RemoteConfig {
+            receiver: Some(KEY.to_string()),
+            shop_name: Some("Cafe".to_string()),
+            ..Default::default()
+        };
+
+        assert!(diff(&current, &current).is_empty());
+        assert!(diff(&current, &RemoteConfig::default()).is_empty());
+
+        // Same key in another case is the same receiver
+        let new = RemoteConfig {
+            receiver: Some(KEY.to_uppercase()),
+            ..Default::default()
+        };
+        assert!(diff(&current, &new).is_empty());
+    }
+}
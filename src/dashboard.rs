@@ -0,0 +1,621 @@
+//! Read-only live sales feed for the merchant
+//!
+//! The merchant logs in with a NIP-07 signer extension or a NIP-46 remote
+//! signer. Token DMs addressed to their key are decrypted by the signer and
+//! the cashu token in each is decoded for the amount, so the feed shows what
+//! has been received, not what was invoiced. Anyone can DM the merchant, so
+//! only tokens sent by known terminal keys are trusted for their terminal name.
+//!
+//! Received tokens can be redeemed: their proofs are checked with the mint
+//! (NUT-07) and the unspent ones swapped in one request per mint for a single
+//! fresh token the merchant can take to their wallet. Each fresh token is
+//! saved and reported even if saving fails, and a mint failing doesn't stop the
+//! others. A token is only claimed once every mint in it has been redeemed from.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use cashu_crab::{
+    nuts::nut00::{wallet::Token, Proofs},
+    Amount,
+};
+use log::warn;
+use nostr_sdk::prelude::{
+    Client, Filter, FromPkStr, Keys, Kind, RelayPoolNotification, Timestamp, ToBech32,
+    XOnlyPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use yew::Callback;
+
+use crate::bindings;
+use crate::melt;
+use crate::message::TokenMessage;
+use crate::nip46::{BunkerUri, RemoteSigner};
+use crate::storage::{CLAIMED_TOKENS_KEY, TOKEN_STATUS_KEY};
+use crate::wallet::Wallet;
+use crate::wallet_store;
+
+/// How far back the feed starts
+pub const FEED_SECS: u64 = 24 * 60 * 60;
+
+const HOUR_SECS: u64 = 60 * 60;
+
+/// Token received from a terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedItem {
+    /// Event id, as relays send the same DM more than once
+    pub id: String,
+    /// Terminal name from JSON messages of known terminals, otherwise the
+    /// sending key
+    pub terminal: String,
+    /// Sent by a known terminal key
+    pub verified: bool,
+    pub created_at: u64,
+    pub amount: Amount,
+    /// Sale amount the token is a share of, if the message says
+    pub sale_amount: Option<Amount>,
+    /// Encoded cashu token
+    pub token: String,
+    pub status: Option<TokenStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenStatus {
+    Claimed,
+    /// Spent before it was redeemed, wholly or in part
+    Spent,
+}
+
+/// Status of a token at each mint it has been redeemed from
+pub type MintStatuses = BTreeMap<Url, TokenStatus>;
+
+/// Result of redeeming the tokens in the feed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redemption {
+    /// One fresh token per mint holding everything claimed
+    pub tokens: Vec<String>,
+    pub claimed: Amount,
+    /// Tokens with proofs already spent
+    pub spent: Vec<String>,
+    /// Status of the tokens at the mints redeemed from
+    pub statuses: Vec<(String, Url, TokenStatus)>,
+    /// Fresh tokens that could not be saved
+    pub unsaved: Vec<String>,
+    /// Mints that could not be redeemed from, with the error
+    pub failed: Vec<(Url, String)>,
+}
+
+impl Redemption {
+    /// Summary shown once redeeming is done
+    pub fn status(&self) -> String {
+        let mut status = format!(
+            "Claimed {} sats, {} tokens were already spent",
+            self.claimed.to_sat(),
+            self.spent.len()
+        );
+        for (mint_url, err) in &self.failed {
+            status.push_str(&format!(". Could not redeem from {}: {}", mint_url, err));
+        }
+        if !self.unsaved.is_empty() {
+            status.push_str(&format!(
+                ". {} claimed tokens could not be saved, copy them now",
+                self.unsaved.len()
+            ));
+        }
+
+        status
+    }
+}
+
+/// Proofs of one mint across the tokens being redeemed
+#[derive(Debug, Default)]
+struct MintProofs {
+    /// Each proof once, as a token may be received more than once
+    proofs: Proofs,
+    /// Encoded tokens with the secrets of their proofs at this mint
+    sources: Vec<(String, HashSet<String>)>,
+}
+
+impl MintProofs {
+    fn add(&mut self, encoded: &str, proofs: Proofs) {
+        let secrets = proofs.iter().map(|p| p.secret.to_string()).collect();
+        for proof in proofs {
+            if !self.proofs.iter().any(|p| p.secret == proof.secret) {
+                self.proofs.push(proof);
+            }
+        }
+        self.sources.push((encoded.to_string(), secrets));
+    }
+}
+
+/// Status of a token with proofs at `mints`, `None` while a mint is left to
+/// redeem from
+fn status_of(mints: &[Url], statuses: Option<&MintStatuses>) -> Option<TokenStatus> {
+    let statuses = mints
+        .iter()
+        .map(|mint| statuses?.get(mint).copied())
+        .collect::<Option<Vec<_>>>()?;
+
+    match statuses.contains(&TokenStatus::Spent) {
+        true => Some(TokenStatus::Spent),
+        false => Some(TokenStatus::Claimed),
+    }
+}
+
+/// Status of an encoded token given the saved statuses
+pub fn token_status(
+    encoded: &str,
+    statuses: &HashMap<String, MintStatuses>,
+) -> Option<TokenStatus> {
+    let token = Token::from_str(encoded).ok()?;
+    let mints: Vec<Url> = token.token.into_iter().map(|m| m.mint).collect();
+
+    status_of(&mints, statuses.get(encoded))
+}
+
+/// Status of each token at the mints redeemed from before
+pub async fn load_statuses() -> Result<HashMap<String, MintStatuses>> {
+    Ok(wallet_store::get_encrypted(TOKEN_STATUS_KEY)
+        .await?
+        .unwrap_or_default())
+}
+
+/// Adds the status of tokens at a mint to `saved`
+pub fn add_statuses(
+    saved: &mut HashMap<String, MintStatuses>,
+    statuses: &[(String, Url, TokenStatus)],
+) {
+    for (encoded, mint_url, status) in statuses {
+        saved
+            .entry(encoded.clone())
+            .or_default()
+            .insert(mint_url.clone(), *status);
+    }
+}
+
+async fn save_statuses(statuses: &[(String, Url, TokenStatus)]) -> Result<()> {
+    let mut saved = load_statuses().await?;
+    add_statuses(&mut saved, statuses);
+
+    wallet_store::set_encrypted(TOKEN_STATUS_KEY, &saved).await
+}
+
+/// Proofs of `tokens` at each mint they have not been redeemed from yet
+fn pending_by_mint(
+    tokens: Vec<(String, Token)>,
+    statuses: &HashMap<String, MintStatuses>,
+) -> BTreeMap<Url, MintProofs> {
+    let mut by_mint: BTreeMap<Url, MintProofs> = BTreeMap::new();
+    for (encoded, token) in tokens {
+        let done = statuses.get(&encoded);
+        for mint_proofs in token.token {
+            if done.is_some_and(|done| done.contains_key(&mint_proofs.mint)) {
+                continue;
+            }
+
+            by_mint
+                .entry(mint_proofs.mint)
+                .or_default()
+                .add(&encoded, mint_proofs.proofs);
+        }
+    }
+
+    by_mint
+}
+
+/// Adds a freshly claimed token to the saved ones
+async fn save_claimed(token: &str) -> Result<()> {
+    let mut claimed: Vec<String> = wallet_store::get_encrypted(CLAIMED_TOKENS_KEY)
+        .await?
+        .unwrap_or_default();
+    claimed.push(token.to_string());
+
+    wallet_store::set_encrypted(CLAIMED_TOKENS_KEY, &claimed).await
+}
+
+/// Amount of the token in a template or JSON token message
+fn parse_message(message: &str) -> Option<(Amount, String, Option<TokenMessage>)> {
+    let json: Option<TokenMessage> = serde_json::from_str(message).ok();
+    let token = match &json {
+        Some(json) => json.token.clone(),
+        None => message
+            .split_whitespace()
+            .find(|word| word.starts_with("cashu"))?
+            .to_string(),
+    };
+
+    let amount = Token::from_str(&token)
+        .ok()?
+        .token
+        .iter()
+        .map(|t| melt::proofs_amount(&t.proofs).to_sat())
+        .sum();
+
+    Some((Amount::from_sat(amount), token, json))
+}
+
+/// How the merchant logs in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Login {
+    /// NIP-07 browser extension
+    Extension,
+    /// NIP-46 remote signer
+    Bunker(BunkerUri),
+}
+
+/// Decrypts DMs to the merchant without the terminal holding their key
+#[derive(Debug, Clone)]
+pub enum Signer {
+    Extension,
+    Remote(RemoteSigner),
+}
+
+impl Signer {
+    /// Connects to the signer, returning it with the merchant key
+    pub async fn login(login: Login) -> Result<(Self, XOnlyPublicKey)> {
+        match login {
+            Login::Extension => {
+                let merchant = bindings::get_pubkey()
+                    .await
+                    .as_string()
+                    .and_then(|k| Keys::from_pk_str(&k).ok())
+                    .ok_or(anyhow!("No nostr signer found"))?;
+
+                Ok((Signer::Extension, merchant.public_key()))
+            }
+            Login::Bunker(uri) => {
+                let (remote, merchant) = RemoteSigner::connect(&uri).await?;
+
+                Ok((Signer::Remote(remote), merchant))
+            }
+        }
+    }
+
+    async fn decrypt(&self, sender: XOnlyPublicKey, content: String) -> Option<String> {
+        match self {
+            Signer::Extension => bindings::decrypt_content(sender.to_string(), content)
+                .await
+                .as_string(),
+            Signer::Remote(remote) => remote.decrypt(sender, content).await.ok(),
+        }
+    }
+}
+
+/// Relay client and signer of the logged in merchant
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub client: Client,
+    pub signer: Signer,
+}
+
+impl Session {
+    /// Disconnects so the feed of this login stops
+    pub async fn shutdown(self) {
+        if let Err(err) = self.client.shutdown().await {
+            warn!("Could not close dashboard client {:?}", err);
+        }
+        if let Signer::Remote(remote) = self.signer {
+            if let Err(err) = remote.shutdown().await {
+                warn!("Could not close signer connection {:?}", err);
+            }
+        }
+    }
+}
+
+/// Emits the tokens DMed to `merchant` since `since` and as they arrive
+///
+/// Tokens from keys other than `terminals` are marked unverified.
+pub async fn listen(
+    session: Session,
+    merchant: XOnlyPublicKey,
+    terminals: HashSet<XOnlyPublicKey>,
+    since: u64,
+    item_cb: Callback<FeedItem>,
+) -> Result<()> {
+    let Session { client, signer } = session;
+
+    let filter = Filter::new()
+        .pubkey(merchant)
+        .kind(Kind::EncryptedDirectMessage)
+        .since(Timestamp::from(since));
+
+    let mut notifications = client.notifications();
+    client.subscribe(vec![filter]).await;
+
+    while let Ok(notification) = notifications.recv().await {
+        if let RelayPoolNotification::Event(_, event) = notification {
+            if event.kind != Kind::EncryptedDirectMessage || event.verify().is_err() {
+                continue;
+            }
+
+            let message = signer.decrypt(event.pubkey, event.content.clone()).await;
+            let (amount, token, json) = match message.and_then(|m| parse_message(&m)) {
+                Some(parsed) => parsed,
+                None => continue,
+            };
+
+            // Names in messages from unknown keys could pass for a real terminal
+            let verified = terminals.contains(&event.pubkey);
+            let terminal = json
+                .as_ref()
+                .filter(|_| verified)
+                .and_then(|j| j.terminal.clone())
+                .or_else(|| event.pubkey.to_bech32().ok())
+                .unwrap_or_else(|| event.pubkey.to_string());
+
+            item_cb.emit(FeedItem {
+                id: event.id.to_hex(),
+                terminal,
+                verified,
+                created_at: event.created_at.as_u64(),
+                amount,
+                sale_amount: json.and_then(|j| j.amount),
+                token,
+                status: None,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Received amount and token count per terminal
+pub fn per_terminal(feed: &[FeedItem]) -> BTreeMap<String, (Amount, usize)> {
+    let mut totals: BTreeMap<String, (Amount, usize)> = BTreeMap::new();
+    for item in feed {
+        let (amount, count) = totals.entry(item.terminal.clone()).or_default();
+        *amount = Amount::from_sat(amount.to_sat() + item.amount.to_sat());
+        *count += 1;
+    }
+
+    totals
+}
+
+/// Received amount per hour, keyed by the unix time the hour starts
+pub fn per_hour(feed: &[FeedItem]) -> BTreeMap<u64, Amount> {
+    let mut totals: BTreeMap<u64, Amount> = BTreeMap::new();
+    for item in feed {
+        let hour = item.created_at - item.created_at % HOUR_SECS;
+        let amount = totals.entry(hour).or_default();
+        *amount = Amount::from_sat(amount.to_sat() + item.amount.to_sat());
+    }
+
+    totals
+}
+
+/// Claims the unspent proofs of `tokens` from one mint
+///
+/// Returns the fresh token with its amount, if anything was unspent, and the
+/// tokens with spent proofs.
+async fn redeem_mint(
+    mint_url: &Url,
+    mint_proofs: &MintProofs,
+) -> Result<(Option<(String, Amount)>, HashSet<String>)> {
+    let wallet = Wallet::new(mint_url).await?;
+
+    let status = wallet.check_spent(&mint_proofs.proofs).await?;
+    let spent: HashSet<String> = mint_proofs
+        .sources
+        .iter()
+        .filter(|(_, secrets)| status.spent.iter().any(|p| secrets.contains(&p.secret)))
+        .map(|(encoded, _)| encoded.clone())
+        .collect();
+
+    if status.spendable.is_empty() {
+        return Ok((None, spent));
+    }
+
+    // Every unspent proof of this mint in one swap
+    // Not derived from the terminal seed as the merchant takes the token away
+    let claimed = wallet.receive(None, &status.spendable).await?;
+    let amount = melt::proofs_amount(&claimed);
+    let token = wallet.proofs_to_token(claimed, None)?;
+
+    Ok((Some((token, amount)), spent))
+}
+
+/// Claims the unspent proofs of `tokens`, reporting the tokens already spent
+///
+/// Mints are redeemed from one at a time and the status of their tokens saved,
+/// so a failing mint only leaves its own proofs unredeemed for the next try.
+/// Fails if the saved statuses can't be read, as the proofs claimed before
+/// would be taken for spent.
+pub async fn redeem(tokens: Vec<String>) -> Result<Redemption> {
+    let saved = load_statuses().await?;
+
+    let tokens = tokens
+        .into_iter()
+        .filter_map(|encoded| match Token::from_str(&encoded) {
+            Ok(token) => Some((encoded, token)),
+            Err(err) => {
+                warn!("Could not decode token {:?}", err);
+                None
+            }
+        })
+        .collect();
+    let by_mint = pending_by_mint(tokens, &saved);
+
+    let mut redemption = Redemption::default();
+    let mut spent_tokens = HashSet::new();
+    for (mint_url, mint_proofs) in by_mint {
+        let (claimed, spent) = match redeem_mint(&mint_url, &mint_proofs).await {
+            Ok(result) => result,
+            Err(err) => {
+                warn!("Could not redeem from {} {:?}", mint_url, err);
+                redemption.failed.push((mint_url, err.to_string()));
+                continue;
+            }
+        };
+
+        let mut statuses = vec![];
+        for (encoded, _) in &mint_proofs.sources {
+            if spent.contains(encoded) {
+                statuses.push((encoded.clone(), mint_url.clone(), TokenStatus::Spent));
+            } else if claimed.is_some() {
+                statuses.push((encoded.clone(), mint_url.clone(), TokenStatus::Claimed));
+            }
+        }
+        spent_tokens.extend(spent);
+
+        // Returned even if not saved so it can still be copied
+        if let Some((token, amount)) = claimed {
+            if let Err(err) = save_claimed(&token).await {
+                warn!("Could not save claimed token {:?}", err);
+                redemption.unsaved.push(token.clone());
+            }
+            redemption.claimed = Amount::from_sat(redemption.claimed.to_sat() + amount.to_sat());
+            redemption.tokens.push(token);
+        }
+
+        if let Err(err) = save_statuses(&statuses).await {
+            warn!("Could not save token status {:?}", err);
+        }
+        redemption.statuses.extend(statuses);
+    }
+    redemption.spent = spent_tokens.into_iter().collect();
+
+    Ok(redemption)
+}
+
+#[cfg(test)]
+mod tests {
+    use cashu_crab::nuts::nut00;
+
+    use super::*;
+
+    fn item(id: &str, terminal: &str, created_at: u64, sats: u64) -> FeedItem {
+        FeedItem {
+            id: id.to_string(),
+            terminal: terminal.to_string(),
+            verified: true,
+            created_at,
+            amount: Amount::from_sat(sats),
+            sale_amount: None,
+            token: format!("cashuA{}", id),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn totals_per_terminal() {
+        let feed = vec![
+            item("1", "Bar", 1_700_000_000, 100),
+            item("2", "Door", 1_700_000_100, 50),
+            item("3", "Bar", 1_700_000_200, 25),
+        ];
+
+        let totals = per_terminal(&feed);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals["Bar"], (Amount::from_sat(125), 2));
+        assert_eq!(totals["Door"], (Amount::from_sat(50), 1));
+        assert!(per_terminal(&[]).is_empty());
+    }
+
+    #[test]
+    fn totals_per_hour() {
+        // 1_699_999_200 starts an hour
+        let feed = vec![
+            item("1", "Bar", 1_699_999_200, 100),
+            item("2", "Bar", 1_700_002_799, 50),
+            item("3", "Door", 1_700_002_800, 25),
+        ];
+
+        let totals = per_hour(&feed);
+        assert_eq!(
+            totals.into_iter().collect::<Vec<_>>(),
+            vec![
+                (1_699_999_200, Amount::from_sat(150)),
+                (1_700_002_800, Amount::from_sat(25)),
+            ]
+        );
+    }
+
+    #[test]
+    fn redemption_status_lists_failed_mints() {
+        let mut redemption = Redemption {
+            tokens: vec!["cashuAfresh".to_string()],
+            claimed: Amount::from_sat(2100),
+            spent: vec!["cashuAspent".to_string()],
+            statuses: vec![],
+            unsaved: vec![],
+            failed: vec![],
+        };
+        assert_eq!(
+            redemption.status(),
+            "Claimed 2100 sats, 1 tokens were already spent"
+        );
+
+        redemption.failed.push((
+            Url::from_str("https://mint.example.com").unwrap(),
+            "timed out".to_string(),
+        ));
+        assert_eq!(
+            redemption.status(),
+            "Claimed 2100 sats, 1 tokens were already spent. Could not redeem from https://mint.example.com/: timed out"
+        );
+
+        redemption.failed.clear();
+        redemption.unsaved.push("cashuAfresh".to_string());
+        assert_eq!(
+            redemption.status(),
+            "Claimed 2100 sats, 1 tokens were already spent. 1 claimed tokens could not be saved, copy them now"
+        );
+    }
+
+    fn token_at(mints: &[&Url]) -> Token {
+        Token {
+            token: mints
+                .iter()
+                .map(|mint| nut00::wallet::MintProofs {
+                    mint: (*mint).clone(),
+                    proofs: vec![],
+                })
+                .collect(),
+            memo: None,
+        }
+    }
+
+    #[test]
+    fn token_comes_back_until_every_mint_redeemed() {
+        let a = Url::from_str("https://a.example.com").unwrap();
+        let b = Url::from_str("https://b.example.com").unwrap();
+        let tokens = || vec![("cashuAab".to_string(), token_at(&[&a, &b]))];
+        let mints = [a.clone(), b.clone()];
+        let mut saved = HashMap::new();
+
+        let pending = pending_by_mint(tokens(), &saved);
+        assert_eq!(pending.keys().collect::<Vec<_>>(), vec![&a, &b]);
+
+        // Claimed at A while B failed
+        add_statuses(
+            &mut saved,
+            &[("cashuAab".to_string(), a.clone(), TokenStatus::Claimed)],
+        );
+        assert_eq!(status_of(&mints, saved.get("cashuAab")), None);
+        let pending = pending_by_mint(tokens(), &saved);
+        assert_eq!(pending.keys().collect::<Vec<_>>(), vec![&b]);
+        assert_eq!(pending[&b].sources[0].0, "cashuAab");
+
+        add_statuses(
+            &mut saved,
+            &[("cashuAab".to_string(), b.clone(), TokenStatus::Claimed)],
+        );
+        assert_eq!(
+            status_of(&mints, saved.get("cashuAab")),
+            Some(TokenStatus::Claimed)
+        );
+        assert!(pending_by_mint(tokens(), &saved).is_empty());
+
+        add_statuses(
+            &mut saved,
+            &[("cashuAab".to_string(), b.clone(), TokenStatus::Spent)],
+        );
+        assert_eq!(
+            status_of(&mints, saved.get("cashuAab")),
+            Some(TokenStatus::Spent)
+        );
+        assert_eq!(status_of(&mints, None), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Blinding for v1 mints (NUT-00)
+//!
+//! cashu-crab blinds with the hash to curve of the legacy api, which v1 mints
+//! reject when the proofs are spent, so outputs are blinded here instead.
+
+use anyhow::{bail, Result};
+use cashu_crab::{
+    nuts::{
+        nut00::{wallet::BlindedMessages, BlindedMessage},
+        nut01::{PublicKey, SecretKey},
+    },
+    Amount,
+};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::ProjectivePoint;
+use sha2::{Digest, Sha256};
+
+const DOMAIN_SEPARATOR: &[u8] = b"Secp256k1_HashToCurve_Cashu_";
+
+/// Point `Y` a secret is blinded from and spent as
+pub fn hash_to_curve(message: &[u8]) -> Result<k256::PublicKey> {
+    let msg_to_hash = Sha256::new()
+        .chain_update(DOMAIN_SEPARATOR)
+        .chain_update(message)
+        .finalize();
+
+    for counter in 0u32..u16::MAX as u32 {
+        let hash = Sha256::new()
+            .chain_update(msg_to_hash)
+            .chain_update(counter.to_le_bytes())
+            .finalize();
+
+        let mut compressed = [0x02; 33];
+        compressed[1..].copy_from_slice(&hash);
+        if let Ok(point) = k256::PublicKey::from_sec1_bytes(&compressed) {
+            return Ok(point);
+        }
+    }
+
+    bail!("No point on the curve for message")
+}
+
+/// Hex `Y` of a proof secret as used by the checkstate endpoint (NUT-07)
+pub fn y(secret: &str) -> Result<String> {
+    Ok(hex::encode(
+        hash_to_curve(secret.as_bytes())?
+            .to_encoded_point(true)
+            .as_bytes(),
+    ))
+}
+
+/// Blinded message `B_ = Y + rG`
+pub fn blind(secret: &str, r: &k256::SecretKey) -> Result<k256::PublicKey> {
+    let y = hash_to_curve(secret.as_bytes())?;
+    let b = ProjectivePoint::from(y.as_affine())
+        + ProjectivePoint::GENERATOR * r.to_nonzero_scalar().as_ref();
+
+    Ok(k256::PublicKey::from_affine(b.to_affine())?)
+}
+
+fn random_bytes() -> Result<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn random_secret_key() -> Result<k256::SecretKey> {
+    loop {
+        // Out of range values are too rare to need more than a retry
+        if let Ok(key) = k256::SecretKey::from_slice(&random_bytes()?) {
+            return Ok(key);
+        }
+    }
+}
+
+/// Outputs with random secrets for `amounts`
+pub fn random_outputs(amounts: Vec<Amount>) -> Result<BlindedMessages> {
+    let mut blinded_messages = BlindedMessages::default();
+
+    for amount in amounts {
+        let secret = hex::encode(random_bytes()?);
+        let r = random_secret_key()?;
+        let b = blind(&secret, &r)?;
+
+        blinded_messages.blinded_messages.push(BlindedMessage {
+            amount,
+            b: PublicKey::from(b),
+        });
+        blinded_messages.secrets.push(secret);
+        blinded_messages.rs.push(SecretKey::from(r));
+        blinded_messages.amounts.push(amount);
+    }
+
+    Ok(blinded_messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex_point(point: &k256::PublicKey) -> String {
+        hex::encode(point.to_encoded_point(true).as_bytes())
+    }
+
+    #[test]
+    fn hash_to_curve_matches_nut00_vectors() {
+        let mut message = [0u8; 32];
+        assert_eq!(
+            hex_point(&hash_to_curve(&message).unwrap()),
+            "024cce997d3b518f739663b757deaec95bcd9473c30a14ac2fd04023a739d1a725"
+        );
+
+        message[31] = 1;
+        assert_eq!(
+            hex_point(&hash_to_curve(&message).unwrap()),
+            "022e7158e11c9506f1aa4248bf531298daa7febd6194f003edcd9b93ade6253acf"
+        );
+
+        message[31] = 2;
+        assert_eq!(
+            hex_point(&hash_to_curve(&message).unwrap()),
+            "026cdbe15362df59cd1dd3c9c11de8aedac2106eca69236ecd9fbe117af897be4f"
+        );
+    }
+
+    #[test]
+    fn blind_matches_nut00_vector() {
+        let mut r = [0u8; 32];
+        r[31] = 1;
+        let r = k256::SecretKey::from_slice(&r).unwrap();
+
+        assert_eq!(
+            hex_point(&blind("test_message", &r).unwrap()),
+            "025cc16fe33b953e2ace39653efb3e7a7049711ae1d8a2f7a9108753f1cdea742b"
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! ESC/POS receipts for thermal printers
+
+use serde::{Deserialize, Serialize};
+
+use crate::receipt::Receipt;
+use crate::sale;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+const FS: u8 = 0x1c;
+const LF: u8 = 0x0a;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaperWidth {
+    Mm58,
+    Mm80,
+}
+
+impl PaperWidth {
+    /// Characters per line in the default font
+    fn columns(&self) -> usize {
+        match self {
+            PaperWidth::Mm58 => 32,
+            PaperWidth::Mm80 => 48,
+        }
+    }
+
+    /// QR module size in dots
+    fn qr_module(&self) -> u8 {
+        match self {
+            PaperWidth::Mm58 => 4,
+            PaperWidth::Mm80 => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrinterSettings {
+    pub width: PaperWidth,
+    /// Print the logo stored in the printer's NV memory
+    pub logo: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+    Left = 0,
+    Center = 1,
+}
+
+/// Builder for an ESC/POS byte stream
+#[derive(Debug, Default)]
+struct EscPos {
+    bytes: Vec<u8>,
+}
+
+impl EscPos {
+    fn init(mut self) -> Self {
+        self.bytes.extend([ESC, b'@']);
+        self
+    }
+
+    /// Selects code page 437, which [`printable`] maps text to
+    fn codepage(mut self) -> Self {
+        self.bytes.extend([ESC, b't', 0]);
+        self
+    }
+
+    fn align(mut self, align: Align) -> Self {
+        self.bytes.extend([ESC, b'a', align as u8]);
+        self
+    }
+
+    fn bold(mut self, bold: bool) -> Self {
+        self.bytes.extend([ESC, b'E', bold as u8]);
+        self
+    }
+
+    fn double_size(mut self, double: bool) -> Self {
+        let size = if double { 0x11 } else { 0x00 };
+        self.bytes.extend([GS, b'!', size]);
+        self
+    }
+
+    fn nv_logo(mut self) -> Self {
+        self.bytes.extend([FS, b'p', 1, 0]);
+        self
+    }
+
+    fn text(mut self, text: &str) -> Self {
+        self.bytes.extend(text.chars().map(printable));
+        self
+    }
+
+    fn line(self, text: &str) -> Self {
+        self.text(text).feed(1)
+    }
+
+    fn feed(mut self, lines: usize) -> Self {
+        self.bytes.resize(self.bytes.len() + lines, LF);
+        self
+    }
+
+    /// QR code using the GS ( k function 165 commands
+    fn qr(mut self, data: &str, module: u8) -> Self {
+        // Model 2
+        self.bytes
+            .extend([GS, b'(', b'k', 4, 0, 0x31, 0x41, 0x32, 0x00]);
+        self.bytes
+            .extend([GS, b'(', b'k', 3, 0, 0x31, 0x43, module]);
+        // Error correction level M
+        self.bytes.extend([GS, b'(', b'k', 3, 0, 0x31, 0x45, 0x31]);
+
+        let len = data.len() + 3;
+        self.bytes.extend([
+            GS,
+            b'(',
+            b'k',
+            (len % 256) as u8,
+            (len / 256) as u8,
+            0x31,
+            0x50,
+            0x30,
+        ]);
+        self.bytes.extend(data.bytes());
+
+        self.bytes.extend([GS, b'(', b'k', 3, 0, 0x31, 0x51, 0x30]);
+        self
+    }
+
+    fn cut(mut self) -> Self {
+        // Feed to the cutter then partial cut
+        self.bytes.extend([GS, b'V', 66, 0]);
+        self
+    }
+}
+
+/// Replaces characters outside of the printer's default code page
+fn printable(c: char) -> u8 {
+    match c {
+        '–' | '—' => b'-',
+        '’' | '‘' => b'\'',
+        c if c.is_ascii() && !c.is_ascii_control() => c as u8,
+        _ => b'?',
+    }
+}
+
+/// `left` and `right` on one line, truncating `left` if they don't fit
+fn columns(left: &str, right: &str, width: usize) -> String {
+    let right_len = right.chars().count();
+    let left: String = left
+        .chars()
+        .take(width.saturating_sub(right_len + 1))
+        .collect();
+    let padding = width.saturating_sub(left.chars().count() + right_len);
+
+    format!("{}{}{}", left, " ".repeat(padding), right)
+}
+
+/// ESC/POS bytes printing the receipt
+pub fn render(receipt: &Receipt, settings: &PrinterSettings) -> Vec<u8> {
+    let width = settings.width.columns();
+    let rule = "-".repeat(width);
+
+    let mut printer = EscPos::default().init().codepage().align(Align::Center);
+
+    if settings.logo {
+        printer = printer.nv_logo();
+    }
+
+    if let Some(shop) = &receipt.shop {
+        printer = printer.double_size(true).line(shop).double_size(false);
+    }
+    if let Some(terminal) = &receipt.terminal {
+        printer = printer.line(terminal);
+    }
+    printer = printer.line(&sale::format_iso(receipt.time));
+
+    printer = printer.align(Align::Left).line(&rule);
+    for item in &receipt.items {
+        printer = printer.line(&columns(
+            &item.description,
+            &format!("{} sats", item.amount.to_sat()),
+            width,
+        ));
+    }
+    printer = printer
+        .line(&rule)
+        .bold(true)
+        .line(&columns(
+            "TOTAL",
+            &format!("{} sats", receipt.amount.to_sat()),
+            width,
+        ))
+        .bold(false)
+        .line(&columns("", &format!("{} USD", receipt.fiat_value), width))
+        .feed(1);
+
+    printer
+        .align(Align::Center)
+        .qr(&receipt.sale, settings.width.qr_module())
+        .line("Payment hash")
+        .feed(3)
+        .cut()
+        .bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use cashu_crab::Amount;
+
+    use super::*;
+    use crate::receipt::ReceiptItem;
+
+    fn receipt() -> Receipt {
+        Receipt {
+            sale: "ab".to_string(),
+            time: 0,
+            shop: Some("Cafe".to_string()),
+            terminal: None,
+            reference: None,
+            items: vec![ReceiptItem {
+                description: "Sale".to_string(),
+                amount: Amount::from_sat(21),
+            }],
+            amount: Amount::from_sat(21),
+            fiat_value: "0.01".to_string(),
+        }
+    }
+
+    fn line(text: &str) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.push(LF);
+        bytes
+    }
+
+    #[test]
+    fn receipt_golden_bytes() {
+        let settings = PrinterSettings {
+            width: PaperWidth::Mm58,
+            logo: false,
+        };
+
+        let expected = [
+            // Init, code page 437, centered
+            vec![ESC, b'@', ESC, b't', 0, ESC, b'a', 1],
+            vec![GS, b'!', 0x11],
+            line("Cafe"),
+            vec![GS, b'!', 0x00],
+            line("1970-01-01T00:00:00Z"),
+            vec![ESC, b'a', 0],
+            line(&"-".repeat(32)),
+            line(&format!("Sale{}21 sats", " ".repeat(21))),
+            line(&"-".repeat(32)),
+            vec![ESC, b'E', 1],
+            line(&format!("TOTAL{}21 sats", " ".repeat(20))),
+            vec![ESC, b'E', 0],
+            line(&format!("{}0.01 USD", " ".repeat(24))),
+            vec![LF],
+            vec![ESC, b'a', 1],
+            // QR model 2, module size, error correction M, store, print
+            vec![GS, b'(', b'k', 4, 0, 0x31, 0x41, 0x32, 0x00],
+            vec![GS, b'(', b'k', 3, 0, 0x31, 0x43, 4],
+            vec![GS, b'(', b'k', 3, 0, 0x31, 0x45, 0x31],
+            vec![GS, b'(', b'k', 5, 0, 0x31, 0x50, 0x30, b'a', b'b'],
+            vec![GS, b'(', b'k', 3, 0, 0x31, 0x51, 0x30],
+            line("Payment hash"),
+            vec![LF, LF, LF],
+            // Partial cut after feeding to the cutter
+            vec![GS, b'V', 66, 0],
+        ]
+        .concat();
+
+        assert_eq!(render(&receipt(), &settings), expected);
+    }
+
+    #[test]
+    fn logo_and_wide_paper() {
+        let settings = PrinterSettings {
+            width: PaperWidth::Mm80,
+            logo: true,
+        };
+
+        let bytes = render(&receipt(), &settings);
+
+        assert_eq!(
+            bytes[..11],
+            [ESC, b'@', ESC, b't', 0, ESC, b'a', 1, FS, b'p', 1]
+        );
+        assert!(bytes
+            .windows(8)
+            .any(|w| w == [GS, b'(', b'k', 3, 0, 0x31, 0x43, 6]));
+        assert!(bytes
+            .windows(49)
+            .any(|w| w == line(&"-".repeat(48)).as_slice()));
+    }
+
+    #[test]
+    fn qr_length_bytes() {
+        let data = "a".repeat(300);
+        let bytes = EscPos::default().qr(&data, 4).bytes;
+
+        // 303 bytes of data and function header, low byte first
+        assert_eq!(bytes[25..33], [GS, b'(', b'k', 47, 1, 0x31, 0x50, 0x30]);
+    }
+
+    #[test]
+    fn text_outside_codepage_is_replaced() {
+        let bytes = EscPos::default().text("Caf\u{e9} \u{2014} \u{2019}s").bytes;
+
+        assert_eq!(bytes, b"Caf? - 's");
+    }
+
+    #[test]
+    fn columns_truncate_left() {
+        assert_eq!(columns("Order 12345", "100 sats", 16), "Order 1 100 sats");
+        assert_eq!(columns("", "1 USD", 8), "   1 USD");
+    }
+}
@@ -0,0 +1,243 @@
+//! Sale history export for accounting
+//!
+//! Both formats have one record per sale with the selected columns in the
+//! order below. CSV files start with a header row of the column names and
+//! JSON files are an array of objects keyed by them.
+//!
+//! | Column         | Type    | Description                                  |
+//! |----------------|---------|----------------------------------------------|
+//! | `time`         | string  | ISO 8601 UTC time the invoice was created    |
+//! | `timestamp`    | integer | Unix time the invoice was created            |
+//! | `payment_hash` | string  | Payment hash of the BOLT11 invoice           |
+//! | `amount_sat`   | integer | Sale amount in sats                          |
+//! | `fiat_usd`     | string  | Dollar value at the rate when sold           |
+//! | `reference`    | string  | Order reference, empty if none               |
+//! | `paid`         | boolean | Whether the sale was paid                    |
+//! | `tip_sat`      | integer | Paid over the sale amount in sats            |
+//! | `retained_sat` | integer | Kept on the terminal in sats                 |
+//! | `undelivered`  | integer | Tokens that could not be DMed                |
+//! | `refunded_sat` | integer | Refunded to the customer in sats             |
+//! | `cashier`      | string  | Cashier signed in, empty if none             |
+//! | `terminal`     | string  | Hex key of the terminal, empty if this one   |
+//!
+//! Columns are only ever added to the end of this list.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::sale::{self, Sale};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Time,
+    Timestamp,
+    PaymentHash,
+    Amount,
+    FiatValue,
+    Reference,
+    Paid,
+    Tip,
+    Retained,
+    Undelivered,
+    Refunded,
+    Cashier,
+    Terminal,
+}
+
+impl Column {
+    pub const ALL: [Column; 13] = [
+        Column::Time,
+        Column::Timestamp,
+        Column::PaymentHash,
+        Column::Amount,
+        Column::FiatValue,
+        Column::Reference,
+        Column::Paid,
+        Column::Tip,
+        Column::Retained,
+        Column::Undelivered,
+        Column::Refunded,
+        Column::Cashier,
+        Column::Terminal,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Time => "time",
+            Column::Timestamp => "timestamp",
+            Column::PaymentHash => "payment_hash",
+            Column::Amount => "amount_sat",
+            Column::FiatValue => "fiat_usd",
+            Column::Reference => "reference",
+            Column::Paid => "paid",
+            Column::Tip => "tip_sat",
+            Column::Retained => "retained_sat",
+            Column::Undelivered => "undelivered",
+            Column::Refunded => "refunded_sat",
+            Column::Cashier => "cashier",
+            Column::Terminal => "terminal",
+        }
+    }
+
+    fn value(&self, sale: &Sale) -> Value {
+        match self {
+            Column::Time => sale::format_iso(sale.created_at).into(),
+            Column::Timestamp => sale.created_at.into(),
+            Column::PaymentHash => sale.hash.clone().into(),
+            Column::Amount => sale.amount.to_sat().into(),
+            Column::FiatValue => sale.fiat_value.clone().into(),
+            Column::Reference => sale.reference.clone().unwrap_or_default().into(),
+            Column::Paid => sale.paid.into(),
+            Column::Tip => sale.tip.to_sat().into(),
+            Column::Retained => sale.retained.to_sat().into(),
+            Column::Undelivered => sale.legs.iter().filter(|l| !l.delivered).count().into(),
+            Column::Refunded => sale.refunded().to_sat().into(),
+            Column::Cashier => sale.cashier.clone().unwrap_or_default().into(),
+            Column::Terminal => sale.terminal.clone().unwrap_or_default().into(),
+        }
+    }
+}
+
+/// Sales created between `from` and `to` (unix time, inclusive)
+fn in_range(sales: &[Sale], from: Option<u64>, to: Option<u64>) -> impl Iterator<Item = &Sale> {
+    sales.iter().filter(move |s| {
+        from.map_or(true, |from| s.created_at >= from) && to.map_or(true, |to| s.created_at <= to)
+    })
+}
+
+/// Characters spreadsheets start a formula with
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+fn csv_field(value: &Value) -> String {
+    let field = match value {
+        // Text typed at the terminal must not run as a formula when opened
+        Value::String(s) if s.starts_with(FORMULA_PREFIXES) => format!("'{}", s),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    };
+
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+pub fn to_csv(sales: &[Sale], from: Option<u64>, to: Option<u64>, columns: &[Column]) -> String {
+    let mut csv = columns
+        .iter()
+        .map(|c| c.name())
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+
+    for sale in in_range(sales, from, to) {
+        let row: Vec<String> = columns.iter().map(|c| csv_field(&c.value(sale))).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub fn to_json(
+    sales: &[Sale],
+    from: Option<u64>,
+    to: Option<u64>,
+    columns: &[Column],
+) -> Result<String> {
+    let records: Vec<Map<String, Value>> = in_range(sales, from, to)
+        .map(|sale| {
+            columns
+                .iter()
+                .map(|c| (c.name().to_string(), c.value(sale)))
+                .collect()
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&records)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use cashu_crab::Amount;
+
+    use super::*;
+
+    fn sale(hash: &str, created_at: u64, reference: Option<&str>) -> Sale {
+        Sale {
+            hash: hash.to_string(),
+            amount: Amount::from_sat(1000),
+            fiat_value: "0.50".to_string(),
+            reference: reference.map(|r| r.to_string()),
+            created_at,
+            paid: true,
+            legs: vec![],
+            retained: Amount::ZERO,
+            tip: Amount::from_sat(10),
+            refunds: vec![],
+            cashier: None,
+            terminal: None,
+        }
+    }
+
+    #[test]
+    fn csv_quotes_separators() {
+        assert_eq!(csv_field(&"plain".into()), "plain");
+        assert_eq!(csv_field(&"a,b".into()), "\"a,b\"");
+        assert_eq!(csv_field(&"say \"hi\"".into()), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field(&"two\nlines".into()), "\"two\nlines\"");
+        assert_eq!(csv_field(&12.into()), "12");
+        assert_eq!(csv_field(&true.into()), "true");
+    }
+
+    #[test]
+    fn csv_escapes_formulas() {
+        assert_eq!(csv_field(&"=1+1".into()), "'=1+1");
+        assert_eq!(csv_field(&"+1".into()), "'+1");
+        assert_eq!(csv_field(&"-1".into()), "'-1");
+        assert_eq!(csv_field(&"@SUM(A1)".into()), "'@SUM(A1)");
+        assert_eq!(
+            csv_field(&"=HYPERLINK(\"x\",\"y\")".into()),
+            "\"'=HYPERLINK(\"\"x\"\",\"\"y\"\")\""
+        );
+        // Numbers are not text and stay as they are
+        assert_eq!(csv_field(&(-1).into()), "-1");
+    }
+
+    #[test]
+    fn csv_rows_in_range() {
+        let sales = [
+            sale("a", 100, Some("=cmd")),
+            sale("b", 200, None),
+            sale("c", 300, None),
+        ];
+        let columns = [Column::PaymentHash, Column::Reference, Column::Tip];
+
+        assert_eq!(
+            to_csv(&sales, Some(100), Some(200), &columns),
+            "payment_hash,reference,tip_sat\na,'=cmd,10\nb,,10\n"
+        );
+        assert_eq!(to_csv(&sales, Some(301), None, &columns).lines().count(), 1);
+        assert_eq!(to_csv(&sales, None, None, &columns).lines().count(), 4);
+    }
+
+    #[test]
+    fn json_records() {
+        let sales = [sale("a", 0, Some("Table 1"))];
+        let json = to_json(
+            &sales,
+            None,
+            None,
+            &[Column::Time, Column::Amount, Column::Paid],
+        )
+        .unwrap();
+
+        let records: Vec<Map<String, Value>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["time"], "1970-01-01T00:00:00Z");
+        assert_eq!(records[0]["amount_sat"], 1000);
+        assert_eq!(records[0]["paid"], true);
+        assert_eq!(records[0].len(), 3);
+    }
+}
@@ -0,0 +1,356 @@
+//! LNURL-pay and Lightning address resolution
+
+use std::future::Future;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use bech32::FromBase32;
+use cashu_crab::{Amount, Invoice};
+use gloo_net::http::Request;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use url::Url;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PayResponse {
+    callback: Url,
+    /// Millisats
+    min_sendable: u64,
+    /// Millisats
+    max_sendable: u64,
+    tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackResponse {
+    pr: String,
+}
+
+/// Error any LNURL endpoint may answer with (LUD-06)
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    status: String,
+    reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Response<T> {
+    Error(ErrorResponse),
+    Ok(T),
+}
+
+/// Parses an LNURL response, turning an error response into an error
+fn parse_response<T: DeserializeOwned>(body: &str) -> Result<T> {
+    match serde_json::from_str(body)? {
+        Response::Error(error) if error.status.eq_ignore_ascii_case("error") => {
+            bail!("LNURL error: {}", error.reason)
+        }
+        Response::Error(_) => bail!("Unexpected LNURL response"),
+        Response::Ok(response) => Ok(response),
+    }
+}
+
+/// Scheme for a Lightning address domain
+///
+/// Onion domains are reached over http (LUD-16), as are local servers so the
+/// resolution can be tried against a stand-in.
+fn address_scheme(domain: &str) -> &'static str {
+    let host = domain.split(':').next().unwrap_or(domain);
+
+    if host == "localhost" || host == "127.0.0.1" || host.ends_with(".onion") {
+        "http"
+    } else {
+        "https"
+    }
+}
+
+/// Url of the LNURL-pay endpoint for a Lightning address (`name@domain`),
+/// a bech32 `lnurl1...` string or a plain pay url
+pub fn pay_url(target: &str) -> Result<Url> {
+    let target = target.trim();
+    let target = target.strip_prefix("lightning:").unwrap_or(target);
+
+    if let Some((name, domain)) = target.split_once('@') {
+        if name.is_empty() || domain.is_empty() {
+            bail!("Invalid Lightning address {}", target);
+        }
+
+        return Ok(Url::parse(&format!(
+            "{}://{}/.well-known/lnurlp/{}",
+            address_scheme(domain),
+            domain,
+            name
+        ))?);
+    }
+
+    if target.to_lowercase().starts_with("lnurl") {
+        let (hrp, data, _variant) = bech32::decode(target)?;
+        if hrp != "lnurl" {
+            bail!("Invalid lnurl prefix {}", hrp);
+        }
+        let url = String::from_utf8(Vec::<u8>::from_base32(&data)?)?;
+
+        return Ok(Url::parse(&url)?);
+    }
+
+    Ok(Url::parse(target)?)
+}
+
+/// Callback url requesting `msat` if the pay response allows it
+fn callback_url(pay_response: PayResponse, msat: u64) -> Result<Url> {
+    if pay_response.tag != "payRequest" {
+        bail!("Not an LNURL-pay endpoint");
+    }
+
+    if msat < pay_response.min_sendable || msat > pay_response.max_sendable {
+        bail!(
+            "Amount {} msat outside of sendable range {}-{}",
+            msat,
+            pay_response.min_sendable,
+            pay_response.max_sendable
+        );
+    }
+
+    let mut callback = pay_response.callback;
+    callback
+        .query_pairs_mut()
+        .append_pair("amount", &msat.to_string());
+
+    Ok(callback)
+}
+
+/// Invoice from the callback response if it is for `msat`
+fn callback_invoice(callback_response: CallbackResponse, msat: u64) -> Result<Invoice> {
+    let invoice = Invoice::from_str(&callback_response.pr)?;
+
+    if invoice.amount_milli_satoshis() != Some(msat) {
+        bail!("Invoice amount does not match requested amount");
+    }
+
+    Ok(invoice)
+}
+
+async fn get(url: Url) -> Result<String> {
+    Ok(Request::get(url.as_str()).send().await?.text().await?)
+}
+
+/// Resolves `target` and requests an invoice for `amount`, fetching with `get`
+async fn resolve<F, Fut>(target: &str, amount: Amount, get: F) -> Result<Invoice>
+where
+    F: Fn(Url) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let msat = amount.to_msat();
+
+    let pay_response = parse_response(&get(pay_url(target)?).await?)?;
+    let callback = callback_url(pay_response, msat)?;
+
+    let callback_response = parse_response(&get(callback).await?)?;
+
+    callback_invoice(callback_response, msat)
+}
+
+/// Requests an invoice for `amount` from an LNURL-pay target
+pub async fn get_invoice(target: &str, amount: Amount) -> Result<Invoice> {
+    resolve(target, amount, get).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    /// 2500u from the BOLT11 examples
+    const INVOICE: &str = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+
+    /// Lightning address server on localhost answering for `alice` with
+    /// `INVOICE` whatever the amount, returning the address
+    fn stand_in(min_sendable: u64, max_sendable: u64) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+
+        let callback = format!("http://{}/callback", host);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = vec![];
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buf[..read]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+                let body = if path == "/.well-known/lnurlp/alice" {
+                    format!(
+                        r#"{{"callback":"{}","minSendable":{},"maxSendable":{},"metadata":"[]","tag":"payRequest"}}"#,
+                        callback, min_sendable, max_sendable
+                    )
+                } else if path.starts_with("/callback?amount=") {
+                    format!(r#"{{"pr":"{}","routes":[]}}"#, INVOICE)
+                } else {
+                    r#"{"status":"ERROR","reason":"Unknown user"}"#.to_string()
+                };
+
+                let response = format!(
+                    "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).ok();
+            }
+        });
+
+        format!("alice@{}", host)
+    }
+
+    /// Plain http GET standing in for the browser fetch
+    async fn http_get(url: Url) -> Result<String> {
+        let host = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+        let mut path = url.path().to_string();
+        if let Some(query) = url.query() {
+            path = format!("{}?{}", path, query);
+        }
+
+        let mut stream = TcpStream::connect(&host)?;
+        let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, host);
+        stream.write_all(request.as_bytes())?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let (_, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or(anyhow::anyhow!("No body"))?;
+        Ok(body.to_string())
+    }
+
+    fn pay_response(tag: &str) -> PayResponse {
+        parse_response(&format!(
+            r#"{{"callback":"https://example.com/lnurlp/alice/callback?k=v","minSendable":1000,"maxSendable":100000000,"metadata":"[]","tag":"{}"}}"#,
+            tag
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn lightning_address_pay_url() {
+        assert_eq!(
+            pay_url("alice@example.com").unwrap().as_str(),
+            "https://example.com/.well-known/lnurlp/alice"
+        );
+        assert_eq!(
+            pay_url(" lightning:alice@example.com ").unwrap().as_str(),
+            "https://example.com/.well-known/lnurlp/alice"
+        );
+        assert_eq!(
+            pay_url("alice@localhost:8080").unwrap().as_str(),
+            "http://localhost:8080/.well-known/lnurlp/alice"
+        );
+        assert!(pay_url("@example.com").is_err());
+        assert!(pay_url("alice@").is_err());
+    }
+
+    #[test]
+    fn bech32_lnurl_pay_url() {
+        let lnurl = "LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS";
+
+        assert_eq!(
+            pay_url(lnurl).unwrap().as_str(),
+            "https://service.com/api?q=3fc3645b439ce8e7f2553a69e5267081d96dcd340693afabe04be7b0ccd178df"
+        );
+    }
+
+    #[test]
+    fn plain_pay_url() {
+        assert_eq!(
+            pay_url("http://127.0.0.1:3000/lnurlp").unwrap().as_str(),
+            "http://127.0.0.1:3000/lnurlp"
+        );
+        assert!(pay_url("not a url").is_err());
+    }
+
+    #[test]
+    fn callback_requests_amount() {
+        let callback = callback_url(pay_response("payRequest"), 21_000).unwrap();
+
+        assert_eq!(
+            callback.as_str(),
+            "https://example.com/lnurlp/alice/callback?k=v&amount=21000"
+        );
+    }
+
+    #[test]
+    fn callback_checks_tag_and_sendable_range() {
+        assert!(callback_url(pay_response("withdrawRequest"), 21_000).is_err());
+        assert!(callback_url(pay_response("payRequest"), 999).is_err());
+        assert!(callback_url(pay_response("payRequest"), 100_000_001).is_err());
+        assert!(callback_url(pay_response("payRequest"), 1000).is_ok());
+        assert!(callback_url(pay_response("payRequest"), 100_000_000).is_ok());
+    }
+
+    #[test]
+    fn error_response() {
+        let err = parse_response::<PayResponse>(r#"{"status":"ERROR","reason":"Unknown user"}"#)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "LNURL error: Unknown user");
+
+        assert!(parse_response::<CallbackResponse>(r#"{"status":"OK","reason":""}"#).is_err());
+        assert!(parse_response::<CallbackResponse>("not json").is_err());
+    }
+
+    #[test]
+    fn callback_invoice_must_match_amount() {
+        let response = CallbackResponse {
+            pr: INVOICE.to_string(),
+        };
+
+        assert!(callback_invoice(response, 1_000_000).is_err());
+    }
+
+    #[test]
+    fn resolves_invoice_from_stand_in() {
+        let address = stand_in(1_000, 1_000_000_000);
+
+        let invoice = block_on(resolve(&address, Amount::from_sat(250_000), http_get)).unwrap();
+        assert_eq!(invoice.to_string(), INVOICE);
+    }
+
+    #[test]
+    fn stand_in_invoice_for_other_amount_rejected() {
+        let address = stand_in(1_000, 1_000_000_000);
+
+        let err = block_on(resolve(&address, Amount::from_sat(21), http_get)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invoice amount does not match requested amount"
+        );
+    }
+
+    #[test]
+    fn stand_in_amount_outside_sendable_rejected() {
+        let address = stand_in(1_000_000, 100_000_000);
+
+        let below = block_on(resolve(&address, Amount::from_sat(999), http_get)).unwrap_err();
+        assert!(below.to_string().contains("outside of sendable range"));
+        let above = block_on(resolve(&address, Amount::from_sat(100_001), http_get)).unwrap_err();
+        assert!(above.to_string().contains("outside of sendable range"));
+    }
+
+    #[test]
+    fn stand_in_error_response() {
+        let address = stand_in(1_000, 1_000_000_000).replace("alice@", "bob@");
+
+        let err = block_on(resolve(&address, Amount::from_sat(21), http_get)).unwrap_err();
+        assert_eq!(err.to_string(), "LNURL error: Unknown user");
+    }
+}
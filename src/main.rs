@@ -1,7 +1,31 @@
+mod admin;
 mod app;
 mod bindings;
 mod components;
+mod config_bundle;
+mod dashboard;
+mod dhke;
+mod escpos;
+mod export;
+mod lnurl;
+mod melt;
+mod message;
+mod mint_api;
+mod nip46;
+mod payment_request;
+mod pending_mint;
+mod quote_watch;
+mod receipt;
+mod remote_config;
+mod sale;
+mod sale_sync;
+mod seed;
+mod shift;
+mod split;
+mod storage;
 mod utls;
+mod wallet;
+mod wallet_store;
 
 use app::App;
 
@@ -0,0 +1,71 @@
+//! Melting the terminal balance to the merchant's Lightning address
+
+use anyhow::{bail, Result};
+use cashu_crab::{nuts::nut00::Proofs, Amount};
+use serde::{Deserialize, Serialize};
+
+use crate::lnurl;
+use crate::seed::Seed;
+use crate::wallet::Wallet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeltSettings {
+    /// Lightning address or LNURL-pay the balance is melted to
+    pub lightning_address: String,
+    /// Balance in sats at which the terminal melts
+    pub threshold: u64,
+    /// Still DM the receiver's share, melting only what stays on the terminal
+    #[serde(default)]
+    pub dm_receiver: bool,
+}
+
+pub fn proofs_amount(proofs: &Proofs) -> Amount {
+    Amount::from_sat(proofs.iter().map(|p| p.amount.to_sat()).sum())
+}
+
+/// Pays out `proofs` to the configured Lightning address via NUT-05
+///
+/// Returns the change from the mint on success.
+pub async fn melt_balance(
+    wallet: &Wallet,
+    seed: Option<&Seed>,
+    settings: &MeltSettings,
+    proofs: Proofs,
+) -> Result<Proofs> {
+    let balance = proofs_amount(&proofs).to_sat();
+
+    // First guess at the fee reserve, corrected once the mint quotes the invoice
+    let mut fee_reserve = (balance / 100).max(2);
+
+    for _ in 0..2 {
+        if balance <= fee_reserve {
+            bail!("Balance too small to cover fee reserve");
+        }
+
+        let amount = Amount::from_sat(balance - fee_reserve);
+        let invoice = lnurl::get_invoice(&settings.lightning_address, amount).await?;
+        let quote = wallet.melt_quote(&invoice).await?;
+        let fee = quote.fee_reserve.to_sat();
+
+        if quote.amount.to_sat() + fee > balance {
+            fee_reserve = fee;
+            continue;
+        }
+
+        let melted = wallet.melt(seed, &quote, proofs).await?;
+
+        if !melted.paid {
+            bail!("Mint did not pay invoice");
+        }
+
+        log::info!(
+            "Melted {} sats to {}",
+            amount.to_sat(),
+            settings.lightning_address
+        );
+
+        return Ok(melted.change.unwrap_or_default());
+    }
+
+    bail!("Could not find an invoice amount that covers the fee")
+}
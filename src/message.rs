@@ -0,0 +1,222 @@
+//! Messages tokens are DMed in
+
+use anyhow::{bail, Result};
+use cashu_crab::Amount;
+use serde::{Deserialize, Serialize};
+
+use crate::sale::{self, Sale};
+
+pub const DEFAULT_TEMPLATE: &str =
+    "Sale {time}\n{amount} sats (${fiat} at {rate} sats/$)\nOrder {reference}\n{terminal}\n\n{token}";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageSettings {
+    pub terminal_name: Option<String>,
+    /// Text with `{time}`, `{amount}`, `{share}`, `{fiat}`, `{rate}`,
+    /// `{reference}`, `{terminal}` and `{token}` placeholders
+    pub template: String,
+    /// Send [`TokenMessage`] JSON instead of the template for merchant bots
+    pub json: bool,
+}
+
+impl Default for MessageSettings {
+    fn default() -> Self {
+        Self {
+            terminal_name: None,
+            template: DEFAULT_TEMPLATE.to_string(),
+            json: false,
+        }
+    }
+}
+
+/// Placeholder the token is inserted at
+const TOKEN_PLACEHOLDER: &str = "{token}";
+
+/// Machine readable token message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenMessage {
+    /// Payment hash of the sale
+    pub sale: Option<String>,
+    /// Unix timestamp of the sale
+    pub time: Option<u64>,
+    /// Sale amount in sats
+    pub amount: Option<Amount>,
+    /// Amount of the token in sats
+    pub share: Amount,
+    pub fiat_value: Option<String>,
+    /// Sats per dollar at the time of the sale
+    pub rate: Option<u64>,
+    pub reference: Option<String>,
+    pub terminal: Option<String>,
+    pub token: String,
+}
+
+impl MessageSettings {
+    /// Rejects templates that would send a message without the token
+    pub fn validate(&self) -> Result<()> {
+        if !self.json && !self.template.contains(TOKEN_PLACEHOLDER) {
+            bail!("Template must contain {}", TOKEN_PLACEHOLDER);
+        }
+
+        Ok(())
+    }
+
+    /// Message for a token worth `share` of `sale`
+    ///
+    /// Tokens not tied to a sale, like sweeps, are sent without a template.
+    pub fn render(&self, sale: Option<&Sale>, share: Amount, token: String) -> String {
+        self.render_with(sale, share, token, sale::format_time)
+    }
+
+    fn render_with(
+        &self,
+        sale: Option<&Sale>,
+        share: Amount,
+        token: String,
+        format_time: impl Fn(u64) -> String,
+    ) -> String {
+        if self.json {
+            let message = TokenMessage {
+                sale: sale.map(|s| s.hash.clone()),
+                time: sale.map(|s| s.created_at),
+                amount: sale.map(|s| s.amount),
+                share,
+                fiat_value: sale.map(|s| s.fiat_value.clone()),
+                rate: sale.and_then(rate),
+                reference: sale.and_then(|s| s.reference.clone()),
+                terminal: self.terminal_name.clone(),
+                token: token.clone(),
+            };
+
+            return serde_json::to_string(&message).unwrap_or(token);
+        }
+
+        let sale = match sale {
+            Some(sale) => sale,
+            None => return token,
+        };
+
+        // Templates from older versions or remote configs may lack the token
+        let template = match self.template.contains(TOKEN_PLACEHOLDER) {
+            true => self.template.clone(),
+            false => format!("{}\n\n{}", self.template, TOKEN_PLACEHOLDER),
+        };
+
+        template
+            .replace("{time}", &format_time(sale.created_at))
+            .replace("{amount}", &sale.amount.to_sat().to_string())
+            .replace("{share}", &share.to_sat().to_string())
+            .replace("{fiat}", &sale.fiat_value)
+            .replace(
+                "{rate}",
+                &rate(sale).map(|r| r.to_string()).unwrap_or_default(),
+            )
+            .replace("{reference}", sale.reference.as_deref().unwrap_or("-"))
+            .replace(
+                "{terminal}",
+                self.terminal_name.as_deref().unwrap_or_default(),
+            )
+            .replace(TOKEN_PLACEHOLDER, &token)
+    }
+}
+
+/// Sats per dollar the sale was priced at
+fn rate(sale: &Sale) -> Option<u64> {
+    let fiat: f64 = sale.fiat_value.parse().ok()?;
+
+    (fiat > 0.0).then(|| (sale.amount.to_sat() as f64 / fiat).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sale() -> Sale {
+        Sale {
+            hash: "hash".to_string(),
+            amount: Amount::from_sat(2000),
+            fiat_value: "1.00".to_string(),
+            reference: Some("Table 4".to_string()),
+            created_at: 1_700_000_000,
+            paid: true,
+            legs: vec![],
+            retained: Amount::ZERO,
+            tip: Amount::ZERO,
+            refunds: vec![],
+            cashier: None,
+            terminal: None,
+        }
+    }
+
+    fn render(settings: &MessageSettings, sale: Option<&Sale>) -> String {
+        settings.render_with(
+            sale,
+            Amount::from_sat(500),
+            "cashuAtoken".to_string(),
+            sale::format_iso,
+        )
+    }
+
+    #[test]
+    fn default_template() {
+        let settings = MessageSettings {
+            terminal_name: Some("Bar".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&settings, Some(&sale())),
+            "Sale 2023-11-14T22:13:20Z\n2000 sats ($1.00 at 2000 sats/$)\nOrder Table 4\nBar\n\ncashuAtoken"
+        );
+    }
+
+    #[test]
+    fn share_and_missing_values() {
+        let settings = MessageSettings {
+            template: "{share} of {amount} for {reference} at {terminal}|{token}".to_string(),
+            ..Default::default()
+        };
+        let mut sale = sale();
+        sale.reference = None;
+        sale.fiat_value = "0".to_string();
+
+        assert_eq!(
+            render(&settings, Some(&sale)),
+            "500 of 2000 for - at |cashuAtoken"
+        );
+    }
+
+    #[test]
+    fn token_appended_when_missing() {
+        let settings = MessageSettings {
+            template: "Thanks".to_string(),
+            ..Default::default()
+        };
+
+        assert!(settings.validate().is_err());
+        assert_eq!(render(&settings, Some(&sale())), "Thanks\n\ncashuAtoken");
+    }
+
+    #[test]
+    fn token_only_without_sale() {
+        assert_eq!(render(&MessageSettings::default(), None), "cashuAtoken");
+    }
+
+    #[test]
+    fn json_message() {
+        let settings = MessageSettings {
+            template: String::new(),
+            json: true,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        let message: TokenMessage =
+            serde_json::from_str(&render(&settings, Some(&sale()))).unwrap();
+
+        assert_eq!(message.sale.as_deref(), Some("hash"));
+        assert_eq!(message.share, Amount::from_sat(500));
+        assert_eq!(message.rate, Some(2000));
+        assert_eq!(message.token, "cashuAtoken");
+    }
+}
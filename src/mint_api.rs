@@ -0,0 +1,505 @@
+//! Mint endpoints of the v1 cashu api (NUT-01 to NUT-09)
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use cashu_crab::{
+    nuts::{
+        nut00::{wallet::BlindedMessages, BlindedSignature, Proofs},
+        nut01::{Keys, PublicKey},
+    },
+    Amount, Invoice,
+};
+use gloo_net::http::{Request, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+/// Blinded message with the keyset id v1 mints expect
+#[derive(Debug, Clone, Serialize)]
+pub struct BlindedOutput {
+    pub amount: Amount,
+    pub id: String,
+    #[serde(rename = "B_")]
+    pub b: PublicKey,
+}
+
+impl BlindedOutput {
+    pub fn from_premint(premint: &BlindedMessages, keyset_id: &str) -> Vec<Self> {
+        premint
+            .blinded_messages
+            .iter()
+            .map(|m| Self {
+                amount: m.amount,
+                id: keyset_id.to_string(),
+                b: m.b.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Active keyset of the mint with the id it signs under
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Keyset {
+    pub id: String,
+    #[serde(default)]
+    unit: String,
+    pub keys: Keys,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysResponse {
+    keysets: Vec<Keyset>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum QuoteState {
+    Unpaid,
+    Paid,
+    Issued,
+}
+
+/// Mint quote as returned by the mint
+#[derive(Debug, Deserialize)]
+pub struct QuoteResponse {
+    quote: String,
+    request: String,
+    /// Only sent by mints from before quote states
+    #[serde(default)]
+    paid: bool,
+    state: Option<QuoteState>,
+    expiry: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintQuote {
+    pub id: String,
+    pub invoice: Invoice,
+    pub state: QuoteState,
+    /// Unix timestamp after which the quote can no longer be paid
+    pub expiry: Option<u64>,
+}
+
+impl QuoteResponse {
+    pub fn quote(&self) -> &str {
+        &self.quote
+    }
+
+    pub fn state(&self) -> QuoteState {
+        self.state.unwrap_or(match self.paid {
+            true => QuoteState::Paid,
+            false => QuoteState::Unpaid,
+        })
+    }
+}
+
+impl TryFrom<QuoteResponse> for MintQuote {
+    type Error = anyhow::Error;
+
+    fn try_from(response: QuoteResponse) -> Result<Self> {
+        let state = response.state();
+
+        let invoice = Invoice::from_str(&response.request)?;
+
+        // Quotes without an expiry can't outlive their invoice
+        let expiry = response.expiry.or(Some(invoice_expiry(&invoice)));
+
+        Ok(Self {
+            id: response.quote,
+            invoice,
+            state,
+            expiry,
+        })
+    }
+}
+
+/// State of a proof (NUT-07)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ProofState {
+    Unspent,
+    Pending,
+    Spent,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckStateRequest {
+    #[serde(rename = "Ys")]
+    ys: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckStateEntry {
+    #[serde(rename = "Y")]
+    y: String,
+    state: ProofState,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckStateResponse {
+    states: Vec<CheckStateEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct SwapRequest<'a> {
+    inputs: &'a Proofs,
+    outputs: Vec<BlindedOutput>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MeltState {
+    Unpaid,
+    Pending,
+    Paid,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeltQuoteResponse {
+    quote: String,
+    amount: Amount,
+    fee_reserve: Amount,
+    /// Only sent by mints from before quote states
+    #[serde(default)]
+    paid: bool,
+    state: Option<MeltState>,
+    payment_preimage: Option<String>,
+    #[serde(default)]
+    change: Vec<BlindedSignature>,
+}
+
+impl MeltQuoteResponse {
+    fn state(&self) -> MeltState {
+        self.state.unwrap_or(match self.paid {
+            true => MeltState::Paid,
+            false => MeltState::Unpaid,
+        })
+    }
+}
+
+/// Quote to pay a bolt11 invoice with proofs (NUT-05)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeltQuote {
+    pub id: String,
+    pub amount: Amount,
+    pub fee_reserve: Amount,
+}
+
+/// Outcome of paying a melt quote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeltResult {
+    pub state: MeltState,
+    pub preimage: Option<String>,
+    /// Signatures on the blank outputs for the unused fee reserve (NUT-08)
+    pub change: Vec<BlindedSignature>,
+}
+
+#[derive(Debug, Serialize)]
+struct MeltQuoteRequest {
+    request: String,
+    unit: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MeltRequest<'a> {
+    quote: String,
+    inputs: &'a Proofs,
+    outputs: Vec<BlindedOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreRequest {
+    outputs: Vec<BlindedOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoredOutput {
+    #[serde(rename = "B_")]
+    b: PublicKey,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreResponse {
+    outputs: Vec<RestoredOutput>,
+    #[serde(alias = "promises")]
+    signatures: Vec<BlindedSignature>,
+}
+
+#[derive(Debug, Serialize)]
+struct QuoteRequest {
+    amount: Amount,
+    unit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MintRequest {
+    quote: String,
+    outputs: Vec<BlindedOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MintResponse {
+    signatures: Vec<BlindedSignature>,
+}
+
+/// Error a mint answers failed requests with (NUT-00)
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    detail: Option<String>,
+    code: Option<u64>,
+}
+
+/// Parses a mint response body, turning an error status into the mint's error
+fn parse_body<T: DeserializeOwned>(status: u16, body: &str) -> Result<T> {
+    if !(200..300).contains(&status) {
+        match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(ErrorResponse {
+                detail: Some(detail),
+                code,
+            }) => match code {
+                Some(code) => bail!("Mint error {}: {}", code, detail),
+                None => bail!("Mint error: {}", detail),
+            },
+            _ => bail!("Mint responded with status {}", status),
+        }
+    }
+
+    Ok(serde_json::from_str(body)?)
+}
+
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let status = response.status();
+    let body = response.text().await?;
+
+    parse_body(status, &body)
+}
+
+/// Unix timestamp at which the invoice expires
+pub fn invoice_expiry(invoice: &Invoice) -> u64 {
+    (invoice.duration_since_epoch() + invoice.expiry_time()).as_secs()
+}
+
+pub fn endpoint(mint_url: &Url, path: &str) -> String {
+    format!("{}/{}", mint_url.as_str().trim_end_matches('/'), path)
+}
+
+/// Mint info (NUT-06)
+pub async fn info(mint_url: &Url) -> Result<Value> {
+    json(Request::get(&endpoint(mint_url, "v1/info")).send().await?).await
+}
+
+/// Whether the mint accepts a description on bolt11 mint quotes
+pub async fn description_supported(mint_url: &Url) -> bool {
+    let info = match info(mint_url).await {
+        Ok(info) => info,
+        Err(_) => return false,
+    };
+
+    info["nuts"]["4"]["methods"]
+        .as_array()
+        .map(|methods| {
+            methods.iter().any(|m| {
+                m["method"] == "bolt11"
+                    && (m["description"] == true || m["options"]["description"] == true)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Active sat keyset of the mint
+pub async fn get_keyset(mint_url: &Url) -> Result<Keyset> {
+    let response: KeysResponse =
+        json(Request::get(&endpoint(mint_url, "v1/keys")).send().await?).await?;
+
+    let keyset = response
+        .keysets
+        .into_iter()
+        .find(|k| k.unit == "sat")
+        .ok_or(anyhow!("Mint has no sat keyset"))?;
+
+    Ok(keyset)
+}
+
+/// Requests a bolt11 invoice to mint `amount`
+pub async fn mint_quote(
+    mint_url: &Url,
+    amount: Amount,
+    description: Option<String>,
+) -> Result<MintQuote> {
+    let response: QuoteResponse = json(
+        Request::post(&endpoint(mint_url, "v1/mint/quote/bolt11"))
+            .json(&QuoteRequest {
+                amount,
+                unit: "sat".to_string(),
+                description,
+            })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    response.try_into()
+}
+
+pub async fn mint_quote_state(mint_url: &Url, quote_id: &str) -> Result<MintQuote> {
+    let response: QuoteResponse = json(
+        Request::get(&endpoint(
+            mint_url,
+            &format!("v1/mint/quote/bolt11/{}", quote_id),
+        ))
+        .send()
+        .await?,
+    )
+    .await?;
+
+    response.try_into()
+}
+
+/// Mints the outputs of a paid quote
+pub async fn mint(
+    mint_url: &Url,
+    quote_id: &str,
+    outputs: Vec<BlindedOutput>,
+) -> Result<Vec<BlindedSignature>> {
+    let response: MintResponse = json(
+        Request::post(&endpoint(mint_url, "v1/mint/bolt11"))
+            .json(&MintRequest {
+                quote: quote_id.to_string(),
+                outputs,
+            })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    Ok(response.signatures)
+}
+
+/// Swaps proofs for signatures on new outputs of the same amount (NUT-03)
+pub async fn swap(
+    mint_url: &Url,
+    inputs: &Proofs,
+    outputs: Vec<BlindedOutput>,
+) -> Result<Vec<BlindedSignature>> {
+    let response: MintResponse = json(
+        Request::post(&endpoint(mint_url, "v1/swap"))
+            .json(&SwapRequest { inputs, outputs })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    Ok(response.signatures)
+}
+
+/// States of the proofs with the hex `Y`s, in the same order (NUT-07)
+pub async fn check_state(mint_url: &Url, ys: Vec<String>) -> Result<Vec<ProofState>> {
+    let response: CheckStateResponse = json(
+        Request::post(&endpoint(mint_url, "v1/checkstate"))
+            .json(&CheckStateRequest { ys: ys.clone() })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    ys.iter()
+        .map(|y| {
+            response
+                .states
+                .iter()
+                .find(|s| &s.y == y)
+                .map(|s| s.state)
+                .ok_or(anyhow!("Mint did not return the state of {}", y))
+        })
+        .collect()
+}
+
+/// Asks the mint what paying `invoice` costs
+pub async fn melt_quote(mint_url: &Url, invoice: &Invoice) -> Result<MeltQuote> {
+    let response: MeltQuoteResponse = json(
+        Request::post(&endpoint(mint_url, "v1/melt/quote/bolt11"))
+            .json(&MeltQuoteRequest {
+                request: invoice.to_string(),
+                unit: "sat".to_string(),
+            })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    Ok(MeltQuote {
+        id: response.quote,
+        amount: response.amount,
+        fee_reserve: response.fee_reserve,
+    })
+}
+
+/// Pays a melt quote with `inputs`, with blank `outputs` for the change
+pub async fn melt(
+    mint_url: &Url,
+    quote_id: &str,
+    inputs: &Proofs,
+    outputs: Vec<BlindedOutput>,
+) -> Result<MeltResult> {
+    let response: MeltQuoteResponse = json(
+        Request::post(&endpoint(mint_url, "v1/melt/bolt11"))
+            .json(&MeltRequest {
+                quote: quote_id.to_string(),
+                inputs,
+                outputs,
+            })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    Ok(MeltResult {
+        state: response.state(),
+        preimage: response.payment_preimage,
+        change: response.change,
+    })
+}
+
+/// Signatures the mint already made on any of `outputs` (NUT-09)
+///
+/// Returns the outputs that were signed alongside their signatures.
+pub async fn restore(
+    mint_url: &Url,
+    outputs: Vec<BlindedOutput>,
+) -> Result<(Vec<PublicKey>, Vec<BlindedSignature>)> {
+    let response: RestoreResponse = json(
+        Request::post(&endpoint(mint_url, "v1/restore"))
+            .json(&RestoreRequest { outputs })?
+            .send()
+            .await?,
+    )
+    .await?;
+
+    Ok((
+        response.outputs.into_iter().map(|o| o.b).collect(),
+        response.signatures,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_detail_returned() {
+        let err = parse_body::<Value>(400, r#"{"detail": "Token already spent.", "code": 11001}"#)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Mint error 11001: Token already spent.");
+
+        let err = parse_body::<Value>(400, r#"{"detail": "Quote not paid"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "Mint error: Quote not paid");
+
+        let err = parse_body::<Value>(502, "Bad Gateway").unwrap_err();
+        assert_eq!(err.to_string(), "Mint responded with status 502");
+
+        assert!(parse_body::<Value>(200, r#"{"paid": true}"#).is_ok());
+    }
+}
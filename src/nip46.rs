@@ -0,0 +1,267 @@
+//! NIP-46 remote signing for the dashboard
+//!
+//! The merchant pastes the `bunker://` URI of their signer app. Requests are
+//! NIP-04 encrypted kind 24133 events between a throwaway key and the remote
+//! signer, so the merchant's secret key never reaches the terminal.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use futures::future::{select, Either};
+use gloo::timers::future::sleep;
+use nostr_sdk::prelude::{
+    nip04, Client, EventBuilder, Filter, Keys, Kind, RelayPoolNotification, Tag, XOnlyPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Nostr Connect requests and responses
+const NOSTR_CONNECT_KIND: u64 = 24133;
+
+/// How long the merchant has to approve a request in their signer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Connection details shown by the signer app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BunkerUri {
+    /// Key the signer answers with, not necessarily the merchant's
+    pub signer: XOnlyPublicKey,
+    pub relays: Vec<Url>,
+    pub secret: Option<String>,
+}
+
+impl FromStr for BunkerUri {
+    type Err = anyhow::Error;
+
+    fn from_str(uri: &str) -> Result<Self> {
+        let url = Url::parse(uri.trim())?;
+        if url.scheme() != "bunker" {
+            bail!("Not a bunker:// URI");
+        }
+
+        let signer = XOnlyPublicKey::from_str(url.host_str().unwrap_or_default())?;
+        let mut relays = vec![];
+        let mut secret = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "relay" => relays.push(Url::parse(&value)?),
+                "secret" => secret = Some(value.to_string()),
+                _ => (),
+            }
+        }
+
+        if relays.is_empty() {
+            bail!("Bunker URI has no relay");
+        }
+
+        Ok(Self {
+            signer,
+            relays,
+            secret,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    id: String,
+    method: &'a str,
+    params: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: String,
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Result of the response to request `id`, `None` for any other message
+fn parse_response(message: &str, id: &str) -> Option<Result<String>> {
+    let response: Response = serde_json::from_str(message).ok()?;
+    if response.id != id {
+        return None;
+    }
+
+    Some(match (response.result, response.error) {
+        (Some(result), Some(url)) if result == "auth_url" => {
+            Err(anyhow!("Approve the request at {}", url))
+        }
+        (_, Some(error)) if !error.is_empty() => Err(anyhow!("Signer error: {}", error)),
+        (Some(result), _) => Ok(result),
+        (None, _) => Err(anyhow!("Empty response from signer")),
+    })
+}
+
+/// Connection to a remote signer holding the merchant key
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: Client,
+    keys: Keys,
+    signer: XOnlyPublicKey,
+}
+
+impl RemoteSigner {
+    /// Connects to the signer of `uri`, returning it with the merchant key
+    pub async fn connect(uri: &BunkerUri) -> Result<(Self, XOnlyPublicKey)> {
+        let keys = Keys::generate();
+        let client = Client::new(&keys);
+        client
+            .add_relays(uri.relays.iter().map(|r| r.to_string()).collect())
+            .await?;
+        client.connect().await;
+
+        let filter = Filter::new()
+            .pubkey(keys.public_key())
+            .kind(Kind::from(NOSTR_CONNECT_KIND));
+        client.subscribe(vec![filter]).await;
+
+        let remote = Self {
+            client,
+            keys,
+            signer: uri.signer,
+        };
+
+        let mut params = vec![uri.signer.to_string()];
+        params.extend(uri.secret.clone());
+        remote.request("connect", params).await?;
+
+        let merchant = remote.request("get_public_key", vec![]).await?;
+
+        Ok((remote, XOnlyPublicKey::from_str(&merchant)?))
+    }
+
+    /// Decrypts a NIP-04 message sent to the merchant by `sender`
+    pub async fn decrypt(&self, sender: XOnlyPublicKey, content: String) -> Result<String> {
+        self.request("nip04_decrypt", vec![sender.to_string(), content])
+            .await
+    }
+
+    pub async fn shutdown(self) -> Result<()> {
+        Ok(self.client.shutdown().await?)
+    }
+
+    async fn request(&self, method: &str, params: Vec<String>) -> Result<String> {
+        let mut id = [0u8; 16];
+        getrandom::getrandom(&mut id)?;
+        let id = hex::encode(id);
+
+        let secret_key = self.keys.secret_key()?;
+        let request = Request {
+            id: id.clone(),
+            method,
+            params,
+        };
+        let content = nip04::encrypt(&secret_key, &self.signer, serde_json::to_string(&request)?)?;
+        let event = EventBuilder::new(
+            Kind::from(NOSTR_CONNECT_KIND),
+            &content,
+            &[Tag::PubKey(self.signer, None)],
+        )
+        .to_event(&self.keys)?;
+
+        // Listening before sending so a quick answer isn't missed
+        let mut notifications = self.client.notifications();
+        self.client.send_event(event).await?;
+
+        let response = async {
+            while let Ok(notification) = notifications.recv().await {
+                let event = match notification {
+                    RelayPoolNotification::Event(_, event) => event,
+                    _ => continue,
+                };
+                if event.pubkey != self.signer
+                    || event.kind != Kind::from(NOSTR_CONNECT_KIND)
+                    || event.verify().is_err()
+                {
+                    continue;
+                }
+
+                let message = match nip04::decrypt(&secret_key, &self.signer, &event.content) {
+                    Ok(message) => message,
+                    Err(_) => continue,
+                };
+                if let Some(result) = parse_response(&message, &id) {
+                    return result;
+                }
+            }
+
+            bail!("Signer connection closed")
+        };
+        futures::pin_mut!(response);
+
+        let timeout = sleep(REQUEST_TIMEOUT);
+        futures::pin_mut!(timeout);
+
+        match select(response, timeout).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => bail!("Signer did not answer {}", method),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    #[test]
+    fn bunker_uri() {
+        let uri: BunkerUri = format!(
+            "bunker://{}?relay=wss%3A%2F%2Frelay.example&relay=wss://other.example&secret=abc",
+            KEY
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(uri.signer.to_string(), KEY);
+        assert_eq!(
+            uri.relays,
+            vec![
+                Url::parse("wss://relay.example").unwrap(),
+                Url::parse("wss://other.example").unwrap()
+            ]
+        );
+        assert_eq!(uri.secret.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn bunker_uri_needs_key_and_relay() {
+        assert!(format!("bunker://{}", KEY).parse::<BunkerUri>().is_err());
+        assert!("bunker://nokey?relay=wss://relay.example"
+            .parse::<BunkerUri>()
+            .is_err());
+        assert!(format!("nostrconnect://{}?relay=wss://relay.example", KEY)
+            .parse::<BunkerUri>()
+            .is_err());
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(
+            parse_response(r#"{"id":"1","result":"ack"}"#, "1")
+                .unwrap()
+                .unwrap(),
+            "ack"
+        );
+        assert!(parse_response(r#"{"id":"2","result":"ack"}"#, "1").is_none());
+        assert!(parse_response("not json", "1").is_none());
+
+        let error = parse_response(r#"{"id":"1","error":"denied"}"#, "1").unwrap();
+        assert!(error.unwrap_err().to_string().contains("denied"));
+
+        let auth = parse_response(
+            r#"{"id":"1","result":"auth_url","error":"https://signer.example/approve"}"#,
+            "1",
+        )
+        .unwrap();
+        assert!(auth
+            .unwrap_err()
+            .to_string()
+            .contains("https://signer.example/approve"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Cashu payment requests (NUT-18)
+//!
+//! Requests carry a nostr transport to the terminal's own key so ecash wallets
+//! can pay a sale without Lightning.
+
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose, Engine as _};
+use cashu_crab::{nuts::nut00::Proofs, Amount};
+use nostr_sdk::nips::nip19::Profile;
+use nostr_sdk::prelude::{
+    nip04, Client, Filter, Kind, RelayPoolNotification, Timestamp, ToBech32, XOnlyPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use yew::Callback;
+
+use crate::melt;
+
+/// Prefix of a version A encoded payment request
+const PREFIX: &str = "creqA";
+
+/// How the payer sends the token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transport {
+    #[serde(rename = "t")]
+    pub kind: String,
+    #[serde(rename = "a")]
+    pub target: String,
+    #[serde(rename = "g", default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Vec<String>>,
+}
+
+impl Transport {
+    /// Nostr transport to `pubkey`
+    ///
+    /// Payments are received as NIP-04 DMs as nostr-sdk has no NIP-17 support.
+    pub fn nostr(pubkey: XOnlyPublicKey, relays: Vec<String>) -> Result<Self> {
+        Ok(Self {
+            kind: "nostr".to_string(),
+            target: Profile::new(pubkey, relays).to_bech32()?,
+            tags: vec![vec!["n".to_string(), "04".to_string()]],
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    #[serde(rename = "i", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "a", skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(rename = "u", skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(rename = "s", skip_serializing_if = "Option::is_none")]
+    pub single_use: Option<bool>,
+    #[serde(rename = "m", skip_serializing_if = "Option::is_none")]
+    pub mints: Option<Vec<String>>,
+    #[serde(rename = "d", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "t", default, skip_serializing_if = "Vec::is_empty")]
+    pub transports: Vec<Transport>,
+}
+
+impl PaymentRequest {
+    /// Single use request for `amount` sats of ecash from `mint_url`
+    pub fn new(id: String, amount: Amount, mint_url: &Url, transports: Vec<Transport>) -> Self {
+        Self {
+            id: Some(id),
+            amount: Some(amount.to_sat()),
+            unit: Some("sat".to_string()),
+            single_use: Some(true),
+            mints: Some(vec![mint_url.as_str().trim_end_matches('/').to_string()]),
+            description: None,
+            transports,
+        }
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        let mut cbor = vec![];
+        ciborium::into_writer(self, &mut cbor)?;
+
+        Ok(format!(
+            "{}{}",
+            PREFIX,
+            general_purpose::URL_SAFE.encode(cbor)
+        ))
+    }
+}
+
+/// Token sent by the payer in answer to a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentRequestPayload {
+    pub id: Option<String>,
+    pub memo: Option<String>,
+    pub mint: String,
+    pub unit: String,
+    pub proofs: Proofs,
+}
+
+impl PaymentRequestPayload {
+    /// Checks the payload covers `amount` sats from `mint_url`
+    pub fn validate(&self, amount: Amount, mint_url: &Url) -> Result<()> {
+        if self.mint.trim_end_matches('/') != mint_url.as_str().trim_end_matches('/') {
+            bail!("Payment from unexpected mint {}", self.mint);
+        }
+
+        if self.unit != "sat" {
+            bail!("Payment in unexpected unit {}", self.unit);
+        }
+
+        let paid = melt::proofs_amount(&self.proofs).to_sat();
+        if paid < amount.to_sat() {
+            bail!("Paid {} sats of {} sats", paid, amount.to_sat());
+        }
+
+        Ok(())
+    }
+}
+
+/// Emits payloads DMed to the terminal key
+pub async fn listen(client: Client, payload_cb: Callback<PaymentRequestPayload>) -> Result<()> {
+    let keys = client.keys();
+    let secret_key = keys.secret_key()?;
+
+    let filter = Filter::new()
+        .pubkey(keys.public_key())
+        .kind(Kind::EncryptedDirectMessage)
+        .since(Timestamp::now());
+
+    let mut notifications = client.notifications();
+    client.subscribe(vec![filter]).await;
+
+    while let Ok(notification) = notifications.recv().await {
+        if let RelayPoolNotification::Event(_, event) = notification {
+            if event.kind != Kind::EncryptedDirectMessage {
+                continue;
+            }
+
+            // Anything else sent to the terminal is not a payment
+            if let Some(payload) = nip04::decrypt(&secret_key, &event.pubkey, &event.content)
+                .ok()
+                .and_then(|message| serde_json::from_str(&message).ok())
+            {
+                payload_cb.emit(payload);
+            }
+        }
+    }
+
+    Ok(())
+}
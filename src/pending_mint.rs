@@ -0,0 +1,160 @@
+//! Paid quotes whose proofs have not been minted yet
+//!
+//! A quote is recorded once it is paid, with the counter its deterministic
+//! outputs start at, so a failed or interrupted mint can be retried after a
+//! reload. If the mint signed the outputs but the response was lost, the
+//! signatures are recovered through restore.
+//!
+//! Minted quotes are kept so a restore can tell which outputs were handed out.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use cashu_crab::{
+    dhke::construct_proofs,
+    nuts::nut00::{wallet::BlindedMessages, BlindedSignature, Proofs},
+    nuts::nut01::PublicKey,
+    Amount,
+};
+use gloo::storage::{LocalStorage, Storage};
+use gloo::timers::future::sleep;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::mint_api::{self, BlindedOutput, QuoteState};
+use crate::seed::Seed;
+use crate::storage::{MINTED_KEY, PENDING_MINTS_KEY};
+use crate::wallet::Wallet;
+
+/// Attempts before the mint is left for the next start
+const MINT_ATTEMPTS: u32 = 5;
+
+const RETRY_START: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingMint {
+    /// Payment hash of the sale
+    pub hash: String,
+    pub quote_id: String,
+    pub amount: Amount,
+    pub keyset_id: String,
+    /// Fingerprint of the seed the outputs are derived from
+    #[serde(default)]
+    pub seed: String,
+    /// First counter of the outputs reserved for the quote
+    pub counter: u32,
+    /// Paid by the merchant to fund a refund of the sale
+    #[serde(default)]
+    pub refund: bool,
+}
+
+impl PendingMint {
+    /// Counters of the outputs reserved for the quote
+    pub fn counters(&self) -> Range<u32> {
+        self.counter..self.counter + self.amount.split().len() as u32
+    }
+}
+
+pub fn load() -> Vec<PendingMint> {
+    LocalStorage::get(PENDING_MINTS_KEY).unwrap_or_default()
+}
+
+pub fn add(pending: PendingMint) {
+    let mut pending_mints = load();
+    pending_mints.retain(|p| p.quote_id != pending.quote_id);
+    pending_mints.push(pending);
+    LocalStorage::set(PENDING_MINTS_KEY, pending_mints).ok();
+}
+
+/// Quotes that were minted, oldest first
+pub fn minted() -> Vec<PendingMint> {
+    LocalStorage::get(MINTED_KEY).unwrap_or_default()
+}
+
+/// Moves a quote that has been minted out of the pending ones
+pub fn settle(quote_id: &str) {
+    let (settled, pending_mints): (Vec<_>, Vec<_>) =
+        load().into_iter().partition(|p| p.quote_id == quote_id);
+    LocalStorage::set(PENDING_MINTS_KEY, pending_mints).ok();
+
+    let mut minted = minted();
+    minted.extend(settled);
+    LocalStorage::set(MINTED_KEY, minted).ok();
+}
+
+/// Unblinds the signatures of the outputs in `signed` order
+fn unblind(
+    wallet: &Wallet,
+    premint: &BlindedMessages,
+    signed: &[PublicKey],
+    signatures: Vec<BlindedSignature>,
+) -> Result<Proofs> {
+    let mut rs = vec![];
+    let mut secrets = vec![];
+    for b in signed {
+        let i = premint
+            .blinded_messages
+            .iter()
+            .position(|m| &m.b == b)
+            .ok_or(anyhow!("Mint restored an unknown output"))?;
+        rs.push(premint.rs[i].clone());
+        secrets.push(premint.secrets[i].clone());
+    }
+
+    Ok(construct_proofs(
+        signatures,
+        rs,
+        secrets,
+        &wallet.keyset.keys,
+    )?)
+}
+
+/// Mints the proofs of a paid quote, retrying while the mint is unreachable
+pub async fn complete(wallet: &Wallet, seed: &Seed, pending: &PendingMint) -> Result<Proofs> {
+    if !pending.seed.is_empty() && pending.seed != seed.fingerprint() {
+        bail!("Quote {} was minted from another seed", pending.quote_id);
+    }
+    if wallet.keyset.id != pending.keyset_id {
+        bail!(
+            "Keyset {} of the quote is no longer active",
+            pending.keyset_id
+        );
+    }
+
+    let premint = seed.premint_at(&pending.keyset_id, pending.counter, pending.amount)?;
+    let outputs = BlindedOutput::from_premint(&premint, &pending.keyset_id);
+
+    let mut delay = RETRY_START;
+    let mut attempt = 1;
+    loop {
+        let err = match mint_api::mint(&wallet.mint_url, &pending.quote_id, outputs.clone()).await {
+            Ok(signatures) => {
+                return Ok(construct_proofs(
+                    signatures,
+                    premint.rs,
+                    premint.secrets,
+                    &wallet.keyset.keys,
+                )?)
+            }
+            Err(err) => err,
+        };
+
+        // Signed by an earlier attempt whose response never arrived
+        if let Ok(quote) = mint_api::mint_quote_state(&wallet.mint_url, &pending.quote_id).await {
+            if quote.state == QuoteState::Issued {
+                let (signed, signatures) = mint_api::restore(&wallet.mint_url, outputs).await?;
+                return unblind(wallet, &premint, &signed, signatures);
+            }
+        }
+
+        if attempt == MINT_ATTEMPTS {
+            return Err(err);
+        }
+        warn!("Could not mint paid quote, retrying {:?}", err);
+
+        sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,563 @@
+//! Waiting for mint quotes to be paid
+//!
+//! Uses NUT-17 websocket subscriptions when the mint supports them and falls
+//! back to polling the quote with exponential backoff.
+
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use futures::channel::oneshot;
+use futures::future::{select, Either, FutureExt, LocalBoxFuture};
+use futures::{SinkExt, Stream, StreamExt};
+use gloo::timers::future::sleep;
+use gloo_net::websocket::{futures::WebSocket, Message, WebSocketError};
+use log::warn;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::mint_api::{self, MintQuote, QuoteResponse, QuoteState};
+use crate::sale;
+
+/// First delay between polls
+const POLL_START: Duration = Duration::from_secs(1);
+
+/// Longest delay between polls
+const POLL_MAX: Duration = Duration::from_secs(10);
+
+/// Subscription kind for mint quote updates
+const MINT_QUOTE_KIND: &str = "bolt11_mint_quote";
+
+/// What waiting on a quote needs from the mint, kept apart so the fallback
+/// from the websocket to polling can be tested without one
+trait QuoteSource {
+    /// Whether quote updates can be subscribed to
+    fn ws_supported(&self) -> LocalBoxFuture<'_, bool>;
+
+    /// Waits on a subscription until the quote leaves the unpaid state
+    fn wait_ws<'a>(&'a self, quote_id: &'a str) -> LocalBoxFuture<'a, Result<QuoteState>>;
+
+    fn quote_state<'a>(&'a self, quote_id: &'a str) -> LocalBoxFuture<'a, Result<QuoteState>>;
+
+    fn sleep(&self, delay: Duration) -> LocalBoxFuture<'static, ()>;
+
+    fn now(&self) -> u64;
+}
+
+/// The mint at the given url
+struct Mint<'a>(&'a Url);
+
+impl QuoteSource for Mint<'_> {
+    fn ws_supported(&self) -> LocalBoxFuture<'_, bool> {
+        ws_supported(self.0).boxed_local()
+    }
+
+    fn wait_ws<'a>(&'a self, quote_id: &'a str) -> LocalBoxFuture<'a, Result<QuoteState>> {
+        wait_ws(self.0, quote_id).boxed_local()
+    }
+
+    fn quote_state<'a>(&'a self, quote_id: &'a str) -> LocalBoxFuture<'a, Result<QuoteState>> {
+        async move { Ok(mint_api::mint_quote_state(self.0, quote_id).await?.state) }.boxed_local()
+    }
+
+    fn sleep(&self, delay: Duration) -> LocalBoxFuture<'static, ()> {
+        sleep(delay).boxed_local()
+    }
+
+    fn now(&self) -> u64 {
+        sale::unix_time()
+    }
+}
+
+/// Whether the mint advertises NUT-17 subscriptions to mint quotes
+pub async fn ws_supported(mint_url: &Url) -> bool {
+    match mint_api::info(mint_url).await {
+        Ok(info) => supports_quote_subscriptions(&info),
+        Err(_) => false,
+    }
+}
+
+/// Whether the mint info lists mint quotes among the NUT-17 commands
+fn supports_quote_subscriptions(info: &Value) -> bool {
+    info["nuts"]["17"]["supported"]
+        .as_array()
+        .map(|supported| {
+            supported.iter().any(|s| {
+                s["commands"]
+                    .as_array()
+                    .map(|c| c.iter().any(|c| c == MINT_QUOTE_KIND))
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Websocket url of the mint
+fn ws_url(mint_url: &Url) -> Result<Url> {
+    let mut url = Url::parse(&mint_api::endpoint(mint_url, "v1/ws"))?;
+    let scheme = match url.scheme() {
+        "https" => "wss",
+        _ => "ws",
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow!("Invalid websocket url"))?;
+
+    Ok(url)
+}
+
+/// Returns once the quote has been paid, or `false` if `cancel` fired first
+///
+/// Fails when the quote expires unpaid or has already been issued.
+pub async fn wait_until_paid(
+    mint_url: &Url,
+    quote: &MintQuote,
+    cancel: oneshot::Receiver<()>,
+) -> Result<bool> {
+    let mint = Mint(mint_url);
+    let paid = wait_paid(&mint, quote);
+    futures::pin_mut!(paid);
+
+    match select(paid, cancel).await {
+        Either::Left((result, _)) => result.map(|_| true),
+        Either::Right((Ok(()), _)) => Ok(false),
+        // Nobody can cancel anymore
+        Either::Right((Err(_), paid)) => paid.await.map(|_| true),
+    }
+}
+
+async fn wait_paid(source: &impl QuoteSource, quote: &MintQuote) -> Result<()> {
+    if source.ws_supported().await {
+        let expiry = expiry_timer(source, quote);
+        futures::pin_mut!(expiry);
+
+        let subscription = source.wait_ws(&quote.id);
+
+        match select(subscription, expiry).await {
+            Either::Left((Ok(state), _)) => return check_state(state, &quote.id),
+            Either::Left((Err(err), _)) => {
+                warn!("Quote subscription failed, polling instead {:?}", err)
+            }
+            Either::Right(_) => bail!("Quote {} expired", quote.id),
+        }
+    }
+
+    poll(
+        &quote.id,
+        quote.expiry,
+        || source.now(),
+        || source.quote_state(&quote.id),
+        |delay| source.sleep(delay),
+    )
+    .await
+}
+
+fn check_state(state: QuoteState, quote_id: &str) -> Result<()> {
+    match state {
+        QuoteState::Paid => Ok(()),
+        QuoteState::Issued => bail!("Quote {} already issued", quote_id),
+        QuoteState::Unpaid => bail!("Quote {} not paid", quote_id),
+    }
+}
+
+/// Resolves when the quote expires, never if it has no expiry
+async fn expiry_timer(source: &impl QuoteSource, quote: &MintQuote) {
+    match quote.expiry {
+        Some(expiry) => {
+            let remaining = expiry.saturating_sub(source.now());
+            source.sleep(Duration::from_secs(remaining)).await;
+        }
+        None => futures::future::pending().await,
+    }
+}
+
+/// Quote state carried by a NUT-17 message, `None` if it carries none
+///
+/// Responses to the subscribe request have no payload, and notifications for
+/// other quotes are ignored.
+fn notification_state(text: &str, quote_id: &str) -> Result<Option<QuoteState>> {
+    let message: Value = serde_json::from_str(text)?;
+
+    if let Some(error) = message.get("error") {
+        bail!("Subscription error {}", error);
+    }
+
+    let payload = &message["params"]["payload"];
+    if payload.is_null() {
+        return Ok(None);
+    }
+
+    let payload: QuoteResponse = serde_json::from_value(payload.clone())?;
+    if payload.quote() != quote_id {
+        return Ok(None);
+    }
+
+    Ok(Some(payload.state()))
+}
+
+/// Waits on the NUT-17 subscription until the quote leaves the unpaid state
+async fn wait_ws(mint_url: &Url, quote_id: &str) -> Result<QuoteState> {
+    let mut ws = WebSocket::open(ws_url(mint_url)?.as_str())?;
+
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "subscribe",
+        "params": {
+            "kind": MINT_QUOTE_KIND,
+            "subId": quote_id,
+            "filters": [quote_id],
+        }
+    });
+    ws.send(Message::Text(subscribe.to_string())).await?;
+
+    wait_notified(ws, quote_id).await
+}
+
+/// Reads subscription messages until one moves the quote out of the unpaid state
+async fn wait_notified(
+    messages: impl Stream<Item = Result<Message, WebSocketError>>,
+    quote_id: &str,
+) -> Result<QuoteState> {
+    futures::pin_mut!(messages);
+
+    while let Some(message) = messages.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Bytes(_) => continue,
+        };
+
+        match notification_state(&text, quote_id)? {
+            Some(QuoteState::Unpaid) | None => continue,
+            Some(state) => return Ok(state),
+        }
+    }
+
+    bail!("Mint closed the websocket")
+}
+
+/// Polls the quote state with `get_state`, backing off with `wait` while it
+/// stays unpaid
+async fn poll<S, SF, W, WF>(
+    quote_id: &str,
+    expiry: Option<u64>,
+    now: impl Fn() -> u64,
+    mut get_state: S,
+    mut wait: W,
+) -> Result<()>
+where
+    S: FnMut() -> SF,
+    SF: Future<Output = Result<QuoteState>>,
+    W: FnMut(Duration) -> WF,
+    WF: Future<Output = ()>,
+{
+    let mut delay = POLL_START;
+
+    loop {
+        match get_state().await {
+            Ok(QuoteState::Unpaid) => (),
+            Ok(state) => return check_state(state, quote_id),
+            Err(err) => warn!("Could not get quote state {:?}", err),
+        }
+
+        if expiry.is_some_and(|e| e < now()) {
+            bail!("Quote {} expired", quote_id);
+        }
+
+        wait(delay).await;
+        delay = (delay * 2).min(POLL_MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::str::FromStr;
+
+    use cashu_crab::Invoice;
+    use futures::executor::block_on;
+    use futures::stream;
+
+    use super::*;
+
+    const INVOICE: &str = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+
+    /// Mint answering the subscription and polls from canned results
+    struct MockMint {
+        ws: bool,
+        subscription: RefCell<Option<Result<QuoteState>>>,
+        states: RefCell<std::vec::IntoIter<Result<QuoteState>>>,
+        subscribed: Cell<bool>,
+        polls: Cell<usize>,
+    }
+
+    impl MockMint {
+        fn new(
+            ws: bool,
+            subscription: Option<Result<QuoteState>>,
+            states: Vec<Result<QuoteState>>,
+        ) -> Self {
+            Self {
+                ws,
+                subscription: RefCell::new(subscription),
+                states: RefCell::new(states.into_iter()),
+                subscribed: Cell::new(false),
+                polls: Cell::new(0),
+            }
+        }
+    }
+
+    impl QuoteSource for MockMint {
+        fn ws_supported(&self) -> LocalBoxFuture<'_, bool> {
+            async move { self.ws }.boxed_local()
+        }
+
+        fn wait_ws<'a>(&'a self, _quote_id: &'a str) -> LocalBoxFuture<'a, Result<QuoteState>> {
+            self.subscribed.set(true);
+            let result = self.subscription.borrow_mut().take().unwrap();
+            async move { result }.boxed_local()
+        }
+
+        fn quote_state<'a>(&'a self, _quote_id: &'a str) -> LocalBoxFuture<'a, Result<QuoteState>> {
+            self.polls.set(self.polls.get() + 1);
+            let result = self.states.borrow_mut().next().unwrap();
+            async move { result }.boxed_local()
+        }
+
+        fn sleep(&self, _delay: Duration) -> LocalBoxFuture<'static, ()> {
+            async {}.boxed_local()
+        }
+
+        fn now(&self) -> u64 {
+            1000
+        }
+    }
+
+    fn quote() -> MintQuote {
+        MintQuote {
+            id: "q1".to_string(),
+            invoice: Invoice::from_str(INVOICE).unwrap(),
+            state: QuoteState::Unpaid,
+            expiry: None,
+        }
+    }
+
+    #[test]
+    fn paid_over_websocket_without_polling() {
+        let mint = MockMint::new(true, Some(Ok(QuoteState::Paid)), vec![]);
+
+        assert!(block_on(wait_paid(&mint, &quote())).is_ok());
+        assert_eq!(mint.polls.get(), 0);
+    }
+
+    #[test]
+    fn issued_over_websocket_fails() {
+        let mint = MockMint::new(true, Some(Ok(QuoteState::Issued)), vec![]);
+
+        assert!(block_on(wait_paid(&mint, &quote())).is_err());
+        assert_eq!(mint.polls.get(), 0);
+    }
+
+    #[test]
+    fn failed_subscription_falls_back_to_polling() {
+        let mint = MockMint::new(
+            true,
+            Some(Err(anyhow!("Mint closed the websocket"))),
+            vec![Ok(QuoteState::Unpaid), Ok(QuoteState::Paid)],
+        );
+
+        assert!(block_on(wait_paid(&mint, &quote())).is_ok());
+        assert!(mint.subscribed.get());
+        assert_eq!(mint.polls.get(), 2);
+    }
+
+    #[test]
+    fn polls_without_websocket_support() {
+        let mint = MockMint::new(false, None, vec![Ok(QuoteState::Paid)]);
+
+        assert!(block_on(wait_paid(&mint, &quote())).is_ok());
+        assert!(!mint.subscribed.get());
+        assert_eq!(mint.polls.get(), 1);
+    }
+
+    #[test]
+    fn quote_subscriptions_read_from_info() {
+        let info = json!({
+            "nuts": {
+                "17": {
+                    "supported": [{
+                        "method": "bolt11",
+                        "unit": "sat",
+                        "commands": ["bolt11_melt_quote", "proof_state", "bolt11_mint_quote"]
+                    }]
+                }
+            }
+        });
+        assert!(supports_quote_subscriptions(&info));
+
+        let info = json!({
+            "nuts": {
+                "17": {
+                    "supported": [{ "method": "bolt11", "unit": "sat", "commands": ["proof_state"] }]
+                }
+            }
+        });
+        assert!(!supports_quote_subscriptions(&info));
+        assert!(!supports_quote_subscriptions(&json!({ "nuts": {} })));
+    }
+
+    #[test]
+    fn waits_for_notification_of_quote() {
+        let messages = stream::iter(vec![
+            Ok(Message::Text(
+                r#"{"jsonrpc":"2.0","result":{"status":"OK","subId":"q1"},"id":0}"#.to_string(),
+            )),
+            Ok(Message::Bytes(vec![0])),
+            Ok(Message::Text(notification("q1", "UNPAID"))),
+            Ok(Message::Text(notification("q2", "PAID"))),
+            Ok(Message::Text(notification("q1", "PAID"))),
+        ]);
+
+        assert_eq!(
+            block_on(wait_notified(messages, "q1")).unwrap(),
+            QuoteState::Paid
+        );
+    }
+
+    #[test]
+    fn closed_websocket_is_an_error() {
+        let messages = stream::iter(vec![Ok(Message::Text(notification("q1", "UNPAID")))]);
+
+        assert!(block_on(wait_notified(messages, "q1")).is_err());
+    }
+
+    fn notification(quote_id: &str, state: &str) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "subscribe",
+            "params": {
+                "subId": quote_id,
+                "payload": {
+                    "quote": quote_id,
+                    "request": "lnbc1",
+                    "state": state,
+                    "expiry": 1700000000,
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn subscribe_response_has_no_state() {
+        let response = r#"{"jsonrpc":"2.0","result":{"status":"OK","subId":"q1"},"id":0}"#;
+
+        assert_eq!(notification_state(response, "q1").unwrap(), None);
+    }
+
+    #[test]
+    fn notification_carries_quote_state() {
+        assert_eq!(
+            notification_state(&notification("q1", "UNPAID"), "q1").unwrap(),
+            Some(QuoteState::Unpaid)
+        );
+        assert_eq!(
+            notification_state(&notification("q1", "PAID"), "q1").unwrap(),
+            Some(QuoteState::Paid)
+        );
+        assert_eq!(
+            notification_state(&notification("q1", "ISSUED"), "q1").unwrap(),
+            Some(QuoteState::Issued)
+        );
+    }
+
+    #[test]
+    fn notification_without_state_uses_paid() {
+        let legacy = json!({
+            "jsonrpc": "2.0",
+            "method": "subscribe",
+            "params": {
+                "subId": "q1",
+                "payload": { "quote": "q1", "request": "lnbc1", "paid": true }
+            }
+        })
+        .to_string();
+
+        assert_eq!(
+            notification_state(&legacy, "q1").unwrap(),
+            Some(QuoteState::Paid)
+        );
+    }
+
+    #[test]
+    fn notification_for_other_quote_is_ignored() {
+        assert_eq!(
+            notification_state(&notification("q2", "PAID"), "q1").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn subscription_error() {
+        let error =
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":0}"#;
+
+        assert!(notification_state(error, "q1").is_err());
+        assert!(notification_state("not json", "q1").is_err());
+    }
+
+    /// Polls through `states` and returns the result and the delays waited
+    fn run_poll(
+        states: Vec<Result<QuoteState>>,
+        expiry: Option<u64>,
+    ) -> (Result<()>, Vec<Duration>) {
+        let states = RefCell::new(states.into_iter());
+        let waits = RefCell::new(vec![]);
+
+        let result = block_on(poll(
+            "q1",
+            expiry,
+            || 1000,
+            || async { states.borrow_mut().next().unwrap() },
+            |delay| {
+                waits.borrow_mut().push(delay);
+                async {}
+            },
+        ));
+
+        (result, waits.into_inner())
+    }
+
+    #[test]
+    fn polling_backs_off_until_paid() {
+        let mut states: Vec<Result<QuoteState>> = vec![Err(anyhow!("offline"))];
+        states.extend((0..5).map(|_| Ok(QuoteState::Unpaid)));
+        states.push(Ok(QuoteState::Paid));
+
+        let (result, waits) = run_poll(states, None);
+
+        assert!(result.is_ok());
+        assert_eq!(
+            waits,
+            [1, 2, 4, 8, 10, 10]
+                .into_iter()
+                .map(Duration::from_secs)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn polling_fails_on_issued_quote() {
+        let (result, waits) = run_poll(vec![Ok(QuoteState::Issued)], None);
+
+        assert!(result.is_err());
+        assert!(waits.is_empty());
+    }
+
+    #[test]
+    fn polling_stops_at_expiry() {
+        let (result, waits) = run_poll(vec![Ok(QuoteState::Unpaid)], Some(999));
+
+        assert!(result.unwrap_err().to_string().contains("expired"));
+        assert!(waits.is_empty());
+    }
+}
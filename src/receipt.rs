@@ -0,0 +1,68 @@
+//! Customer receipts signed by the terminal key
+//!
+//! A receipt is a NIP-78 app data event so it can be checked against the
+//! terminal pubkey offline from the QR or fetched from the relays by its `d` tag.
+
+use anyhow::Result;
+use cashu_crab::Amount;
+use nostr_sdk::prelude::{Event, EventBuilder, Keys, Kind, Tag};
+use serde::{Deserialize, Serialize};
+
+use crate::sale::Sale;
+
+/// NIP-78 application specific data
+const APP_DATA_KIND: u16 = 30078;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptItem {
+    pub description: String,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Payment hash of the sale
+    pub sale: String,
+    pub time: u64,
+    pub shop: Option<String>,
+    pub terminal: Option<String>,
+    pub reference: Option<String>,
+    pub items: Vec<ReceiptItem>,
+    pub amount: Amount,
+    pub fiat_value: String,
+}
+
+impl Receipt {
+    pub fn new(sale: &Sale, shop: Option<String>, terminal: Option<String>) -> Self {
+        let description = match &sale.reference {
+            Some(reference) => format!("Order {}", reference),
+            None => "Sale".to_string(),
+        };
+
+        Self {
+            sale: sale.hash.clone(),
+            time: sale.created_at,
+            shop,
+            terminal,
+            reference: sale.reference.clone(),
+            items: vec![ReceiptItem {
+                description,
+                amount: sale.amount,
+            }],
+            amount: sale.amount,
+            fiat_value: sale.fiat_value.clone(),
+        }
+    }
+
+    /// Receipt as an event signed by `keys`
+    pub fn to_event(&self, keys: &Keys) -> Result<Event> {
+        let tags = [Tag::Identifier(format!("receipt:{}", self.sale))];
+
+        Ok(EventBuilder::new(
+            Kind::ParameterizedReplaceable(APP_DATA_KIND),
+            &serde_json::to_string(self)?,
+            &tags,
+        )
+        .to_event(keys)?)
+    }
+}
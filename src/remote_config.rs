@@ -0,0 +1,309 @@
+//! Configuration pushed to paired terminals by the merchant
+//!
+//! Updates are NIP-04 DMs to the terminal key holding a JSON [`RemoteConfig`].
+//! The app only applies updates signed by the paired merchant key, in the
+//! order they were sent, and applies every field of an update or none of them.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use log::warn;
+use nostr_sdk::prelude::{
+    nip04, Client, Event, Filter, FromPkStr, Keys, Kind, RelayPoolNotification, SecretKey,
+    Timestamp, XOnlyPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use yew::Callback;
+
+use crate::message::MessageSettings;
+use crate::split::{Share, SplitRecipient};
+
+/// How long the relays have to return updates sent while offline
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fields left out are kept as they are on the terminal
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    #[serde(default)]
+    pub mint_url: Option<Url>,
+    #[serde(default)]
+    pub relays: Option<HashSet<Url>>,
+    /// Receiver pubkey as hex or npub
+    #[serde(default)]
+    pub receiver: Option<String>,
+    #[serde(default)]
+    pub splits: Option<Vec<SplitRecipient>>,
+    #[serde(default)]
+    pub shop_name: Option<String>,
+    #[serde(default)]
+    pub auto_return: Option<u32>,
+    #[serde(default)]
+    pub message: Option<MessageSettings>,
+}
+
+/// Update received from the relays
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteUpdate {
+    pub author: XOnlyPublicKey,
+    pub created_at: u64,
+    pub config: RemoteConfig,
+}
+
+/// Updates merged into one, saved in a single write before any of it is applied
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedConfig {
+    /// Time the last of the updates was sent
+    pub created_at: u64,
+    pub config: RemoteConfig,
+}
+
+impl RemoteConfig {
+    /// Fields set in `other` replace those of `self`
+    fn merge(&mut self, other: RemoteConfig) {
+        let RemoteConfig {
+            mint_url,
+            relays,
+            receiver,
+            splits,
+            shop_name,
+            auto_return,
+            message,
+        } = other;
+
+        self.mint_url = mint_url.or(self.mint_url.take());
+        self.relays = relays.or(self.relays.take());
+        self.receiver = receiver.or(self.receiver.take());
+        self.splits = splits.or(self.splits.take());
+        self.shop_name = shop_name.or(self.shop_name.take());
+        self.auto_return = auto_return.or(self.auto_return.take());
+        self.message = message.or(self.message.take());
+    }
+
+    /// Checks the whole update before any of it is applied
+    pub fn validate(&self) -> Result<()> {
+        if self.relays.as_ref().is_some_and(|r| r.is_empty()) {
+            bail!("Relay list is empty");
+        }
+
+        if let Some(receiver) = &self.receiver {
+            Keys::from_pk_str(receiver)?;
+        }
+
+        if let Some(splits) = &self.splits {
+            let percent: u32 = splits
+                .iter()
+                .filter_map(|s| match s.share {
+                    Share::Percent(percent) => Some(percent as u32),
+                    Share::Fixed(_) => None,
+                })
+                .sum();
+            if percent > 100 {
+                bail!("Splits add up to more than 100%");
+            }
+        }
+
+        if let Some(message) = &self.message {
+            message.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Merges the updates sent after `applied_at` in the order they were sent
+///
+/// Invalid updates are skipped whole, `None` if there is nothing to apply.
+pub fn merge_updates(mut updates: Vec<RemoteUpdate>, applied_at: u64) -> Option<AppliedConfig> {
+    updates.sort_by_key(|u| u.created_at);
+
+    let mut applied: Option<AppliedConfig> = None;
+    for update in updates.into_iter().filter(|u| u.created_at > applied_at) {
+        if let Err(err) = update.config.validate() {
+            warn!("Ignoring remote config {:?}", err);
+            continue;
+        }
+
+        let applied = applied.get_or_insert_with(AppliedConfig::default);
+        applied.created_at = update.created_at;
+        applied.config.merge(update.config);
+    }
+
+    applied
+}
+
+fn parse_update(secret_key: &SecretKey, event: &Event) -> Option<RemoteUpdate> {
+    if event.kind != Kind::EncryptedDirectMessage || event.verify().is_err() {
+        return None;
+    }
+
+    // Payments and other messages don't parse as config
+    let config = nip04::decrypt(secret_key, &event.pubkey, &event.content)
+        .ok()
+        .and_then(|message| serde_json::from_str(&message).ok())?;
+
+    Some(RemoteUpdate {
+        author: event.pubkey,
+        created_at: event.created_at.as_u64(),
+        config,
+    })
+}
+
+/// Emits the updates sent to the terminal key since `since` oldest first,
+/// then every new one as it arrives
+pub async fn listen(
+    client: Client,
+    since: u64,
+    update_cb: Callback<Vec<RemoteUpdate>>,
+) -> Result<()> {
+    let keys = client.keys();
+    let secret_key = keys.secret_key()?;
+
+    let filter = Filter::new()
+        .pubkey(keys.public_key())
+        .kind(Kind::EncryptedDirectMessage)
+        .since(Timestamp::from(since));
+
+    // Subscribed first so nothing sent while fetching is missed, the
+    // notifications are only read once the earlier updates are out
+    let mut notifications = client.notifications();
+    client.subscribe(vec![filter.clone()]).await;
+
+    // Relays return the updates pushed while the terminal was offline in any order
+    let mut updates: Vec<RemoteUpdate> = client
+        .get_events_of(vec![filter], Some(FETCH_TIMEOUT))
+        .await?
+        .iter()
+        .filter_map(|event| parse_update(&secret_key, event))
+        .collect();
+    updates.sort_by_key(|u| u.created_at);
+    update_cb.emit(updates);
+
+    while let Ok(notification) = notifications.recv().await {
+        if let RelayPoolNotification::Event(_, event) = notification {
+            if let Some(update) = parse_update(&secret_key, &event) {
+                update_cb.emit(vec![update]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey() -> XOnlyPublicKey {
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+            .parse()
+            .unwrap()
+    }
+
+    fn update(created_at: u64, config: RemoteConfig) -> RemoteUpdate {
+        RemoteUpdate {
+            author: pubkey(),
+            created_at,
+            config,
+        }
+    }
+
+    fn shop(name: &str) -> RemoteConfig {
+        RemoteConfig {
+            shop_name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_fields() {
+        assert!(RemoteConfig::default().validate().is_ok());
+
+        let empty_relays = RemoteConfig {
+            relays: Some(HashSet::new()),
+            ..Default::default()
+        };
+        assert!(empty_relays.validate().is_err());
+
+        let bad_receiver = RemoteConfig {
+            receiver: Some("not a key".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_receiver.validate().is_err());
+
+        let no_token = RemoteConfig {
+            message: Some(MessageSettings {
+                template: "Thanks".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(no_token.validate().is_err());
+    }
+
+    #[test]
+    fn validate_split_percentages() {
+        let splits = |percents: &[u8]| RemoteConfig {
+            splits: Some(
+                percents
+                    .iter()
+                    .map(|percent| SplitRecipient {
+                        pubkey: pubkey(),
+                        share: Share::Percent(*percent),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
+        assert!(splits(&[60, 40]).validate().is_ok());
+        assert!(splits(&[60, 41]).validate().is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(serde_json::from_str::<RemoteConfig>(r#"{"shop_name":"Cafe"}"#).is_ok());
+        assert!(serde_json::from_str::<RemoteConfig>(r#"{"shop":"Cafe"}"#).is_err());
+    }
+
+    #[test]
+    fn updates_merged_in_order_sent() {
+        let auto_return = RemoteConfig {
+            auto_return: Some(30),
+            ..Default::default()
+        };
+        let updates = vec![
+            update(300, shop("Late")),
+            update(100, shop("Early")),
+            update(200, auto_return),
+        ];
+
+        let applied = merge_updates(updates, 0).unwrap();
+
+        assert_eq!(applied.created_at, 300);
+        assert_eq!(applied.config.shop_name.as_deref(), Some("Late"));
+        assert_eq!(applied.config.auto_return, Some(30));
+    }
+
+    #[test]
+    fn applied_and_invalid_updates_skipped() {
+        let invalid = RemoteConfig {
+            shop_name: Some("Invalid".to_string()),
+            relays: Some(HashSet::new()),
+            ..Default::default()
+        };
+        let updates = vec![
+            update(100, shop("Applied")),
+            update(200, shop("New")),
+            update(300, invalid),
+        ];
+
+        let applied = merge_updates(updates.clone(), 100).unwrap();
+        assert_eq!(applied.created_at, 200);
+        assert_eq!(applied.config, shop("New"));
+
+        assert_eq!(merge_updates(updates, 300), None);
+    }
+}
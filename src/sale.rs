@@ -0,0 +1,158 @@
+//! Sale records
+
+use cashu_crab::Amount;
+use nostr_sdk::prelude::XOnlyPublicKey;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+/// Longest order reference, keeps receipts and their QR codes small
+pub const MAX_REFERENCE_LEN: usize = 64;
+
+/// Part of a sale delivered to one receiver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaleLeg {
+    pub pubkey: XOnlyPublicKey,
+    pub amount: Amount,
+    pub delivered: bool,
+}
+
+/// Ecash given back to the customer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Refund {
+    pub amount: Amount,
+    pub created_at: u64,
+    /// Token shown to the customer, kept so it can be shown again
+    ///
+    /// Stored encrypted apart from the sale and left out of the sale record
+    /// once it is.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token: String,
+}
+
+impl Refund {
+    /// Key of the token in the encrypted store
+    pub fn token_key(&self, hash: &str) -> String {
+        format!("{}/{}", hash, self.created_at)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sale {
+    /// Payment hash of the invoice
+    pub hash: String,
+    pub amount: Amount,
+    pub fiat_value: String,
+    /// Order note or reference typed by the cashier
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// Unix timestamp the invoice was created
+    pub created_at: u64,
+    pub paid: bool,
+    pub legs: Vec<SaleLeg>,
+    /// Amount kept on the terminal to be melted
+    #[serde(default)]
+    pub retained: Amount,
+    /// Paid over the sale amount
+    #[serde(default)]
+    pub tip: Amount,
+    #[serde(default)]
+    pub refunds: Vec<Refund>,
+    /// Cashier signed in when the sale was made
+    #[serde(default)]
+    pub cashier: Option<String>,
+    /// Hex key of the terminal that made the sale, `None` for this terminal
+    #[serde(default)]
+    pub terminal: Option<String>,
+}
+
+impl Sale {
+    pub fn new(
+        hash: String,
+        amount: Amount,
+        fiat_value: String,
+        reference: Option<String>,
+        cashier: Option<String>,
+    ) -> Self {
+        Self {
+            hash,
+            amount,
+            fiat_value,
+            reference,
+            created_at: unix_time(),
+            paid: false,
+            legs: vec![],
+            retained: Amount::default(),
+            tip: Amount::default(),
+            refunds: vec![],
+            cashier,
+            terminal: None,
+        }
+    }
+
+    pub fn refunded(&self) -> Amount {
+        Amount::from_sat(self.refunds.iter().map(|r| r.amount.to_sat()).sum())
+    }
+
+    /// Paid amount not yet refunded
+    pub fn refundable(&self) -> Amount {
+        if !self.paid {
+            return Amount::default();
+        }
+
+        Amount::from_sat(
+            (self.amount.to_sat() + self.tip.to_sat()).saturating_sub(self.refunded().to_sat()),
+        )
+    }
+}
+
+/// Description shown to the customer for a sale
+pub fn description(shop_name: Option<&str>, reference: Option<&str>) -> Option<String> {
+    match (shop_name, reference) {
+        (Some(shop_name), Some(reference)) => Some(format!("{} – order #{}", shop_name, reference)),
+        (Some(shop_name), None) => Some(shop_name.to_string()),
+        (None, Some(reference)) => Some(format!("Order #{}", reference)),
+        (None, None) => None,
+    }
+}
+
+/// Current unix time in seconds
+pub fn unix_time() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Local date and time of a unix timestamp
+pub fn format_time(time: u64) -> String {
+    js_sys::Date::new(&(time as f64 * 1000.0).into())
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into()
+}
+
+/// ISO 8601 UTC date and time of a unix timestamp
+///
+/// Unlike [`format_time`] this does not depend on the browser so the output
+/// is the same everywhere.
+pub fn format_iso(time: u64) -> String {
+    let days = (time / 86_400) as i64;
+    let seconds = time % 86_400;
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
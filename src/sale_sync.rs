@@ -0,0 +1,187 @@
+//! Sales shared between the terminals of a shop
+//!
+//! Terminals of a shop share a sync key. Each publishes its sales as NIP-78
+//! events keyed by payment hash and signed by its own key, with the sale
+//! NIP-04 encrypted to the sync key so only terminals holding it can read them.
+//! Refund tokens stay on the terminal that made them.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use cashu_crab::Amount;
+use nostr_sdk::prelude::{nip04, Client, Event, EventBuilder, Filter, Keys, Kind, Tag};
+use serde::Serialize;
+
+use crate::sale::{Sale, SaleLeg};
+
+/// NIP-78 application specific data
+const APP_DATA_KIND: u16 = 30078;
+
+/// How long to wait for relays when pulling
+const PULL_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn identifier(hash: &str) -> String {
+    format!("sale:{}", hash)
+}
+
+/// Refund as published, without the token the customer may not have spent yet
+#[derive(Debug, Serialize)]
+struct SharedRefund {
+    amount: Amount,
+    created_at: u64,
+}
+
+/// Sale as published, read back as a [`Sale`] by the other terminals
+#[derive(Debug, Serialize)]
+struct SharedSale<'a> {
+    hash: &'a str,
+    amount: Amount,
+    fiat_value: &'a str,
+    reference: &'a Option<String>,
+    created_at: u64,
+    paid: bool,
+    legs: &'a [SaleLeg],
+    retained: Amount,
+    tip: Amount,
+    refunds: Vec<SharedRefund>,
+    cashier: &'a Option<String>,
+    terminal: &'a Option<String>,
+}
+
+impl<'a> From<&'a Sale> for SharedSale<'a> {
+    fn from(sale: &'a Sale) -> Self {
+        Self {
+            hash: &sale.hash,
+            amount: sale.amount,
+            fiat_value: &sale.fiat_value,
+            reference: &sale.reference,
+            created_at: sale.created_at,
+            paid: sale.paid,
+            legs: &sale.legs,
+            retained: sale.retained,
+            tip: sale.tip,
+            refunds: sale
+                .refunds
+                .iter()
+                .map(|r| SharedRefund {
+                    amount: r.amount,
+                    created_at: r.created_at,
+                })
+                .collect(),
+            cashier: &sale.cashier,
+            terminal: &sale.terminal,
+        }
+    }
+}
+
+/// Event content before it's encrypted
+fn content(sale: &Sale) -> Result<String> {
+    Ok(serde_json::to_string(&SharedSale::from(sale))?)
+}
+
+/// Sale as an event signed by the terminal and readable with `sync_keys`
+fn to_event(sale: &Sale, terminal_keys: &Keys, sync_keys: &Keys) -> Result<Event> {
+    let content = nip04::encrypt(
+        &sync_keys.secret_key()?,
+        &sync_keys.public_key(),
+        content(sale)?,
+    )?;
+    let tags = [
+        Tag::Identifier(identifier(&sale.hash)),
+        Tag::PubKey(sync_keys.public_key(), None),
+    ];
+
+    Ok(EventBuilder::new(
+        Kind::ParameterizedReplaceable(APP_DATA_KIND),
+        &content,
+        &tags,
+    )
+    .to_event(terminal_keys)?)
+}
+
+/// Publishes the latest state of a sale, replacing the earlier one
+pub async fn publish(client: &Client, sync_keys: &Keys, sale: &Sale) -> Result<()> {
+    let event = to_event(sale, &client.keys(), sync_keys)?;
+    client.send_event(event).await?;
+
+    Ok(())
+}
+
+/// Sales published by the other terminals holding `sync_keys`
+pub async fn pull(client: &Client, sync_keys: &Keys) -> Result<Vec<Sale>> {
+    let secret_key = sync_keys.secret_key()?;
+    let own_pubkey = client.keys().public_key();
+
+    let filter = Filter::new()
+        .pubkey(sync_keys.public_key())
+        .kind(Kind::ParameterizedReplaceable(APP_DATA_KIND));
+    let events = client
+        .get_events_of(vec![filter], Some(PULL_TIMEOUT))
+        .await?;
+
+    // Relays may return older versions of a replaced event
+    let mut latest: HashMap<String, (u64, Sale)> = HashMap::new();
+    for event in events {
+        if event.pubkey == own_pubkey || event.verify().is_err() {
+            continue;
+        }
+
+        let mut sale: Sale =
+            match nip04::decrypt(&secret_key, &sync_keys.public_key(), &event.content)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+            {
+                Some(sale) => sale,
+                None => continue,
+            };
+        sale.terminal = Some(event.pubkey.to_string());
+
+        let created_at = event.created_at.as_u64();
+        if latest
+            .get(&sale.hash)
+            .map_or(true, |(seen_at, _)| *seen_at < created_at)
+        {
+            latest.insert(sale.hash.clone(), (created_at, sale));
+        }
+    }
+
+    Ok(latest.into_values().map(|(_, sale)| sale).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sale::Refund;
+
+    #[test]
+    fn refund_tokens_not_published() {
+        let sale = Sale {
+            hash: "hash".to_string(),
+            amount: Amount::from_sat(1000),
+            fiat_value: "0.50".to_string(),
+            reference: Some("Table 2".to_string()),
+            created_at: 1_700_000_000,
+            paid: true,
+            legs: vec![],
+            retained: Amount::from_sat(1000),
+            tip: Amount::ZERO,
+            refunds: vec![Refund {
+                amount: Amount::from_sat(400),
+                created_at: 1_700_000_100,
+                token: "cashuAsecret".to_string(),
+            }],
+            cashier: Some("Alice".to_string()),
+            terminal: None,
+        };
+
+        let content = content(&sale).unwrap();
+        assert!(!content.contains("token"));
+        assert!(!content.contains("cashuAsecret"));
+
+        // Read back as the same sale without the token
+        let mut expected = sale.clone();
+        expected.refunds[0].token.clear();
+        assert_eq!(serde_json::from_str::<Sale>(&content).unwrap(), expected);
+    }
+}
@@ -0,0 +1,362 @@
+//! Deterministic secrets (NUT-13) and restore (NUT-09)
+//!
+//! Outputs for minted, swapped and melt change proofs are derived from the
+//! terminal mnemonic so any proofs lost from browser storage can be recovered
+//! from the mint. Proofs handed to someone else are recorded so a restore
+//! leaves them to their owner.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
+use bitcoin::bip32::{ChildNumber, ExtendedPrivKey};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::Network;
+use cashu_crab::{
+    dhke::construct_proofs,
+    nuts::{
+        nut00::{wallet::BlindedMessages, BlindedMessage, Proofs},
+        nut01::{PublicKey, SecretKey},
+    },
+    Amount,
+};
+use gloo::storage::{LocalStorage, Storage};
+
+use crate::dhke;
+use crate::mint_api::{self, BlindedOutput};
+use crate::storage::{COUNTERS_KEY, HANDED_OUT_KEY};
+use crate::wallet::Wallet;
+
+/// Purpose used for cashu derivation paths
+const CASHU_PURPOSE: u32 = 129372;
+
+/// Number of outputs sent to the mint per restore request
+const RESTORE_BATCH: u32 = 100;
+
+/// Empty batches in a row before restore stops looking
+const RESTORE_EMPTY_BATCHES: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct Seed {
+    mnemonic: Mnemonic,
+    xpriv: ExtendedPrivKey,
+}
+
+impl Seed {
+    pub fn new(mnemonic: Mnemonic) -> Result<Self> {
+        let xpriv = ExtendedPrivKey::new_master(Network::Bitcoin, &mnemonic.to_seed(""))?;
+
+        Ok(Self { mnemonic, xpriv })
+    }
+
+    /// Creates a seed from a new 12 word mnemonic
+    pub fn generate() -> Result<Self> {
+        let mut entropy = [0u8; 16];
+        getrandom::getrandom(&mut entropy)?;
+
+        Self::new(Mnemonic::from_entropy(&entropy)?)
+    }
+
+    pub fn mnemonic(&self) -> &Mnemonic {
+        &self.mnemonic
+    }
+
+    /// BIP-32 fingerprint telling seeds apart without revealing them
+    pub fn fingerprint(&self) -> String {
+        self.xpriv.fingerprint(&Secp256k1::new()).to_string()
+    }
+
+    fn counter_key(&self, keyset_id: &str) -> String {
+        format!("{}/{}", self.fingerprint(), keyset_id)
+    }
+
+    /// Next unused counter of a keyset
+    ///
+    /// Counters stored before they were kept per seed may belong to any seed,
+    /// so they are never gone below.
+    fn next_counter(&self, counters: &HashMap<String, u32>, keyset_id: &str) -> u32 {
+        let counter = counters.get(&self.counter_key(keyset_id)).copied();
+        let legacy = counters.get(keyset_id).copied();
+
+        counter.max(legacy).unwrap_or_default()
+    }
+
+    /// Reserves `count` counters of a keyset and returns the first
+    fn reserve_counters(&self, keyset_id: &str, count: u32) -> u32 {
+        let mut counters = counters();
+        let first = self.next_counter(&counters, keyset_id);
+        counters.insert(self.counter_key(keyset_id), first + count);
+        LocalStorage::set(COUNTERS_KEY, counters).ok();
+
+        first
+    }
+
+    /// Moves the counter of a keyset past `next` if it is not already
+    fn advance_counter(&self, keyset_id: &str, next: u32) {
+        let mut counters = counters();
+        let counter = self.next_counter(&counters, keyset_id).max(next);
+        counters.insert(self.counter_key(keyset_id), counter);
+        LocalStorage::set(COUNTERS_KEY, counters).ok();
+    }
+
+    /// Secret and blinding factor for output `counter` of a keyset
+    fn derive(&self, keyset_id: &str, counter: u32) -> Result<(String, k256::SecretKey)> {
+        let secp = Secp256k1::new();
+        let path = [
+            ChildNumber::from_hardened_idx(CASHU_PURPOSE)?,
+            ChildNumber::from_hardened_idx(0)?,
+            ChildNumber::from_hardened_idx(keyset_id_int(keyset_id)?)?,
+            ChildNumber::from_hardened_idx(counter)?,
+        ];
+        let output_key = self.xpriv.derive_priv(&secp, &path)?;
+
+        let secret = output_key.derive_priv(&secp, &[ChildNumber::from_normal_idx(0)?])?;
+        let r = output_key.derive_priv(&secp, &[ChildNumber::from_normal_idx(1)?])?;
+
+        let r = k256::SecretKey::from_slice(&r.private_key.secret_bytes())?;
+
+        Ok((hex::encode(secret.private_key.secret_bytes()), r))
+    }
+
+    fn blind(&self, keyset_id: &str, counter: u32) -> Result<(String, PublicKey, SecretKey)> {
+        let (secret, r) = self.derive(keyset_id, counter)?;
+        let b = dhke::blind(&secret, &r)?;
+
+        Ok((secret, b.into(), r.into()))
+    }
+
+    /// Deterministic outputs for minting `amount`, and the counter they start at
+    ///
+    /// The counters are reserved before returning so outputs are never reused
+    /// even if the mint request is never made.
+    pub fn premint(&self, keyset_id: &str, amount: Amount) -> Result<(u32, BlindedMessages)> {
+        let counter = self.reserve_counters(keyset_id, amount.split().len() as u32);
+
+        Ok((counter, self.premint_at(keyset_id, counter, amount)?))
+    }
+
+    /// The outputs `premint` returned for `amount` at `counter`
+    pub fn premint_at(
+        &self,
+        keyset_id: &str,
+        counter: u32,
+        amount: Amount,
+    ) -> Result<BlindedMessages> {
+        self.outputs_at(keyset_id, counter, amount.split())
+    }
+
+    /// Deterministic outputs for `amounts` in order, with their counters reserved
+    pub fn outputs(&self, keyset_id: &str, amounts: Vec<Amount>) -> Result<BlindedMessages> {
+        let counter = self.reserve_counters(keyset_id, amounts.len() as u32);
+
+        self.outputs_at(keyset_id, counter, amounts)
+    }
+
+    fn outputs_at(
+        &self,
+        keyset_id: &str,
+        counter: u32,
+        amounts: Vec<Amount>,
+    ) -> Result<BlindedMessages> {
+        let mut blinded_messages = BlindedMessages::default();
+        for (i, amount) in amounts.into_iter().enumerate() {
+            let (secret, b, r) = self.blind(keyset_id, counter + i as u32)?;
+
+            blinded_messages
+                .blinded_messages
+                .push(BlindedMessage { amount, b });
+            blinded_messages.secrets.push(secret);
+            blinded_messages.rs.push(r);
+            blinded_messages.amounts.push(amount);
+        }
+
+        Ok(blinded_messages)
+    }
+
+    /// Recovers unspent proofs the mint signed for outputs derived from this seed
+    ///
+    /// Outputs with counters in `exclude` or recorded by [`hand_out`] were
+    /// handed out and are left alone.
+    pub async fn restore(&self, wallet: &Wallet, exclude: &[Range<u32>]) -> Result<Proofs> {
+        let keyset_id = &wallet.keyset.id;
+        let handed_out = handed_out();
+
+        let mut proofs = vec![];
+        let mut start = 0;
+        let mut empty_batches = 0;
+        let mut next_counter = None;
+
+        while empty_batches < RESTORE_EMPTY_BATCHES {
+            let mut outputs = vec![];
+            let mut derived = vec![];
+            for counter in start..start + RESTORE_BATCH {
+                let (secret, b, r) = self.blind(keyset_id, counter)?;
+                outputs.push(BlindedOutput {
+                    // The mint ignores the amount when restoring
+                    amount: Amount::from_sat(1),
+                    id: keyset_id.clone(),
+                    b: b.clone(),
+                });
+                derived.push((b, counter, secret, r));
+            }
+
+            let (restored, signatures) = mint_api::restore(&wallet.mint_url, outputs).await?;
+
+            if signatures.is_empty() {
+                empty_batches += 1;
+            } else {
+                empty_batches = 0;
+
+                let mut kept = vec![];
+                let mut secrets = vec![];
+                let mut rs = vec![];
+                for (output, signature) in restored.iter().zip(signatures) {
+                    let (_, counter, secret, r) = derived
+                        .iter()
+                        .find(|(b, ..)| b == output)
+                        .ok_or(anyhow!("Mint restored an unknown output"))?;
+                    next_counter = next_counter.max(Some(counter + 1));
+
+                    if exclude.iter().any(|range| range.contains(counter))
+                        || handed_out.contains(&dhke::y(secret)?)
+                    {
+                        continue;
+                    }
+                    kept.push(signature);
+                    secrets.push(secret.clone());
+                    rs.push(r.clone());
+                }
+
+                proofs.extend(construct_proofs(kept, rs, secrets, &wallet.keyset.keys)?);
+            }
+
+            start += RESTORE_BATCH;
+        }
+
+        // Never hand out outputs the mint has already signed
+        if let Some(next_counter) = next_counter {
+            self.advance_counter(keyset_id, next_counter);
+        }
+
+        if proofs.is_empty() {
+            return Ok(proofs);
+        }
+
+        Ok(wallet.check_spent(&proofs).await?.spendable)
+    }
+}
+
+impl FromStr for Seed {
+    type Err = anyhow::Error;
+
+    fn from_str(mnemonic: &str) -> Result<Self> {
+        Self::new(Mnemonic::parse(mnemonic.trim())?)
+    }
+}
+
+fn keyset_id_int(keyset_id: &str) -> Result<u32> {
+    let bytes = hex::decode(keyset_id)?;
+    let int = bytes
+        .iter()
+        .fold(0u64, |acc, b| (acc * 256 + *b as u64) % (2u64.pow(31) - 1));
+
+    Ok(int as u32)
+}
+
+/// Next unused counter per seed fingerprint and keyset
+fn counters() -> HashMap<String, u32> {
+    LocalStorage::get(COUNTERS_KEY).unwrap_or_default()
+}
+
+/// `Y`s of the proofs handed out
+fn handed_out() -> HashSet<String> {
+    LocalStorage::get(HANDED_OUT_KEY).unwrap_or_default()
+}
+
+/// Records proofs given to a receiver or customer so restore never takes
+/// them back while they are unspent
+///
+/// Only the `Y` of each proof is kept, which can't be spent.
+pub fn hand_out(proofs: &Proofs) -> Result<()> {
+    let mut handed_out = handed_out();
+    for proof in proofs {
+        handed_out.insert(dhke::y(&proof.secret)?);
+    }
+    LocalStorage::set(HANDED_OUT_KEY, handed_out)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str =
+        "half depart obvious quality work element tank gorilla view sugar picture humble";
+    const KEYSET_ID: &str = "009a1f293253e41e";
+
+    #[test]
+    fn keyset_id_int_matches_nut13() {
+        assert_eq!(keyset_id_int(KEYSET_ID).unwrap(), 864559728);
+    }
+
+    #[test]
+    fn derive_matches_nut13() {
+        let seed: Seed = MNEMONIC.parse().unwrap();
+        let (secret, r) = seed.derive(KEYSET_ID, 0).unwrap();
+
+        assert_eq!(
+            secret,
+            "485875df74771877439ac06339e284c3acfcd9be7abf3bc20b516faeadfe77ae"
+        );
+        assert_eq!(
+            hex::encode(r.to_bytes()),
+            "ad00d431add9c673e843d4c2bf9a778a5f402b985b8da2d5550bf39cda41d679"
+        );
+    }
+
+    #[test]
+    fn swap_outputs_derived_in_order() {
+        let seed: Seed = MNEMONIC.parse().unwrap();
+        let amounts = vec![
+            Amount::from_sat(8),
+            Amount::from_sat(2),
+            Amount::from_sat(1),
+        ];
+
+        let outputs = seed.outputs_at(KEYSET_ID, 5, amounts.clone()).unwrap();
+
+        assert_eq!(outputs.amounts, amounts);
+        for (i, secret) in outputs.secrets.iter().enumerate() {
+            assert_eq!(secret, &seed.derive(KEYSET_ID, 5 + i as u32).unwrap().0);
+        }
+    }
+
+    #[test]
+    fn counters_are_kept_per_seed() {
+        let seed: Seed = MNEMONIC.parse().unwrap();
+        let other = Seed::generate().unwrap();
+
+        let mut counters = HashMap::new();
+        counters.insert(seed.counter_key(KEYSET_ID), 7);
+
+        assert_eq!(seed.next_counter(&counters, KEYSET_ID), 7);
+        assert_eq!(other.next_counter(&counters, KEYSET_ID), 0);
+        assert_eq!(seed.next_counter(&counters, "00ffd48b8f5ecf80"), 0);
+    }
+
+    #[test]
+    fn legacy_counters_apply_to_every_seed() {
+        let seed: Seed = MNEMONIC.parse().unwrap();
+        let other = Seed::generate().unwrap();
+
+        let mut counters = HashMap::new();
+        counters.insert(KEYSET_ID.to_string(), 12);
+        counters.insert(seed.counter_key(KEYSET_ID), 20);
+
+        assert_eq!(seed.next_counter(&counters, KEYSET_ID), 20);
+        assert_eq!(other.next_counter(&counters, KEYSET_ID), 12);
+    }
+}
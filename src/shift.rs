@@ -0,0 +1,108 @@
+//! Cashier shifts and end of shift Z-reports
+
+use cashu_crab::Amount;
+use serde::{Deserialize, Serialize};
+
+use crate::sale::{self, Sale};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shift {
+    pub cashier: String,
+    pub opened_at: u64,
+    pub closed_at: Option<u64>,
+}
+
+impl Shift {
+    pub fn open(cashier: String) -> Self {
+        Self {
+            cashier,
+            opened_at: sale::unix_time(),
+            closed_at: None,
+        }
+    }
+
+    /// Whether `time` falls within the shift
+    pub fn includes(&self, time: u64) -> bool {
+        time >= self.opened_at && self.closed_at.map_or(true, |closed| time <= closed)
+    }
+
+    /// Whether the sale was made during the shift
+    pub fn contains(&self, sale: &Sale) -> bool {
+        self.includes(sale.created_at)
+    }
+}
+
+/// Totals of the paid sales of a shift
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZReport {
+    pub shift: Shift,
+    pub sales: usize,
+    pub amount: Amount,
+    /// Sum of the fiat values at the rate of each sale
+    pub fiat_value: f64,
+    /// Paid over the sale amounts
+    pub tips: Amount,
+    /// Tokens that could not be DMed to their receiver
+    pub undelivered_tokens: usize,
+    pub undelivered_amount: Amount,
+    /// Refunded during the shift, including for sales of earlier shifts
+    #[serde(default)]
+    pub refunds: Amount,
+}
+
+impl ZReport {
+    pub fn new(shift: &Shift, sales: &[Sale]) -> Self {
+        let refunds = sales
+            .iter()
+            .flat_map(|s| s.refunds.iter())
+            .filter(|r| shift.includes(r.created_at))
+            .map(|r| r.amount.to_sat())
+            .sum();
+        let sales: Vec<&Sale> = sales
+            .iter()
+            .filter(|s| s.paid && shift.contains(s))
+            .collect();
+        let undelivered: Vec<_> = sales
+            .iter()
+            .flat_map(|s| s.legs.iter().filter(|l| !l.delivered))
+            .collect();
+
+        Self {
+            shift: shift.clone(),
+            sales: sales.len(),
+            amount: Amount::from_sat(sales.iter().map(|s| s.amount.to_sat()).sum()),
+            fiat_value: sales
+                .iter()
+                .map(|s| s.fiat_value.parse::<f64>().unwrap_or_default())
+                .sum(),
+            tips: Amount::from_sat(sales.iter().map(|s| s.tip.to_sat()).sum()),
+            undelivered_tokens: undelivered.len(),
+            undelivered_amount: Amount::from_sat(
+                undelivered.iter().map(|l| l.amount.to_sat()).sum(),
+            ),
+            refunds: Amount::from_sat(refunds),
+        }
+    }
+
+    /// Plain text report for the screen and DMs
+    pub fn to_text(&self) -> String {
+        let closed = match self.shift.closed_at {
+            Some(closed_at) => sale::format_time(closed_at),
+            None => "open".to_string(),
+        };
+
+        format!(
+            "Z-report {}\nOpened {}\nClosed {}\nSales {}\nTotal {} sats\nFiat ${:.2}\nTips {} sats\nUndelivered tokens {} ({} sats)\nRefunds {} sats",
+            self.shift.cashier,
+            sale::format_time(self.shift.opened_at),
+            closed,
+            self.sales,
+            self.amount.to_sat(),
+            self.fiat_value,
+            self.tips.to_sat(),
+            self.undelivered_tokens,
+            self.undelivered_amount.to_sat(),
+            self.refunds.to_sat()
+        )
+    }
+}
@@ -0,0 +1,179 @@
+//! Revenue split between receivers
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use cashu_crab::Amount;
+use nostr_sdk::prelude::XOnlyPublicKey;
+use serde::{Deserialize, Serialize};
+
+/// Portion of a sale that goes to a split recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Share {
+    /// Percent of the sale amount
+    Percent(u8),
+    /// Fixed number of sats
+    Fixed(u64),
+}
+
+impl FromStr for Share {
+    type Err = anyhow::Error;
+
+    /// Parses `10%` as a percentage and `500` or `500 sats` as a fixed sat
+    /// amount, so shown shares parse back
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent: u8 = percent.trim().parse()?;
+            if percent > 100 {
+                bail!("Share cannot be more than 100%");
+            }
+            return Ok(Share::Percent(percent));
+        }
+
+        let sats = s
+            .strip_suffix("sats")
+            .or_else(|| s.strip_suffix("sat"))
+            .unwrap_or(s);
+
+        Ok(Share::Fixed(sats.trim().parse()?))
+    }
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Share::Percent(percent) => write!(f, "{}%", percent),
+            Share::Fixed(sats) => write!(f, "{} sats", sats),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitRecipient {
+    pub pubkey: XOnlyPublicKey,
+    pub share: Share,
+}
+
+/// Amount owed to each split recipient for a sale of `total`
+///
+/// Recipients are paid in the order they were configured and each share is
+/// capped at what is left of the sale. Whatever remains after all splits
+/// belongs to the main receiver and is not included. Shares are of the sale
+/// amount only, so tips always go to the main receiver.
+pub fn split_amount(total: Amount, recipients: &[SplitRecipient]) -> Vec<(XOnlyPublicKey, Amount)> {
+    let total = total.to_sat();
+    let mut remaining = total;
+
+    let mut legs = vec![];
+    for recipient in recipients {
+        let owed = match recipient.share {
+            Share::Percent(percent) => total * percent as u64 / 100,
+            Share::Fixed(sats) => sats,
+        }
+        .min(remaining);
+
+        if owed == 0 {
+            continue;
+        }
+
+        remaining -= owed;
+        legs.push((recipient.pubkey, Amount::from_sat(owed)));
+    }
+
+    legs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipient(byte: u8, share: Share) -> SplitRecipient {
+        SplitRecipient {
+            pubkey: XOnlyPublicKey::from_str(&hex::encode([byte; 32])).unwrap(),
+            share,
+        }
+    }
+
+    #[test]
+    fn share_from_str() {
+        assert_eq!(Share::from_str("10%").unwrap(), Share::Percent(10));
+        assert_eq!(Share::from_str(" 25 % ").unwrap(), Share::Percent(25));
+        assert_eq!(Share::from_str("100%").unwrap(), Share::Percent(100));
+        assert_eq!(Share::from_str("500").unwrap(), Share::Fixed(500));
+        assert!(Share::from_str("101%").is_err());
+        assert!(Share::from_str("-5").is_err());
+        assert!(Share::from_str("ten").is_err());
+        assert!(Share::from_str("").is_err());
+        assert!(Share::from_str("sats").is_err());
+    }
+
+    #[test]
+    fn share_display_round_trips() {
+        for share in [
+            Share::Percent(0),
+            Share::Percent(15),
+            Share::Fixed(1),
+            Share::Fixed(500),
+        ] {
+            assert_eq!(Share::from_str(&share.to_string()).unwrap(), share);
+        }
+        assert_eq!(Share::Fixed(500).to_string(), "500 sats");
+        assert_eq!(Share::from_str("1 sat").unwrap(), Share::Fixed(1));
+    }
+
+    #[test]
+    fn percent_shares_round_down() {
+        let a = recipient(1, Share::Percent(10));
+        let b = recipient(2, Share::Percent(33));
+
+        let legs = split_amount(Amount::from_sat(999), &[a.clone(), b.clone()]);
+
+        assert_eq!(
+            legs,
+            vec![
+                (a.pubkey, Amount::from_sat(99)),
+                (b.pubkey, Amount::from_sat(329)),
+            ]
+        );
+    }
+
+    #[test]
+    fn remainder_stays_with_receiver() {
+        let a = recipient(1, Share::Percent(50));
+        let b = recipient(2, Share::Fixed(100));
+
+        let legs = split_amount(Amount::from_sat(1001), &[a, b]);
+        let split: u64 = legs.iter().map(|(_, amount)| amount.to_sat()).sum();
+
+        assert_eq!(split, 600);
+    }
+
+    #[test]
+    fn shares_capped_at_what_is_left() {
+        let a = recipient(1, Share::Percent(80));
+        let b = recipient(2, Share::Fixed(500));
+        let c = recipient(3, Share::Fixed(10));
+
+        let legs = split_amount(Amount::from_sat(1000), &[a.clone(), b.clone(), c]);
+
+        // Nothing is left for the last recipient so they get no leg
+        assert_eq!(
+            legs,
+            vec![
+                (a.pubkey, Amount::from_sat(800)),
+                (b.pubkey, Amount::from_sat(200)),
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_shares_are_skipped() {
+        let a = recipient(1, Share::Percent(1));
+        let b = recipient(2, Share::Fixed(0));
+
+        assert!(split_amount(Amount::from_sat(99), &[a, b]).is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+//! What the terminal keeps in browser storage
+//!
+//! Settings and sales are kept in LocalStorage as they are. Proofs, secrets,
+//! where payments go and the PINs are kept in the encrypted store of
+//! [`wallet_store`] and loaded once it's unlocked.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use cashu_crab::nuts::nut00::Proofs;
+use gloo::storage::{LocalStorage, Storage};
+use log::warn;
+use nostr_sdk::prelude::{FromSkStr, Keys};
+use tokio::sync::Mutex as TokioMutex;
+use yew::Callback;
+
+use crate::admin::{PinLockout, PinLockouts};
+use crate::components::invoice::QrPayload;
+use crate::escpos::PrinterSettings;
+use crate::message::MessageSettings;
+use crate::sale::Sale;
+use crate::seed::Seed;
+use crate::shift::{Shift, ZReport};
+use crate::utls;
+use crate::wallet_store::{self, StoredConfig, SweepSchedule};
+
+pub const CONFIG_KEY: &str = "config";
+pub const NOSTR_KEY: &str = "nostr_rec";
+pub const MINT_URL_KEY: &str = "mint_url";
+pub const RELAYS_KEY: &str = "relays";
+pub const SPLITS_KEY: &str = "splits";
+pub const SALES_KEY: &str = "sales";
+pub const SYNCED_SALES_KEY: &str = "synced_sales";
+pub const MELT_KEY: &str = "melt";
+pub const BALANCE_KEY: &str = "balance";
+pub const WALLET_KEY: &str = "wallet";
+pub const SWEEP_KEY: &str = "sweep";
+pub const LAST_SWEEP_KEY: &str = "last_sweep";
+pub const SEED_KEY: &str = "seed";
+pub const SEED_BACKED_UP_KEY: &str = "seed_backed_up";
+pub const COUNTERS_KEY: &str = "counters";
+pub const HANDED_OUT_KEY: &str = "handed_out";
+pub const PENDING_MINTS_KEY: &str = "pending_mints";
+pub const MINTED_KEY: &str = "minted";
+pub const PREVIOUS_SEEDS_KEY: &str = "previous_seeds";
+pub const QR_PAYLOAD_KEY: &str = "qr_payload";
+pub const POS_KEY: &str = "pos_key";
+pub const SHOP_NAME_KEY: &str = "shop_name";
+pub const ONCHAIN_ADDRESS_KEY: &str = "onchain_address";
+pub const MESSAGE_KEY: &str = "message";
+pub const AUTO_RETURN_KEY: &str = "auto_return";
+pub const PRINTER_KEY: &str = "printer";
+pub const SHIFT_KEY: &str = "shift";
+pub const Z_REPORTS_KEY: &str = "z_reports";
+pub const ADMIN_PIN_KEY: &str = "admin_pin";
+pub const PIN_LOCKOUT_KEY: &str = "pin_lockout";
+/// Failed attempts at unlocking the store, plaintext as they're checked before
+/// anything can be decrypted
+pub const STORAGE_LOCKOUT_KEY: &str = "storage_lockout";
+pub const CASHIERS_KEY: &str = "cashiers";
+pub const REMOTE_CONFIG_AT_KEY: &str = "remote_config_at";
+pub const CLAIMED_TOKENS_KEY: &str = "claimed_tokens";
+pub const REFUND_TOKENS_KEY: &str = "refund_tokens";
+pub const REMOTE_CONFIG_KEY: &str = "remote_config";
+pub const TOKEN_STATUS_KEY: &str = "token_status";
+
+/// Settings and sales kept in LocalStorage, read when the app starts
+#[derive(Debug, Default)]
+pub struct LocalSettings {
+    pub sales: Vec<Sale>,
+    pub synced_sales: Vec<Sale>,
+    pub sweep_schedule: Option<SweepSchedule>,
+    pub qr_payload: QrPayload,
+    pub shop_name: Option<String>,
+    pub message_settings: MessageSettings,
+    pub auto_return: Option<u32>,
+    pub printer_settings: Option<PrinterSettings>,
+    pub shift: Option<Shift>,
+    pub z_reports: Vec<ZReport>,
+    pub storage_lockout: PinLockout,
+    pub seed_backed_up: bool,
+    pub remote_config_at: u64,
+}
+
+impl LocalSettings {
+    pub fn load() -> Self {
+        Self {
+            sales: LocalStorage::get(SALES_KEY).unwrap_or_default(),
+            synced_sales: LocalStorage::get(SYNCED_SALES_KEY).unwrap_or_default(),
+            sweep_schedule: LocalStorage::get(SWEEP_KEY).ok(),
+            qr_payload: LocalStorage::get(QR_PAYLOAD_KEY).unwrap_or_default(),
+            shop_name: LocalStorage::get(SHOP_NAME_KEY).ok(),
+            message_settings: LocalStorage::get(MESSAGE_KEY).unwrap_or_default(),
+            auto_return: LocalStorage::get(AUTO_RETURN_KEY).ok(),
+            printer_settings: LocalStorage::get(PRINTER_KEY).ok(),
+            shift: LocalStorage::get(SHIFT_KEY).ok(),
+            z_reports: LocalStorage::get(Z_REPORTS_KEY).unwrap_or_default(),
+            storage_lockout: LocalStorage::get(STORAGE_LOCKOUT_KEY).unwrap_or_default(),
+            seed_backed_up: LocalStorage::get(SEED_BACKED_UP_KEY).unwrap_or_default(),
+            remote_config_at: LocalStorage::get(REMOTE_CONFIG_AT_KEY).unwrap_or_default(),
+        }
+    }
+}
+
+/// Where [`load_storage`] sends what it read from the encrypted store
+pub struct StorageCallbacks {
+    pub seed_cb: Callback<Seed>,
+    pub config_cb: Callback<StoredConfig>,
+    pub pin_lockouts_cb: Callback<PinLockouts>,
+    pub wallet_failed_cb: Callback<String>,
+    pub refund_tokens_cb: Callback<HashMap<String, String>>,
+}
+
+/// Generates and stores the seed shown for backup on first start
+async fn new_seed() -> Result<Seed> {
+    let seed = Seed::generate()?;
+    wallet_store::set_encrypted(SEED_KEY, &seed.mnemonic().to_string()).await?;
+
+    Ok(seed)
+}
+
+/// Stores `seed` in place of the current seed, keeping the current one
+///
+/// Returns the seeds replaced so far.
+pub async fn replace_seed(current: Option<Seed>, seed: &Seed) -> Result<Vec<String>> {
+    let mut previous: Vec<String> = wallet_store::get_encrypted(PREVIOUS_SEEDS_KEY)
+        .await?
+        .unwrap_or_default();
+
+    let mnemonic = seed.mnemonic().to_string();
+    if let Some(current) = current.map(|c| c.mnemonic().to_string()) {
+        if current != mnemonic && !previous.contains(&current) {
+            previous.push(current);
+            wallet_store::set_encrypted(PREVIOUS_SEEDS_KEY, &previous).await?;
+        }
+    }
+
+    wallet_store::set_encrypted(SEED_KEY, &mnemonic).await?;
+
+    Ok(previous)
+}
+
+/// Nostr keys of the terminal, created on first use
+///
+/// Kept across restarts so payment requests stay reachable.
+pub async fn pos_keys() -> Result<Keys> {
+    if let Some(secret_key) = wallet_store::get_encrypted::<String>(POS_KEY).await? {
+        return Ok(Keys::from_sk_str(&secret_key)?);
+    }
+
+    let keys = utls::handle_keys(None)?;
+    wallet_store::set_encrypted(POS_KEY, &keys.secret_key()?.display_secret().to_string()).await?;
+
+    Ok(keys)
+}
+
+/// Loads the proofs, seed and config from the encrypted store
+pub async fn load_storage(balance: Arc<TokioMutex<Proofs>>, callbacks: StorageCallbacks) {
+    let StorageCallbacks {
+        seed_cb,
+        config_cb,
+        pin_lockouts_cb,
+        wallet_failed_cb,
+        refund_tokens_cb,
+    } = callbacks;
+
+    // Locked before any sale can settle so nothing is written over the stored proofs
+    let mut balance = balance.lock().await;
+    if let Err(err) = wallet_store::resume_set_pin().await {
+        warn!("Could not finish changing the storage PIN {:?}", err);
+    }
+
+    match wallet_store::load_proofs().await {
+        Ok(proofs) => *balance = proofs,
+        Err(err) => {
+            warn!("Could not load terminal wallet {:?}", err);
+            wallet_failed_cb.emit(format!("Could not load the terminal wallet: {}", err));
+        }
+    }
+
+    let seed = match wallet_store::get_encrypted::<String>(SEED_KEY).await {
+        Ok(Some(mnemonic)) => mnemonic.parse(),
+        Ok(None) => new_seed().await,
+        Err(err) => Err(err),
+    };
+    match seed {
+        Ok(seed) => seed_cb.emit(seed),
+        Err(err) => warn!("Could not load wallet seed {:?}", err),
+    }
+
+    // Before the config so no PIN can be tried without them
+    match wallet_store::load_pin_lockouts().await {
+        Ok(lockouts) => pin_lockouts_cb.emit(lockouts),
+        Err(err) => warn!("Could not load PIN lockouts {:?}", err),
+    }
+
+    match wallet_store::load_config().await {
+        Ok(config) => config_cb.emit(config),
+        Err(err) => {
+            warn!("Could not load config {:?}", err);
+            config_cb.emit(StoredConfig::default());
+        }
+    }
+
+    match wallet_store::get_encrypted(REFUND_TOKENS_KEY).await {
+        Ok(tokens) => refund_tokens_cb.emit(tokens.unwrap_or_default()),
+        Err(err) => warn!("Could not load refund tokens {:?}", err),
+    }
+}
+
+/// Adds refund tokens to the encrypted store
+pub async fn save_refund_tokens(tokens: HashMap<String, String>) -> Result<()> {
+    let mut stored: HashMap<String, String> = wallet_store::get_encrypted(REFUND_TOKENS_KEY)
+        .await?
+        .unwrap_or_default();
+    stored.extend(tokens);
+
+    wallet_store::set_encrypted(REFUND_TOKENS_KEY, &stored).await
+}
@@ -0,0 +1,222 @@
+//! Wallet operations against the v1 api of the mint
+//!
+//! Replaces the cashu-crab wallet, which still speaks the legacy api.
+
+use anyhow::{anyhow, bail, Result};
+use cashu_crab::{
+    dhke::construct_proofs,
+    nuts::nut00::{
+        wallet::{BlindedMessages, Token},
+        Proofs,
+    },
+    types::{Melted, ProofsStatus, SendProofs},
+    Amount, Invoice,
+};
+use url::Url;
+
+use crate::dhke;
+use crate::melt;
+use crate::mint_api::{self, BlindedOutput, Keyset, MeltQuote, MeltState, ProofState};
+use crate::seed::Seed;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wallet {
+    pub mint_url: Url,
+    pub keyset: Keyset,
+}
+
+/// Number of blank outputs able to return any change up to `max_change`
+fn blank_outputs_count(max_change: Amount) -> usize {
+    let max_change = max_change.to_sat();
+    if max_change <= 1 {
+        return 1;
+    }
+
+    (u64::BITS - (max_change - 1).leading_zeros()) as usize
+}
+
+/// Blank outputs for melting `inputs` to pay `quote`
+///
+/// Inputs above the quote amount and fee reserve come back as change too.
+fn melt_outputs_count(inputs: Amount, quote: &MeltQuote) -> usize {
+    let max_change = inputs
+        .to_sat()
+        .saturating_sub(quote.amount.to_sat())
+        .max(quote.fee_reserve.to_sat());
+
+    blank_outputs_count(Amount::from_sat(max_change))
+}
+
+impl Wallet {
+    /// Wallet for the active keyset of the mint
+    pub async fn new(mint_url: &Url) -> Result<Self> {
+        Ok(Self {
+            mint_url: mint_url.clone(),
+            keyset: mint_api::get_keyset(mint_url).await?,
+        })
+    }
+
+    /// Outputs for `amounts`, derived from `seed` so they can be restored
+    fn outputs(&self, seed: Option<&Seed>, amounts: Vec<Amount>) -> Result<BlindedMessages> {
+        match seed {
+            Some(seed) => seed.outputs(&self.keyset.id, amounts),
+            None => dhke::random_outputs(amounts),
+        }
+    }
+
+    /// Swaps `proofs` for new proofs of `amounts`, in the same order
+    async fn swap(
+        &self,
+        seed: Option<&Seed>,
+        proofs: &Proofs,
+        amounts: Vec<Amount>,
+    ) -> Result<Proofs> {
+        let premint = self.outputs(seed, amounts)?;
+        let outputs = BlindedOutput::from_premint(&premint, &self.keyset.id);
+
+        let signatures = mint_api::swap(&self.mint_url, proofs, outputs).await?;
+
+        Ok(construct_proofs(
+            signatures,
+            premint.rs,
+            premint.secrets,
+            &self.keyset.keys,
+        )?)
+    }
+
+    /// Swaps `proofs` into proofs for exactly `amount` and the change
+    pub async fn send(
+        &self,
+        seed: Option<&Seed>,
+        amount: Amount,
+        proofs: Proofs,
+    ) -> Result<SendProofs> {
+        let total = melt::proofs_amount(&proofs).to_sat();
+        if total < amount.to_sat() {
+            bail!(
+                "Proofs of {} sats can't cover {} sats",
+                total,
+                amount.to_sat()
+            );
+        }
+
+        let send_amounts = amount.split();
+        let send_count = send_amounts.len();
+        let mut amounts = send_amounts;
+        amounts.extend(Amount::from_sat(total - amount.to_sat()).split());
+
+        let mut change_proofs = self.swap(seed, &proofs, amounts).await?;
+        let send_proofs = change_proofs.drain(..send_count).collect();
+
+        Ok(SendProofs {
+            change_proofs,
+            send_proofs,
+        })
+    }
+
+    /// Swaps received proofs so the sender can no longer spend them
+    pub async fn receive(&self, seed: Option<&Seed>, proofs: &Proofs) -> Result<Proofs> {
+        let amount = melt::proofs_amount(proofs);
+
+        self.swap(seed, proofs, amount.split()).await
+    }
+
+    /// Splits `proofs` into the unspent and the spent ones
+    ///
+    /// Pending proofs are locked by a payment in flight so they count as spent.
+    pub async fn check_spent(&self, proofs: &Proofs) -> Result<ProofsStatus> {
+        let ys = proofs
+            .iter()
+            .map(|p| dhke::y(&p.secret))
+            .collect::<Result<Vec<_>>>()?;
+        let states = mint_api::check_state(&self.mint_url, ys).await?;
+
+        let mut status = ProofsStatus::default();
+        for (proof, state) in proofs.iter().zip(states) {
+            match state {
+                ProofState::Unspent => status.spendable.push(proof.clone()),
+                ProofState::Pending | ProofState::Spent => status.spent.push(proof.clone()),
+            }
+        }
+
+        Ok(status)
+    }
+
+    pub async fn melt_quote(&self, invoice: &Invoice) -> Result<MeltQuote> {
+        mint_api::melt_quote(&self.mint_url, invoice).await
+    }
+
+    /// Pays a melt quote, returning the change of the fee reserve
+    pub async fn melt(
+        &self,
+        seed: Option<&Seed>,
+        quote: &MeltQuote,
+        proofs: Proofs,
+    ) -> Result<Melted> {
+        let blank = self.outputs(
+            seed,
+            vec![Amount::from_sat(1); melt_outputs_count(melt::proofs_amount(&proofs), quote)],
+        )?;
+        let outputs = BlindedOutput::from_premint(&blank, &self.keyset.id);
+
+        let result = mint_api::melt(&self.mint_url, &quote.id, &proofs, outputs).await?;
+        if result.state == MeltState::Pending {
+            bail!("Mint is still paying the invoice");
+        }
+
+        // The mint signs as many blank outputs as it needs, in order
+        let signed = result.change.len();
+        let change = match signed {
+            0 => None,
+            _ => Some(construct_proofs(
+                result.change,
+                blank.rs.into_iter().take(signed).collect(),
+                blank.secrets.into_iter().take(signed).collect(),
+                &self.keyset.keys,
+            )?),
+        };
+
+        Ok(Melted {
+            paid: result.state == MeltState::Paid,
+            preimage: result.preimage,
+            change,
+        })
+    }
+
+    pub fn proofs_to_token(&self, proofs: Proofs, memo: Option<String>) -> Result<String> {
+        Token::new(self.mint_url.clone(), proofs, memo)
+            .convert_to_string()
+            .map_err(|err| anyhow!("Could not encode token {:?}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_outputs_cover_fee_reserve() {
+        assert_eq!(blank_outputs_count(Amount::from_sat(0)), 1);
+        assert_eq!(blank_outputs_count(Amount::from_sat(1)), 1);
+        assert_eq!(blank_outputs_count(Amount::from_sat(2)), 1);
+        assert_eq!(blank_outputs_count(Amount::from_sat(3)), 2);
+        assert_eq!(blank_outputs_count(Amount::from_sat(1000)), 10);
+        assert_eq!(blank_outputs_count(Amount::from_sat(1024)), 10);
+        assert_eq!(blank_outputs_count(Amount::from_sat(1025)), 11);
+    }
+
+    #[test]
+    fn blank_outputs_cover_overpaid_inputs() {
+        let quote = MeltQuote {
+            id: "quote".to_string(),
+            amount: Amount::from_sat(9_900),
+            fee_reserve: Amount::from_sat(2),
+        };
+
+        // First fee guess of 1% sent 98 sats more than the mint reserves
+        assert_eq!(melt_outputs_count(Amount::from_sat(10_000), &quote), 7);
+        assert_eq!(melt_outputs_count(Amount::from_sat(9_902), &quote), 1);
+        // Short inputs are refused by the mint, still cover the reserve
+        assert_eq!(melt_outputs_count(Amount::from_sat(9_000), &quote), 1);
+    }
+}
@@ -0,0 +1,309 @@
+//! Encrypted store for proofs, secrets and configuration kept on the terminal
+//!
+//! Values are encrypted with an AES-GCM key kept in IndexedDB. Once an admin
+//! PIN is set that key is wrapped by a key derived from the PIN, and nothing
+//! can be read after a restart until the PIN is entered.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, bail, Result};
+use cashu_crab::nuts::nut00::Proofs;
+use gloo::storage::{LocalStorage, Storage};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::admin::{Cashier, PinHash, PinLockout, PinLockouts};
+use crate::bindings;
+use crate::melt::MeltSettings;
+use crate::split::SplitRecipient;
+use crate::storage::{
+    ADMIN_PIN_KEY, BALANCE_KEY, CASHIERS_KEY, CLAIMED_TOKENS_KEY, CONFIG_KEY, MELT_KEY,
+    MINT_URL_KEY, NOSTR_KEY, ONCHAIN_ADDRESS_KEY, PIN_LOCKOUT_KEY, POS_KEY, PREVIOUS_SEEDS_KEY,
+    REFUND_TOKENS_KEY, RELAYS_KEY, REMOTE_CONFIG_KEY, SEED_KEY, SPLITS_KEY, TOKEN_STATUS_KEY,
+    WALLET_KEY,
+};
+
+/// When the terminal balance is swept to the receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SweepSchedule {
+    Hourly,
+    /// Once a day at the given hour (local time) the shift ends
+    EndOfShift(u8),
+    /// Only when swept from the wallet view
+    OnDemand,
+}
+
+impl SweepSchedule {
+    /// Whether a sweep is due at `now` given the time of the last sweep
+    ///
+    /// `hour` is the current local hour and only used for end of shift sweeps.
+    pub fn is_due(&self, last_sweep: u64, now: u64, hour: u8) -> bool {
+        match self {
+            SweepSchedule::Hourly => now.saturating_sub(last_sweep) >= 60 * 60,
+            // Sweep once in the hour after closing
+            SweepSchedule::EndOfShift(end) => {
+                hour == *end && now.saturating_sub(last_sweep) >= 60 * 60
+            }
+            SweepSchedule::OnDemand => false,
+        }
+    }
+}
+
+/// Entries written with [`set_encrypted`]
+const ENCRYPTED_KEYS: [&str; 10] = [
+    CONFIG_KEY,
+    WALLET_KEY,
+    SEED_KEY,
+    PREVIOUS_SEEDS_KEY,
+    POS_KEY,
+    CLAIMED_TOKENS_KEY,
+    REFUND_TOKENS_KEY,
+    REMOTE_CONFIG_KEY,
+    TOKEN_STATUS_KEY,
+    PIN_LOCKOUT_KEY,
+];
+
+/// Where payments go and who may change it, kept encrypted so neither can be
+/// replaced by editing storage
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredConfig {
+    pub mint_url: Option<Url>,
+    /// Hex pubkey of the receiver
+    pub nostr_rec: Option<String>,
+    pub relays: HashSet<Url>,
+    /// Hex pubkey of the merchant admin pushing remote config
+    #[serde(default)]
+    pub merchant: Option<String>,
+    /// Secret key shared by the terminals syncing sales
+    #[serde(default)]
+    pub sync_key: Option<String>,
+    /// Hex pubkeys trusted to sign config bundles besides the merchant
+    #[serde(default)]
+    pub trusted_signers: Vec<String>,
+    #[serde(default)]
+    pub splits: Vec<SplitRecipient>,
+    #[serde(default)]
+    pub melt_settings: Option<MeltSettings>,
+    /// Address for the BIP21 payload
+    #[serde(default)]
+    pub onchain_address: Option<String>,
+    #[serde(default)]
+    pub admin_pin: Option<PinHash>,
+    #[serde(default)]
+    pub cashiers: Vec<Cashier>,
+}
+
+/// Reads and decrypts a value written with [`set_encrypted`]
+pub async fn get_encrypted<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    let encrypted = match LocalStorage::get::<String>(key) {
+        Ok(encrypted) => encrypted,
+        Err(_) => return Ok(None),
+    };
+
+    let decrypted = bindings::decrypt_local(encrypted)
+        .await
+        .as_string()
+        .ok_or(anyhow!("Could not decrypt {}", key))?;
+
+    Ok(Some(serde_json::from_str(&decrypted)?))
+}
+
+pub async fn set_encrypted<T: Serialize>(key: &str, value: &T) -> Result<()> {
+    let encrypted = bindings::encrypt_local(serde_json::to_string(value)?)
+        .await
+        .as_string()
+        .ok_or(anyhow!("Could not encrypt {}", key))?;
+
+    LocalStorage::set(key, encrypted)?;
+
+    Ok(())
+}
+
+/// Set once the stored proofs have been read
+///
+/// Until then the proofs in memory may be missing the stored ones, so writing
+/// them would lose whatever could not be read.
+static PROOFS_LOADED: AtomicBool = AtomicBool::new(false);
+
+/// Reads the terminal proofs, moving any left unencrypted by older versions
+/// into the encrypted store
+pub async fn load_proofs() -> Result<Proofs> {
+    let mut proofs: Proofs = get_encrypted(WALLET_KEY).await?.unwrap_or_default();
+    PROOFS_LOADED.store(true, Ordering::SeqCst);
+
+    if let Ok(plaintext) = LocalStorage::get::<Proofs>(BALANCE_KEY) {
+        proofs.extend(plaintext);
+        save_proofs(&proofs).await?;
+        LocalStorage::delete(BALANCE_KEY);
+    }
+
+    Ok(proofs)
+}
+
+/// Whether proofs can be kept on the terminal
+pub fn proofs_loaded() -> bool {
+    PROOFS_LOADED.load(Ordering::SeqCst)
+}
+
+pub async fn save_proofs(proofs: &Proofs) -> Result<()> {
+    if !proofs_loaded() {
+        bail!("Stored proofs could not be loaded, not overwriting them");
+    }
+
+    set_encrypted(WALLET_KEY, proofs).await
+}
+
+/// Whether the PIN has to be entered before anything can be decrypted
+pub async fn is_locked() -> bool {
+    bindings::storage_locked()
+        .await
+        .as_bool()
+        .unwrap_or_default()
+}
+
+/// Unwraps the storage key with the admin PIN, false if the PIN is wrong
+pub async fn unlock(pin: &str) -> bool {
+    bindings::unlock_storage(pin.to_string())
+        .await
+        .as_bool()
+        .unwrap_or_default()
+}
+
+/// Re-encrypts every entry under a new key wrapped by `pin`, or a device key
+/// if `None`
+///
+/// The old key is only deleted once every entry has been rewritten, until
+/// then the new one is kept sealed with it so [`resume_set_pin`] can finish
+/// an interrupted change.
+pub async fn set_pin(pin: Option<&str>) -> Result<()> {
+    if !bindings::stage_storage_pin(pin.map(|p| p.to_string()))
+        .await
+        .as_bool()
+        .unwrap_or_default()
+    {
+        bail!("Could not create storage key");
+    }
+
+    rekey().await
+}
+
+/// Finishes a PIN change interrupted before every entry was rewritten
+pub async fn resume_set_pin() -> Result<()> {
+    if bindings::storage_key_staged()
+        .await
+        .as_bool()
+        .unwrap_or_default()
+    {
+        rekey().await?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites every entry with the staged key, then replaces the old key
+async fn rekey() -> Result<()> {
+    for key in ENCRYPTED_KEYS {
+        // Written with the staged key once there is one
+        if let Some(value) = get_encrypted::<Value>(key).await? {
+            set_encrypted(key, &value).await?;
+        }
+    }
+
+    if !bindings::commit_storage_pin()
+        .await
+        .as_bool()
+        .unwrap_or_default()
+    {
+        bail!("Could not replace storage key");
+    }
+
+    Ok(())
+}
+
+/// Reads the config, moving the plaintext entries of older versions into
+/// the encrypted store
+pub async fn load_config() -> Result<StoredConfig> {
+    let mut config: StoredConfig = get_encrypted(CONFIG_KEY).await?.unwrap_or_default();
+    let mut migrated = false;
+
+    if let Ok(mint_url) = LocalStorage::get::<Url>(MINT_URL_KEY) {
+        config.mint_url.get_or_insert(mint_url);
+        migrated = true;
+    }
+    // Stored as a JSON string inside the LocalStorage string
+    if let Ok(nostr_rec) = LocalStorage::get::<String>(NOSTR_KEY) {
+        if let Ok(nostr_rec) = serde_json::from_str::<String>(&nostr_rec) {
+            config.nostr_rec.get_or_insert(nostr_rec);
+        }
+        migrated = true;
+    }
+    if let Ok(relays) = LocalStorage::get::<HashSet<Url>>(RELAYS_KEY) {
+        config.relays.extend(relays);
+        migrated = true;
+    }
+    if let Ok(splits) = LocalStorage::get::<Vec<SplitRecipient>>(SPLITS_KEY) {
+        if config.splits.is_empty() {
+            config.splits = splits;
+        }
+        migrated = true;
+    }
+    if let Ok(melt_settings) = LocalStorage::get::<MeltSettings>(MELT_KEY) {
+        config.melt_settings.get_or_insert(melt_settings);
+        migrated = true;
+    }
+    if let Ok(onchain_address) = LocalStorage::get::<String>(ONCHAIN_ADDRESS_KEY) {
+        config.onchain_address.get_or_insert(onchain_address);
+        migrated = true;
+    }
+    if let Ok(admin_pin) = LocalStorage::get::<PinHash>(ADMIN_PIN_KEY) {
+        config.admin_pin.get_or_insert(admin_pin);
+        migrated = true;
+    }
+    if let Ok(cashiers) = LocalStorage::get::<Vec<Cashier>>(CASHIERS_KEY) {
+        if config.cashiers.is_empty() {
+            config.cashiers = cashiers;
+        }
+        migrated = true;
+    }
+
+    if migrated {
+        set_encrypted(CONFIG_KEY, &config).await?;
+        for key in [
+            MINT_URL_KEY,
+            NOSTR_KEY,
+            RELAYS_KEY,
+            SPLITS_KEY,
+            MELT_KEY,
+            ONCHAIN_ADDRESS_KEY,
+            ADMIN_PIN_KEY,
+            CASHIERS_KEY,
+        ] {
+            LocalStorage::delete(key);
+        }
+    }
+
+    Ok(config)
+}
+
+/// Reads the failed PIN attempts, moving those left unencrypted by older
+/// versions into the encrypted store
+pub async fn load_pin_lockouts() -> Result<PinLockouts> {
+    // Entries of older versions are plaintext, which can't be decrypted
+    let plaintext = LocalStorage::get::<PinLockouts>(PIN_LOCKOUT_KEY)
+        .or_else(|_| LocalStorage::get::<PinLockout>(PIN_LOCKOUT_KEY).map(PinLockouts::migrate));
+    if let Ok(lockouts) = plaintext {
+        set_encrypted(PIN_LOCKOUT_KEY, &lockouts).await?;
+        return Ok(lockouts);
+    }
+
+    Ok(get_encrypted(PIN_LOCKOUT_KEY).await?.unwrap_or_default())
+}
+
+pub async fn update_config(update: impl FnOnce(&mut StoredConfig)) -> Result<()> {
+    let mut config: StoredConfig = get_encrypted(CONFIG_KEY).await?.unwrap_or_default();
+    update(&mut config);
+
+    set_encrypted(CONFIG_KEY, &config).await
+}